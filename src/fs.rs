@@ -359,6 +359,42 @@ pub fn get_parent_path(path: &Path) -> Option<PathBuf> {
 /// }
 /// ```
 pub fn list_files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    walk_files(dir, |path| {
+        // Handle empty extension case - when ext is empty, we want files with no extension
+        if ext.is_empty() {
+            path.extension().is_none()
+        } else {
+            // Normal case - match extension
+            path.extension().map(|extension| extension == ext).unwrap_or(false)
+        }
+    })
+}
+
+/// Lists all files in a directory and its subdirectories, regardless of extension
+///
+/// # Arguments
+/// * `dir` - A reference to a Path object representing the directory to search
+///
+/// # Returns
+/// A vector of PathBuf objects representing the paths to all files found
+///
+/// # Examples
+/// ```
+/// use acovo::fs::list_all_files;
+/// use std::path::Path;
+///
+/// let files = list_all_files(Path::new("./src"));
+/// println!("Found {} files", files.len());
+/// ```
+pub fn list_all_files(dir: &Path) -> Vec<PathBuf> {
+    walk_files(dir, |_| true)
+}
+
+// Walks a directory tree iteratively and collects the files accepted by `filter`
+fn walk_files<F>(dir: &Path, filter: F) -> Vec<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
     let mut files = Vec::new();
     let mut dirs_to_visit = vec![dir.to_path_buf()];
 
@@ -370,18 +406,8 @@ pub fn list_files(dir: &Path, ext: &str) -> Vec<PathBuf> {
                         if let Ok(entry) = entry {
                             let path = entry.path();
                             if path.is_file() {
-                                // Handle empty extension case - when ext is empty, we want files with no extension
-                                if ext.is_empty() {
-                                    if path.extension().is_none() {
-                                        files.push(path);
-                                    }
-                                } else {
-                                    // Normal case - match extension
-                                    if let Some(extension) = path.extension() {
-                                        if extension == ext {
-                                            files.push(path);
-                                        }
-                                    }
+                                if filter(&path) {
+                                    files.push(path);
                                 }
                             } else if path.is_dir() {
                                 dirs_to_visit.push(path);
@@ -1169,6 +1195,26 @@ mod tests {
         let _ = fs::remove_dir_all(test_dir);
     }
 
+    #[test]
+    fn test_list_all_files() {
+        let test_dir = "/tmp/acovo_list_all_files_test";
+        let _ = fs::remove_dir_all(test_dir);
+        mkdir(&format!("{}/subdir", test_dir)).expect("Failed to create test directory");
+
+        fs::File::create(format!("{}/file1.rs", test_dir)).expect("Failed to create file1");
+        fs::File::create(format!("{}/file2", test_dir)).expect("Failed to create file2");
+        fs::File::create(format!("{}/subdir/file3.txt", test_dir)).expect("Failed to create file3");
+
+        // Every file is returned regardless of extension, directories are not
+        let files = list_all_files(Path::new(test_dir));
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| f.is_file()));
+        assert!(list_all_files(Path::new("/this/path/should/not/exist")).is_empty());
+
+        // Clean up
+        let _ = fs::remove_dir_all(test_dir);
+    }
+
     #[test]
     fn test_file_name() {
         // Test with a file path
@@ -24,7 +24,7 @@
 #[cfg(feature = "hash")]
 pub fn crc64_str(data: &str, seperator: Option<&str>) -> String {
     // Compute the CRC64 checksum of the input data
-    let cksum = crc64::crc64(0, data.as_bytes());
+    let cksum = crc64_engine().checksum(data.as_bytes());
     
     // If a separator is provided, format with separator efficiently
     if let Some(sep) = seperator {
@@ -51,6 +51,2153 @@ pub fn crc64_str(data: &str, seperator: Option<&str>) -> String {
     }
 }
 
+/// Parameters of a CRC algorithm in the Rocksoft/reveng model
+///
+/// The catalog constants below carry the published check value (the CRC of the
+/// ASCII string "123456789"), which `Crc::verify_check` compares against.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CrcParams {
+    /// Catalog name, e.g. "CRC-32/ISO-HDLC"
+    pub name: &'static str,
+    /// Width in bits, 1 to 64
+    pub width: u8,
+    /// Generator polynomial in normal (MSB-first) form, without the top bit
+    pub poly: u64,
+    /// Initial register value
+    pub init: u64,
+    /// Whether input bytes are reflected
+    pub refin: bool,
+    /// Whether the final register is reflected
+    pub refout: bool,
+    /// Value XORed into the final result
+    pub xorout: u64,
+    /// Published CRC of "123456789", if known
+    pub check: Option<u64>,
+}
+
+#[cfg(feature = "hash")]
+impl CrcParams {
+    /// Define a custom CRC; its check value is unknown
+    pub const fn new(width: u8, poly: u64, init: u64, refin: bool, refout: bool, xorout: u64) -> Self {
+        CrcParams {
+            name: "custom",
+            width,
+            poly,
+            init,
+            refin,
+            refout,
+            xorout,
+            check: None,
+        }
+    }
+
+    /// Catalog entry by name, case-insensitive; common aliases are accepted
+    pub fn by_name(name: &str) -> Option<CrcParams> {
+        let upper = name.to_uppercase();
+        let canonical = match upper.as_str() {
+            "CRC-16/CCITT-FALSE" => "CRC-16/IBM-3740",
+            "CRC-16/CCITT" => "CRC-16/KERMIT",
+            "CRC-32" | "CRC-32/ZIP" => "CRC-32/ISO-HDLC",
+            "CRC-32C" | "CRC-32/CASTAGNOLI" => "CRC-32/ISCSI",
+            "CRC-64/JONES" => "CRC-64/REDIS",
+            other => other,
+        };
+        CRC_CATALOG.iter().find(|p| p.name == canonical).copied()
+    }
+
+    // Mask covering the low `width` bits
+    fn mask(&self) -> u64 {
+        if self.width >= 64 {
+            u64::MAX
+        } else {
+            (1u64 << self.width) - 1
+        }
+    }
+}
+
+/// CRC-16/IBM-3740, also known as CRC-16/CCITT-FALSE
+#[cfg(feature = "hash")]
+pub const CRC_16_IBM_3740: CrcParams = CrcParams {
+    name: "CRC-16/IBM-3740",
+    width: 16,
+    poly: 0x1021,
+    init: 0xFFFF,
+    refin: false,
+    refout: false,
+    xorout: 0,
+    check: Some(0x29B1),
+};
+
+/// CRC-16/KERMIT, the reflected CCITT CRC
+#[cfg(feature = "hash")]
+pub const CRC_16_KERMIT: CrcParams = CrcParams {
+    name: "CRC-16/KERMIT",
+    width: 16,
+    poly: 0x1021,
+    init: 0,
+    refin: true,
+    refout: true,
+    xorout: 0,
+    check: Some(0x2189),
+};
+
+/// CRC-16/XMODEM
+#[cfg(feature = "hash")]
+pub const CRC_16_XMODEM: CrcParams = CrcParams {
+    name: "CRC-16/XMODEM",
+    width: 16,
+    poly: 0x1021,
+    init: 0,
+    refin: false,
+    refout: false,
+    xorout: 0,
+    check: Some(0x31C3),
+};
+
+/// CRC-16/MODBUS
+#[cfg(feature = "hash")]
+pub const CRC_16_MODBUS: CrcParams = CrcParams {
+    name: "CRC-16/MODBUS",
+    width: 16,
+    poly: 0x8005,
+    init: 0xFFFF,
+    refin: true,
+    refout: true,
+    xorout: 0,
+    check: Some(0x4B37),
+};
+
+/// CRC-32/ISO-HDLC, the CRC-32 of zip, gzip and Ethernet
+#[cfg(feature = "hash")]
+pub const CRC_32_ISO_HDLC: CrcParams = CrcParams {
+    name: "CRC-32/ISO-HDLC",
+    width: 32,
+    poly: 0x04C1_1DB7,
+    init: 0xFFFF_FFFF,
+    refin: true,
+    refout: true,
+    xorout: 0xFFFF_FFFF,
+    check: Some(0xCBF4_3926),
+};
+
+/// CRC-32/ISCSI, also known as CRC-32C (Castagnoli)
+#[cfg(feature = "hash")]
+pub const CRC_32_ISCSI: CrcParams = CrcParams {
+    name: "CRC-32/ISCSI",
+    width: 32,
+    poly: 0x1EDC_6F41,
+    init: 0xFFFF_FFFF,
+    refin: true,
+    refout: true,
+    xorout: 0xFFFF_FFFF,
+    check: Some(0xE306_9283),
+};
+
+/// CRC-64/ECMA-182
+#[cfg(feature = "hash")]
+pub const CRC_64_ECMA_182: CrcParams = CrcParams {
+    name: "CRC-64/ECMA-182",
+    width: 64,
+    poly: 0x42F0_E1EB_A9EA_3693,
+    init: 0,
+    refin: false,
+    refout: false,
+    xorout: 0,
+    check: Some(0x6C40_DF5F_0B49_7347),
+};
+
+/// CRC-64/XZ
+#[cfg(feature = "hash")]
+pub const CRC_64_XZ: CrcParams = CrcParams {
+    name: "CRC-64/XZ",
+    width: 64,
+    poly: 0x42F0_E1EB_A9EA_3693,
+    init: u64::MAX,
+    refin: true,
+    refout: true,
+    xorout: u64::MAX,
+    check: Some(0x995D_C9BB_DF19_39FA),
+};
+
+/// CRC-64/REDIS, also known as CRC-64/Jones; the CRC used by `crc64_str`
+#[cfg(feature = "hash")]
+pub const CRC_64_REDIS: CrcParams = CrcParams {
+    name: "CRC-64/REDIS",
+    width: 64,
+    poly: 0xAD93_D235_94C9_35A9,
+    init: 0,
+    refin: true,
+    refout: true,
+    xorout: 0,
+    check: Some(0xE9C6_D914_C4B8_D9CA),
+};
+
+/// Every built-in CRC definition
+#[cfg(feature = "hash")]
+pub const CRC_CATALOG: [CrcParams; 9] = [
+    CRC_16_IBM_3740,
+    CRC_16_KERMIT,
+    CRC_16_XMODEM,
+    CRC_16_MODBUS,
+    CRC_32_ISO_HDLC,
+    CRC_32_ISCSI,
+    CRC_64_ECMA_182,
+    CRC_64_XZ,
+    CRC_64_REDIS,
+];
+
+// Reverse the low `width` bits of `value`
+#[cfg(feature = "hash")]
+fn reflect_bits(value: u64, width: u8) -> u64 {
+    value.reverse_bits() >> (64 - width as u32)
+}
+
+/// A table-driven CRC engine for any `CrcParams`
+///
+/// Data is processed eight bytes at a time with slice-by-8 tables. CRC-32/ISO-HDLC
+/// is delegated to `crc32fast`, which uses SIMD where the CPU supports it.
+/// Building the tables costs about 16 KiB and a few microseconds, so reuse a `Crc`
+/// rather than creating one per message.
+///
+/// # Example
+/// ```
+/// use acovo::hash::{Crc, CRC_16_MODBUS};
+///
+/// let crc = Crc::new(CRC_16_MODBUS).unwrap();
+/// assert_eq!(crc.checksum(b"123456789"), 0x4B37);
+/// ```
+#[cfg(feature = "hash")]
+#[derive(Clone)]
+pub struct Crc {
+    params: CrcParams,
+    tables: Box<[[u64; 256]; 8]>,
+}
+
+#[cfg(feature = "hash")]
+impl std::fmt::Debug for Crc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Crc").field("params", &self.params).finish()
+    }
+}
+
+#[cfg(feature = "hash")]
+impl Crc {
+    /// Build the lookup tables for `params`
+    ///
+    /// # Errors
+    /// * Returns an error if the width is not 1 to 64 or a value does not fit the width
+    pub fn new(params: CrcParams) -> anyhow::Result<Crc> {
+        if params.width == 0 || params.width > 64 {
+            return Err(anyhow::anyhow!("CRC width {} is not in 1..=64", params.width));
+        }
+        let mask = params.mask();
+        if params.poly & !mask != 0 || params.init & !mask != 0 || params.xorout & !mask != 0 {
+            return Err(anyhow::anyhow!("CRC parameters do not fit in {} bits", params.width));
+        }
+
+        let mut tables = Box::new([[0u64; 256]; 8]);
+        if params.refin {
+            // Reflected register in the low bits, shifting right
+            let poly = reflect_bits(params.poly, params.width);
+            for byte in 0..256 {
+                let mut crc = byte as u64;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+                }
+                tables[0][byte] = crc;
+            }
+            for k in 1..8 {
+                for byte in 0..256 {
+                    let prev = tables[k - 1][byte];
+                    tables[k][byte] = (prev >> 8) ^ tables[0][(prev & 0xFF) as usize];
+                }
+            }
+        } else {
+            // Normal register aligned to the top of a u64, shifting left
+            let poly = params.poly << (64 - params.width as u32);
+            for byte in 0..256 {
+                let mut crc = (byte as u64) << 56;
+                for _ in 0..8 {
+                    crc = if crc & (1 << 63) != 0 { (crc << 1) ^ poly } else { crc << 1 };
+                }
+                tables[0][byte] = crc;
+            }
+            for k in 1..8 {
+                for byte in 0..256 {
+                    let prev = tables[k - 1][byte];
+                    tables[k][byte] = (prev << 8) ^ tables[0][(prev >> 56) as usize];
+                }
+            }
+        }
+        Ok(Crc { params, tables })
+    }
+
+    /// The parameters this engine computes
+    pub fn params(&self) -> &CrcParams {
+        &self.params
+    }
+
+    /// CRC of `data` in one call
+    pub fn checksum(&self, data: &[u8]) -> u64 {
+        let mut digest = self.digest();
+        digest.update(data);
+        digest.finalize()
+    }
+
+    /// Start a streaming computation
+    pub fn digest(&self) -> CrcDigest<'_> {
+        let register = if self.params.refin {
+            reflect_bits(self.params.init, self.params.width)
+        } else {
+            self.params.init << (64 - self.params.width as u32)
+        };
+        CrcDigest { crc: self, register }
+    }
+
+    /// Whether the engine reproduces the published check value
+    /// Returns None for parameters without a known check value
+    pub fn verify_check(&self) -> Option<bool> {
+        self.params.check.map(|check| self.checksum(b"123456789") == check)
+    }
+
+    // Advance the register over `data`
+    fn update_register(&self, mut register: u64, data: &[u8]) -> u64 {
+        let t = &self.tables;
+        if self.params == CRC_32_ISO_HDLC {
+            let mut hasher = crc32fast::Hasher::new_with_initial(!(register as u32));
+            hasher.update(data);
+            return !(hasher.finalize()) as u64;
+        }
+
+        let mut blocks = data.chunks_exact(8);
+        if self.params.refin {
+            for block in &mut blocks {
+                let x = register ^ u64::from_le_bytes(block.try_into().unwrap());
+                register = t[7][(x & 0xFF) as usize]
+                    ^ t[6][((x >> 8) & 0xFF) as usize]
+                    ^ t[5][((x >> 16) & 0xFF) as usize]
+                    ^ t[4][((x >> 24) & 0xFF) as usize]
+                    ^ t[3][((x >> 32) & 0xFF) as usize]
+                    ^ t[2][((x >> 40) & 0xFF) as usize]
+                    ^ t[1][((x >> 48) & 0xFF) as usize]
+                    ^ t[0][(x >> 56) as usize];
+            }
+            for &byte in blocks.remainder() {
+                register = (register >> 8) ^ t[0][((register ^ byte as u64) & 0xFF) as usize];
+            }
+        } else {
+            for block in &mut blocks {
+                let x = register ^ u64::from_be_bytes(block.try_into().unwrap());
+                register = t[7][(x >> 56) as usize]
+                    ^ t[6][((x >> 48) & 0xFF) as usize]
+                    ^ t[5][((x >> 40) & 0xFF) as usize]
+                    ^ t[4][((x >> 32) & 0xFF) as usize]
+                    ^ t[3][((x >> 24) & 0xFF) as usize]
+                    ^ t[2][((x >> 16) & 0xFF) as usize]
+                    ^ t[1][((x >> 8) & 0xFF) as usize]
+                    ^ t[0][(x & 0xFF) as usize];
+            }
+            for &byte in blocks.remainder() {
+                register = (register << 8) ^ t[0][((register >> 56) ^ byte as u64) as usize];
+            }
+        }
+        register
+    }
+}
+
+/// A streaming CRC computation started with `Crc::digest`
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone)]
+pub struct CrcDigest<'a> {
+    crc: &'a Crc,
+    register: u64,
+}
+
+#[cfg(feature = "hash")]
+impl CrcDigest<'_> {
+    /// Feed more data
+    pub fn update(&mut self, data: &[u8]) {
+        self.register = self.crc.update_register(self.register, data);
+    }
+
+    /// The CRC of everything fed so far
+    pub fn finalize(&self) -> u64 {
+        let params = &self.crc.params;
+        let value = if params.refin {
+            self.register
+        } else {
+            self.register >> (64 - params.width as u32)
+        };
+        let value = if params.refin != params.refout {
+            reflect_bits(value, params.width)
+        } else {
+            value
+        };
+        (value ^ params.xorout) & params.mask()
+    }
+}
+
+#[cfg(feature = "hash")]
+impl std::io::Write for CrcDigest<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Shared engine for the CRC-64 used by crc64_str and HashAlgorithm::Crc64
+#[cfg(feature = "hash")]
+fn crc64_engine() -> &'static Crc {
+    static ENGINE: std::sync::OnceLock<Crc> = std::sync::OnceLock::new();
+    ENGINE.get_or_init(|| Crc::new(CRC_64_REDIS).unwrap())
+}
+
+/// Hash algorithms supported by `Hasher`
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// CRC-32 (ISO-HDLC, as used by zip and gzip)
+    Crc32,
+    /// CRC-64 as computed by `crc64_str`
+    Crc64,
+    /// xxHash64 with seed 0
+    XxHash64,
+    /// XXH3 64-bit with seed 0
+    Xxh3,
+    /// MD5 (not collision resistant; for legacy checksums only)
+    Md5,
+    /// SHA-1 (not collision resistant; for legacy checksums only)
+    Sha1,
+    /// SHA-256
+    Sha256,
+    /// BLAKE3 with 32-byte output
+    Blake3,
+}
+
+#[cfg(feature = "hash")]
+impl HashAlgorithm {
+    /// All supported algorithms
+    pub const ALL: [HashAlgorithm; 8] = [
+        HashAlgorithm::Crc32,
+        HashAlgorithm::Crc64,
+        HashAlgorithm::XxHash64,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+    ];
+
+    /// Canonical lowercase name, e.g. "sha256"
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Crc64 => "crc64",
+            HashAlgorithm::XxHash64 => "xxh64",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Digest length in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Crc32 => 4,
+            HashAlgorithm::Crc64 | HashAlgorithm::XxHash64 | HashAlgorithm::Xxh3 => 8,
+            HashAlgorithm::Md5 => 16,
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 32,
+        }
+    }
+}
+
+#[cfg(feature = "hash")]
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses algorithm names case-insensitively, ignoring '-' and '_' (e.g. "SHA-256", "xxhash64")
+#[cfg(feature = "hash")]
+impl std::str::FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "crc32" => Ok(HashAlgorithm::Crc32),
+            "crc64" => Ok(HashAlgorithm::Crc64),
+            "xxh64" | "xxhash64" => Ok(HashAlgorithm::XxHash64),
+            "xxh3" | "xxh364" => Ok(HashAlgorithm::Xxh3),
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            _ => Err(anyhow::anyhow!("unknown hash algorithm '{}'", s)),
+        }
+    }
+}
+
+/// The output of a hash computation
+///
+/// Checksums (CRC, xxHash) are stored big-endian so the hex form matches the
+/// conventional printed value.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest {
+    algorithm: HashAlgorithm,
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "hash")]
+impl Digest {
+    /// The algorithm that produced this digest
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Raw digest bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consume the digest and return the raw bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Lowercase hexadecimal string
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bytes)
+    }
+
+    /// Uppercase hexadecimal string, as produced by `crc64_str`
+    pub fn to_hex_upper(&self) -> String {
+        hex::encode_upper(&self.bytes)
+    }
+
+    /// Standard base64 string with padding
+    pub fn to_base64(&self) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(&self.bytes)
+    }
+
+    /// The digest as an integer, for checksums of at most 8 bytes
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.bytes.len() > 8 {
+            return None;
+        }
+        Some(self.bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+    }
+}
+
+/// Displays the digest as lowercase hex
+#[cfg(feature = "hash")]
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "hash")]
+enum HasherState {
+    Crc32(crc32fast::Hasher),
+    Crc64(u64),
+    XxHash64(xxhash_rust::xxh64::Xxh64),
+    Xxh3(Box<xxhash_rust::xxh3::Xxh3>),
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// A streaming hasher over any supported algorithm
+///
+/// Feed data with `update` or through `std::io::Write` (e.g. `std::io::copy`),
+/// then call `finalize`.
+///
+/// # Example
+/// ```
+/// use acovo::hash::{HashAlgorithm, Hasher};
+///
+/// let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+/// hasher.update(b"hello ");
+/// hasher.update(b"world");
+/// println!("{}", hasher.finalize().to_hex());
+/// ```
+#[cfg(feature = "hash")]
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    state: HasherState,
+}
+
+#[cfg(feature = "hash")]
+impl Hasher {
+    /// Create a hasher for the given algorithm
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        use sha2::Digest as _;
+
+        let state = match algorithm {
+            HashAlgorithm::Crc32 => HasherState::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Crc64 => HasherState::Crc64(0),
+            HashAlgorithm::XxHash64 => HasherState::XxHash64(xxhash_rust::xxh64::Xxh64::new(0)),
+            HashAlgorithm::Xxh3 => HasherState::Xxh3(Box::new(xxhash_rust::xxh3::Xxh3::new())),
+            HashAlgorithm::Md5 => HasherState::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => HasherState::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => HasherState::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Blake3 => HasherState::Blake3(Box::new(blake3::Hasher::new())),
+        };
+        Hasher { algorithm, state }
+    }
+
+    /// The algorithm this hasher computes
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Feed more data into the hasher
+    pub fn update(&mut self, data: &[u8]) {
+        use sha2::Digest as _;
+
+        match &mut self.state {
+            HasherState::Crc32(h) => h.update(data),
+            HasherState::Crc64(register) => *register = crc64_engine().update_register(*register, data),
+            HasherState::XxHash64(h) => h.update(data),
+            HasherState::Xxh3(h) => h.update(data),
+            HasherState::Md5(h) => h.update(data),
+            HasherState::Sha1(h) => h.update(data),
+            HasherState::Sha256(h) => h.update(data),
+            HasherState::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    /// Finish hashing and return the digest
+    pub fn finalize(self) -> Digest {
+        use sha2::Digest as _;
+
+        let bytes = match self.state {
+            HasherState::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            HasherState::Crc64(crc) => crc.to_be_bytes().to_vec(),
+            HasherState::XxHash64(h) => h.digest().to_be_bytes().to_vec(),
+            HasherState::Xxh3(h) => h.digest().to_be_bytes().to_vec(),
+            HasherState::Md5(h) => h.finalize().to_vec(),
+            HasherState::Sha1(h) => h.finalize().to_vec(),
+            HasherState::Sha256(h) => h.finalize().to_vec(),
+            HasherState::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        };
+        Digest {
+            algorithm: self.algorithm,
+            bytes,
+        }
+    }
+}
+
+#[cfg(feature = "hash")]
+impl std::io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Buffer size used when hashing readers and files
+#[cfg(feature = "hash")]
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Hash a byte slice in one call
+///
+/// # Arguments
+/// * `algorithm` - The hash algorithm to use
+/// * `data` - The bytes to hash
+///
+/// # Returns
+/// * The digest of `data`
+#[cfg(feature = "hash")]
+pub fn hash_bytes(algorithm: HashAlgorithm, data: &[u8]) -> Digest {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Hash everything read from `reader` until EOF
+///
+/// # Arguments
+/// * `algorithm` - The hash algorithm to use
+/// * `reader` - The source of the data
+///
+/// # Returns
+/// * `Ok(Digest)` - The digest of the stream
+/// * `Err(io::Error)` - If reading failed
+#[cfg(feature = "hash")]
+pub fn hash_reader<R: std::io::Read>(algorithm: HashAlgorithm, mut reader: R) -> std::io::Result<Digest> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+/// Hash a file by path using buffered reads, suitable for multi-GB files
+///
+/// # Arguments
+/// * `algorithm` - The hash algorithm to use
+/// * `path` - The file to hash
+///
+/// # Returns
+/// * `Ok(Digest)` - The digest of the file contents
+/// * `Err(anyhow::Error)` - If the file could not be opened or read
+#[cfg(feature = "hash")]
+pub fn hash_file<P: AsRef<std::path::Path>>(algorithm: HashAlgorithm, path: P) -> anyhow::Result<Digest> {
+    let file = std::fs::File::open(path.as_ref())
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?} for hashing: {}", path.as_ref(), e))?;
+    hash_reader(algorithm, file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {:?} for hashing: {}", path.as_ref(), e))
+}
+
+/// HMAC variants supported by `hmac_bytes`
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HmacAlgorithm {
+    /// HMAC-SHA256, 32-byte tag
+    Sha256,
+    /// HMAC-SHA512, 64-byte tag
+    Sha512,
+}
+
+#[cfg(feature = "hash")]
+impl HmacAlgorithm {
+    /// Canonical name, e.g. "HMAC-SHA256"
+    pub fn name(&self) -> &'static str {
+        match self {
+            HmacAlgorithm::Sha256 => "HMAC-SHA256",
+            HmacAlgorithm::Sha512 => "HMAC-SHA512",
+        }
+    }
+}
+
+/// Compute an HMAC tag over `data` with `key`
+///
+/// # Arguments
+/// * `algorithm` - The HMAC variant to use
+/// * `key` - The secret key (any length)
+/// * `data` - The message to authenticate
+///
+/// # Returns
+/// * The raw tag bytes
+#[cfg(feature = "hash")]
+pub fn hmac_bytes(algorithm: HmacAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::{Hmac, Mac};
+
+    // HMAC accepts keys of any length, so new_from_slice cannot fail
+    match algorithm {
+        HmacAlgorithm::Sha256 => {
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        HmacAlgorithm::Sha512 => {
+            let mut mac = Hmac::<sha2::Sha512>::new_from_slice(key).unwrap();
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+/// Compute an HMAC-SHA256 tag as lowercase hex
+#[cfg(feature = "hash")]
+pub fn hmac_sha256_hex(key: &[u8], data: &[u8]) -> String {
+    hex::encode(hmac_bytes(HmacAlgorithm::Sha256, key, data))
+}
+
+/// Compute an HMAC-SHA512 tag as lowercase hex
+#[cfg(feature = "hash")]
+pub fn hmac_sha512_hex(key: &[u8], data: &[u8]) -> String {
+    hex::encode(hmac_bytes(HmacAlgorithm::Sha512, key, data))
+}
+
+/// Check an HMAC tag in constant time
+///
+/// # Returns
+/// * `true` if `tag` is the HMAC of `data` under `key`
+#[cfg(feature = "hash")]
+pub fn hmac_verify(algorithm: HmacAlgorithm, key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    constant_time_eq(&hmac_bytes(algorithm, key, data), tag)
+}
+
+/// Compare two byte slices without short-circuiting on the first difference
+///
+/// The running time depends only on the lengths, so comparing secrets such as
+/// signatures does not leak how many leading bytes matched.
+#[cfg(feature = "hash")]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Serializes as the canonical algorithm name, e.g. "sha256"
+#[cfg(feature = "hash")]
+impl serde::Serialize for HashAlgorithm {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "hash")]
+impl<'de> serde::Deserialize<'de> for HashAlgorithm {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// One file recorded in a `Manifest`
+#[cfg(all(feature = "hash", feature = "fs"))]
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the manifest root, always '/'-separated
+    pub path: String,
+    /// Lowercase hex digest of the file contents
+    pub digest: String,
+    /// File size in bytes (absent when loaded from checksum text)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Modification time in seconds since the UNIX epoch (absent when loaded from checksum text)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+}
+
+/// A list of file digests for a directory tree
+///
+/// Manifests can be written as sha256sum-compatible text (`<digest>  <path>`
+/// per line) or as JSON, which also records size and modification time:
+///
+/// ```json
+/// {"algorithm":"sha256","files":[{"path":"bin/app","digest":"…","size":1024,"modified":1700000000}]}
+/// ```
+///
+/// # Example
+/// ```no_run
+/// use acovo::hash::{HashAlgorithm, Manifest};
+/// use std::path::Path;
+///
+/// let manifest = Manifest::generate(Path::new("./release"), HashAlgorithm::Sha256).unwrap();
+/// manifest.write_json_file("release.manifest.json").unwrap();
+///
+/// // Later, after deployment
+/// let report = Manifest::load("release.manifest.json").unwrap().verify(Path::new("/opt/app")).unwrap();
+/// assert!(report.is_ok(), "{:?}", report);
+/// ```
+#[cfg(all(feature = "hash", feature = "fs"))]
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct Manifest {
+    /// Algorithm used for every digest
+    pub algorithm: HashAlgorithm,
+    /// Entries sorted by path
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Differences found by `Manifest::verify`
+#[cfg(all(feature = "hash", feature = "fs"))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Files listed in the manifest but absent from the tree
+    pub missing: Vec<String>,
+    /// Files present in the tree but not listed in the manifest
+    pub extra: Vec<String>,
+    /// Files whose size or digest differs from the manifest
+    pub modified: Vec<String>,
+    /// Number of files that matched
+    pub matched: usize,
+}
+
+#[cfg(all(feature = "hash", feature = "fs"))]
+impl VerifyReport {
+    /// Whether the tree matches the manifest exactly
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[cfg(all(feature = "hash", feature = "fs"))]
+impl Manifest {
+    /// Hash every file under `root` in parallel, using one thread per available CPU
+    ///
+    /// # Arguments
+    /// * `root` - The directory tree to hash
+    /// * `algorithm` - The hash algorithm to use
+    ///
+    /// # Returns
+    /// * `Ok(Manifest)` - Entries for every file, sorted by relative path
+    /// * `Err(anyhow::Error)` - If `root` is not a directory or a file could not be read
+    pub fn generate(root: &std::path::Path, algorithm: HashAlgorithm) -> anyhow::Result<Manifest> {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Manifest::generate_with_threads(root, algorithm, threads)
+    }
+
+    /// Like `generate`, with an explicit number of worker threads
+    pub fn generate_with_threads(
+        root: &std::path::Path,
+        algorithm: HashAlgorithm,
+        threads: usize,
+    ) -> anyhow::Result<Manifest> {
+        if !root.is_dir() {
+            return Err(anyhow::anyhow!("{:?} is not a directory", root));
+        }
+        let files = crate::fs::list_all_files(root);
+        let mut entries = hash_files_parallel(root, &files, algorithm, threads)?;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Manifest {
+            algorithm,
+            files: entries,
+        })
+    }
+
+    /// Render as sha256sum-compatible text: `<digest>  <path>` per line
+    ///
+    /// Like GNU sha256sum, a path containing `\\`, a newline or a carriage return
+    /// is escaped and its line starts with `\\`.
+    pub fn to_checksum_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.files {
+            if entry.path.contains(['\\', '\n', '\r']) {
+                text.push('\\');
+                text.push_str(&entry.digest);
+                text.push_str("  ");
+                for c in entry.path.chars() {
+                    match c {
+                        '\\' => text.push_str("\\\\"),
+                        '\n' => text.push_str("\\n"),
+                        '\r' => text.push_str("\\r"),
+                        c => text.push(c),
+                    }
+                }
+            } else {
+                text.push_str(&entry.digest);
+                text.push_str("  ");
+                text.push_str(&entry.path);
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parse sha256sum-style text; lines may use text (`  `) or binary (` *`) mode markers
+    ///
+    /// A line starting with `\\` has an escaped path (`\\\\`, `\\n` and `\\r`), as
+    /// written by GNU sha256sum for names containing those characters.
+    ///
+    /// # Arguments
+    /// * `text` - The checksum text
+    /// * `algorithm` - The algorithm the digests were produced with
+    pub fn from_checksum_text(text: &str, algorithm: HashAlgorithm) -> anyhow::Result<Manifest> {
+        let mut files = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (escaped, line) = match line.strip_prefix('\\') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (digest, path) = line
+                .split_once(' ')
+                .ok_or(anyhow::anyhow!("malformed checksum line {}: '{}'", number + 1, line))?;
+            let path = path.strip_prefix(' ').or(path.strip_prefix('*')).unwrap_or(path);
+            if digest.len() != algorithm.output_len() * 2 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow::anyhow!("invalid {} digest on line {}", algorithm, number + 1));
+            }
+            let path = if escaped {
+                unescape_checksum_path(path)
+                    .ok_or(anyhow::anyhow!("invalid escape in path on checksum line {}", number + 1))?
+            } else {
+                path.to_string()
+            };
+            files.push(ManifestEntry {
+                path,
+                digest: digest.to_lowercase(),
+                size: None,
+                modified: None,
+            });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Manifest { algorithm, files })
+    }
+
+    /// Render as pretty-printed JSON
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a JSON manifest
+    pub fn from_json(text: &str) -> anyhow::Result<Manifest> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Write the checksum text form to `path`
+    pub fn write_checksum_file<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        std::fs::write(path.as_ref(), self.to_checksum_text())
+            .map_err(|e| anyhow::anyhow!("Failed to write manifest {:?}: {}", path.as_ref(), e))
+    }
+
+    /// Write the JSON form to `path`
+    pub fn write_json_file<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        std::fs::write(path.as_ref(), self.to_json()?)
+            .map_err(|e| anyhow::anyhow!("Failed to write manifest {:?}: {}", path.as_ref(), e))
+    }
+
+    /// Load a manifest written by `write_json_file` or `write_checksum_file`
+    /// Checksum text is assumed to be SHA-256, as produced by sha256sum
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<Manifest> {
+        let text = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| anyhow::anyhow!("Failed to read manifest {:?}: {}", path.as_ref(), e))?;
+        if text.trim_start().starts_with('{') {
+            Manifest::from_json(&text)
+        } else {
+            Manifest::from_checksum_text(&text, HashAlgorithm::Sha256)
+        }
+    }
+
+    /// Compare the tree under `root` against this manifest, hashing files in parallel
+    ///
+    /// Files whose recorded size differs are reported as modified without being hashed.
+    ///
+    /// # Returns
+    /// * `Ok(VerifyReport)` - Missing, extra and modified files (each sorted)
+    /// * `Err(anyhow::Error)` - If `root` is not a directory or a file could not be read
+    pub fn verify(&self, root: &std::path::Path) -> anyhow::Result<VerifyReport> {
+        use std::collections::HashMap;
+
+        if !root.is_dir() {
+            return Err(anyhow::anyhow!("{:?} is not a directory", root));
+        }
+
+        let mut on_disk: HashMap<String, std::path::PathBuf> = crate::fs::list_all_files(root)
+            .into_iter()
+            .map(|path| (relative_manifest_path(root, &path), path))
+            .collect();
+
+        let mut report = VerifyReport::default();
+        let mut to_hash = Vec::new();
+        let mut expected = HashMap::new();
+        for entry in &self.files {
+            match on_disk.remove(&entry.path) {
+                None => report.missing.push(entry.path.clone()),
+                Some(path) => {
+                    let size = std::fs::metadata(&path).map(|m| m.len()).ok();
+                    if entry.size.is_some() && size != entry.size {
+                        report.modified.push(entry.path.clone());
+                    } else {
+                        expected.insert(entry.path.clone(), entry.digest.to_lowercase());
+                        to_hash.push(path);
+                    }
+                }
+            }
+        }
+        report.extra = on_disk.into_keys().collect();
+
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        for actual in hash_files_parallel(root, &to_hash, self.algorithm, threads)? {
+            if expected.get(&actual.path) == Some(&actual.digest) {
+                report.matched += 1;
+            } else {
+                report.modified.push(actual.path);
+            }
+        }
+
+        report.missing.sort();
+        report.extra.sort();
+        report.modified.sort();
+        Ok(report)
+    }
+}
+
+// Undo GNU sha256sum path escaping; None on an unknown or trailing escape
+#[cfg(all(feature = "hash", feature = "fs"))]
+fn unescape_checksum_path(path: &str) -> Option<String> {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+// Path of `path` relative to `root`, '/'-separated on every platform
+#[cfg(all(feature = "hash", feature = "fs"))]
+fn relative_manifest_path(root: &std::path::Path, path: &std::path::Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Hash files on a pool of scoped threads; the first error stops the remaining work
+#[cfg(all(feature = "hash", feature = "fs"))]
+fn hash_files_parallel(
+    root: &std::path::Path,
+    files: &[std::path::PathBuf],
+    algorithm: HashAlgorithm,
+    threads: usize,
+) -> anyhow::Result<Vec<ManifestEntry>> {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::UNIX_EPOCH;
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let hash_one = |path: &std::path::PathBuf| -> anyhow::Result<ManifestEntry> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| anyhow::anyhow!("Failed to access file metadata for {:?}: {}", path, e))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        Ok(ManifestEntry {
+            path: relative_manifest_path(root, path),
+            digest: hash_file(algorithm, path)?.to_hex(),
+            size: Some(metadata.len()),
+            modified,
+        })
+    };
+
+    let results: Vec<anyhow::Result<Vec<ManifestEntry>>> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, files.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut entries = Vec::new();
+                    while !failed.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = files.get(index) else { break };
+                        match hash_one(path) {
+                            Ok(entry) => entries.push(entry),
+                            Err(e) => {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(e);
+                            }
+                        }
+                    }
+                    Ok(entries)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|w| w.join().unwrap_or_else(|_| Err(anyhow::anyhow!("hash worker panicked"))))
+            .collect()
+    });
+
+    let mut entries = Vec::with_capacity(files.len());
+    for result in results {
+        entries.extend(result?);
+    }
+    Ok(entries)
+}
+
+// 64-bit hash used for sharding; XXH3 is fast and well distributed
+#[cfg(feature = "hash")]
+fn shard_hash(data: &[u8]) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(data)
+}
+
+/// Something that assigns keys to named nodes
+///
+/// Implemented by `ConsistentRing` and `RendezvousHasher` so that `moved_keys`
+/// can compare assignments before and after a membership change.
+#[cfg(feature = "hash")]
+pub trait NodeSelector {
+    /// The node responsible for `key`, or None if there are no nodes
+    fn node_for(&self, key: &str) -> Option<&str>;
+}
+
+/// A key whose node assignment changed between two selectors
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMove {
+    /// The key that moved
+    pub key: String,
+    /// Previous node, None if there was none
+    pub from: Option<String>,
+    /// New node, None if there is none
+    pub to: Option<String>,
+}
+
+/// List the keys whose node differs between `before` and `after`
+///
+/// # Arguments
+/// * `keys` - The keys to check (e.g., every stock symbol)
+/// * `before` - The assignment before the change
+/// * `after` - The assignment after the change
+///
+/// # Returns
+/// * The moved keys, in the order given
+#[cfg(feature = "hash")]
+pub fn moved_keys<I, K, A, B>(keys: I, before: &A, after: &B) -> Vec<KeyMove>
+where
+    I: IntoIterator<Item = K>,
+    K: AsRef<str>,
+    A: NodeSelector + ?Sized,
+    B: NodeSelector + ?Sized,
+{
+    keys.into_iter()
+        .filter_map(|key| {
+            let key = key.as_ref();
+            let from = before.node_for(key);
+            let to = after.node_for(key);
+            if from == to {
+                return None;
+            }
+            Some(KeyMove {
+                key: key.to_string(),
+                from: from.map(str::to_string),
+                to: to.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// A consistent hashing ring with weighted virtual nodes
+///
+/// Each node is placed on the ring `vnodes * weight` times. A key belongs to the
+/// first virtual node clockwise from its hash, so adding or removing a node only
+/// moves the keys adjacent to that node's virtual nodes.
+///
+/// # Example
+/// ```
+/// use acovo::hash::ConsistentRing;
+///
+/// let mut ring = ConsistentRing::new(160);
+/// ring.add_node("worker-1", 1)?;
+/// ring.add_node("worker-2", 2)?;
+/// let worker = ring.get("AAPL").unwrap();
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone)]
+pub struct ConsistentRing {
+    vnodes: usize,
+    nodes: std::collections::BTreeMap<String, u32>,
+    // Virtual nodes ordered by position; on a hash collision the smaller name comes first
+    ring: std::collections::BTreeSet<(u64, String)>,
+}
+
+/// Largest node weight accepted by `ConsistentRing::add_node`
+#[cfg(feature = "hash")]
+pub const MAX_RING_WEIGHT: u32 = 10_000;
+
+#[cfg(feature = "hash")]
+impl ConsistentRing {
+    /// Create an empty ring placing `vnodes` virtual nodes per unit of weight
+    pub fn new(vnodes: usize) -> Self {
+        ConsistentRing {
+            vnodes: vnodes.max(1),
+            nodes: std::collections::BTreeMap::new(),
+            ring: std::collections::BTreeSet::new(),
+        }
+    }
+
+    /// Add a node, or change its weight if it already exists
+    /// A weight of zero removes the node
+    ///
+    /// # Errors
+    /// * Returns an error if `weight` is above `MAX_RING_WEIGHT`
+    pub fn add_node(&mut self, name: &str, weight: u32) -> anyhow::Result<()> {
+        if weight > MAX_RING_WEIGHT {
+            return Err(anyhow::anyhow!(
+                "node weight {} is above the maximum of {}",
+                weight,
+                MAX_RING_WEIGHT
+            ));
+        }
+        if weight == 0 {
+            self.remove_node(name);
+            return Ok(());
+        }
+        // Virtual node i of a node is the same at any weight, so only the difference changes
+        let old = self.nodes.insert(name.to_string(), weight).unwrap_or(0);
+        let (old, new) = (self.points(old), self.points(weight));
+        if new > old {
+            for i in old..new {
+                self.ring.insert((Self::vnode_hash(name, i), name.to_string()));
+            }
+        } else {
+            for i in new..old {
+                self.ring.remove(&(Self::vnode_hash(name, i), name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a node; returns false if it was not present
+    pub fn remove_node(&mut self, name: &str) -> bool {
+        match self.nodes.remove(name) {
+            Some(weight) => {
+                for i in 0..self.points(weight) {
+                    self.ring.remove(&(Self::vnode_hash(name, i), name.to_string()));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Nodes and their weights, sorted by name
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.nodes.iter().map(|(name, weight)| (name.as_str(), *weight))
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the ring has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The node responsible for `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let point = (shard_hash(key.as_bytes()), String::new());
+        self.ring
+            .range(point..)
+            .chain(self.ring.iter())
+            .next()
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Up to `n` distinct nodes for `key`, in ring order, for replicated placement
+    pub fn get_n(&self, key: &str, n: usize) -> Vec<&str> {
+        let point = (shard_hash(key.as_bytes()), String::new());
+        let mut result: Vec<&str> = Vec::with_capacity(n.min(self.nodes.len()));
+        for (_, name) in self.ring.range(point.clone()..).chain(self.ring.range(..point)) {
+            if result.len() >= n.min(self.nodes.len()) {
+                break;
+            }
+            if !result.contains(&name.as_str()) {
+                result.push(name.as_str());
+            }
+        }
+        result
+    }
+
+    // Number of virtual nodes for a weight
+    fn points(&self, weight: u32) -> usize {
+        self.vnodes.saturating_mul(weight as usize)
+    }
+
+    // Ring position of virtual node `index` of `name`
+    fn vnode_hash(name: &str, index: usize) -> u64 {
+        shard_hash(format!("{}#{}", name, index).as_bytes())
+    }
+}
+
+#[cfg(feature = "hash")]
+impl NodeSelector for ConsistentRing {
+    fn node_for(&self, key: &str) -> Option<&str> {
+        self.get(key)
+    }
+}
+
+/// Weighted rendezvous (highest random weight) hashing
+///
+/// Every node scores every key and the highest score wins. No ring has to be
+/// built, and when a node leaves only the keys it owned move.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Default)]
+pub struct RendezvousHasher {
+    nodes: Vec<(String, f64)>,
+}
+
+#[cfg(feature = "hash")]
+impl RendezvousHasher {
+    /// Create a hasher with no nodes
+    pub fn new() -> Self {
+        RendezvousHasher::default()
+    }
+
+    /// Add a node, or change its weight if it already exists
+    /// Non-positive weights remove the node
+    pub fn add_node(&mut self, name: &str, weight: f64) {
+        self.remove_node(name);
+        if weight > 0.0 {
+            self.nodes.push((name.to_string(), weight));
+        }
+    }
+
+    /// Remove a node; returns false if it was not present
+    pub fn remove_node(&mut self, name: &str) -> bool {
+        let before = self.nodes.len();
+        self.nodes.retain(|(n, _)| n != name);
+        self.nodes.len() != before
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether there are no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The node responsible for `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.ranked(key).into_iter().next()
+    }
+
+    /// The `n` highest-scoring nodes for `key`, best first
+    pub fn get_n(&self, key: &str, n: usize) -> Vec<&str> {
+        let mut ranked = self.ranked(key);
+        ranked.truncate(n);
+        ranked
+    }
+
+    // All nodes ordered by score, ties broken by name for determinism
+    fn ranked(&self, key: &str) -> Vec<&str> {
+        let mut scored: Vec<(f64, &str)> = self
+            .nodes
+            .iter()
+            .map(|(name, weight)| {
+                let h = shard_hash(format!("{}\u{0}{}", name, key).as_bytes());
+                // Map to (0, 1) and apply the logarithmic weighting
+                let unit = ((h >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+                (weight / -unit.ln(), name.as_str())
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+#[cfg(feature = "hash")]
+impl NodeSelector for RendezvousHasher {
+    fn node_for(&self, key: &str) -> Option<&str> {
+        self.get(key)
+    }
+}
+
+// Two independent 64-bit hashes of `data` for double hashing (g_i = h1 + i * h2)
+#[cfg(feature = "hash")]
+fn double_hash(data: &[u8]) -> (u64, u64) {
+    let h1 = shard_hash(data);
+    // Forcing h2 odd keeps the probe sequence from collapsing when h2 is zero
+    let h2 = xxhash_rust::xxh64::xxh64(data, 0) | 1;
+    (h1, h2)
+}
+
+/// A Bloom filter for approximate set membership
+///
+/// `contains` never returns false for an inserted item; it may return true for
+/// an item that was never inserted, at roughly the configured false positive rate.
+///
+/// # Example
+/// ```
+/// use acovo::hash::BloomFilter;
+///
+/// let mut seen = BloomFilter::with_false_positive_rate(1_000_000, 0.001);
+/// if !seen.insert(b"2024-01-01 12:00:00 order 42") {
+///     // probably a duplicate
+/// }
+/// ```
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    inserted: u64,
+}
+
+// Header of the serialized Bloom filter format
+#[cfg(feature = "hash")]
+const BLOOM_MAGIC: &[u8; 4] = b"ACBF";
+#[cfg(feature = "hash")]
+const BLOOM_VERSION: u8 = 1;
+
+#[cfg(feature = "hash")]
+impl BloomFilter {
+    /// Create a filter with `num_bits` bits and `num_hashes` probes per item
+    pub fn new(num_bits: u64, num_hashes: u32) -> Self {
+        let num_bits = num_bits.max(64);
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes: num_hashes.max(1),
+            inserted: 0,
+        }
+    }
+
+    /// Create a filter sized for `expected_items` at the target false positive rate
+    ///
+    /// # Arguments
+    /// * `expected_items` - How many distinct items will be inserted
+    /// * `fp_rate` - Target false positive probability, e.g. 0.01 for 1%
+    pub fn with_false_positive_rate(expected_items: u64, fp_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let p = fp_rate.clamp(1e-12, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-n * p.ln() / (ln2 * ln2)).ceil() as u64;
+        let num_hashes = ((num_bits as f64 / n) * ln2).round() as u32;
+        BloomFilter::new(num_bits, num_hashes)
+    }
+
+    /// Number of bits in the filter
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Number of probes per item
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Number of `insert` calls that added at least one new bit
+    ///
+    /// After `merge` this is an estimate from the bits set, since items present
+    /// in both filters are counted once.
+    pub fn len(&self) -> u64 {
+        self.inserted
+    }
+
+    /// Whether nothing has been inserted
+    pub fn is_empty(&self) -> bool {
+        self.inserted == 0
+    }
+
+    /// Insert an item; returns true if it was definitely not present before
+    pub fn insert(&mut self, item: &[u8]) -> bool {
+        let (h1, h2) = double_hash(item);
+        let mut added = false;
+        for i in 0..self.num_hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                self.bits[word] |= mask;
+                added = true;
+            }
+        }
+        if added {
+            self.inserted += 1;
+        }
+        added
+    }
+
+    /// Whether the item may have been inserted
+    pub fn contains(&self, item: &[u8]) -> bool {
+        let (h1, h2) = double_hash(item);
+        (0..self.num_hashes as u64).all(|i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            self.bits[(bit / 64) as usize] & (1u64 << (bit % 64)) != 0
+        })
+    }
+
+    /// Expected false positive rate given the bits currently set
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        (self.bits_set() as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+
+    // Number of bits set
+    fn bits_set(&self) -> u64 {
+        self.bits.iter().map(|w| w.count_ones() as u64).sum()
+    }
+
+    /// Add every item of `other` into this filter
+    ///
+    /// # Errors
+    /// * Returns an error if the filters have different sizes or probe counts
+    pub fn merge(&mut self, other: &BloomFilter) -> anyhow::Result<()> {
+        if self.num_bits != other.num_bits || self.num_hashes != other.num_hashes {
+            return Err(anyhow::anyhow!("cannot merge Bloom filters with different parameters"));
+        }
+        for (a, b) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *a |= *b;
+        }
+        // Swamidass & Baldi estimate n = -(m / k) * ln(1 - X / m), bounded by the
+        // counts of the two inputs so overlapping items are not counted twice
+        let (m, k) = (self.num_bits as f64, self.num_hashes as f64);
+        let estimate = -(m / k) * (1.0 - self.bits_set() as f64 / m).ln();
+        let lower = self.inserted.max(other.inserted);
+        let upper = self.inserted.saturating_add(other.inserted);
+        self.inserted = if estimate.is_finite() {
+            (estimate.round() as u64).clamp(lower, upper)
+        } else {
+            upper
+        };
+        Ok(())
+    }
+
+    /// Remove all items
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|w| *w = 0);
+        self.inserted = 0;
+    }
+
+    /// Serialize to a portable little-endian byte format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(25 + self.bits.len() * 8);
+        out.extend_from_slice(BLOOM_MAGIC);
+        out.push(BLOOM_VERSION);
+        out.extend_from_slice(&self.num_hashes.to_le_bytes());
+        out.extend_from_slice(&self.num_bits.to_le_bytes());
+        out.extend_from_slice(&self.inserted.to_le_bytes());
+        for word in &self.bits {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    /// Deserialize from the format written by `to_bytes`
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<BloomFilter> {
+        if data.len() < 25 || &data[..4] != BLOOM_MAGIC {
+            return Err(anyhow::anyhow!("not a serialized Bloom filter"));
+        }
+        if data[4] != BLOOM_VERSION {
+            return Err(anyhow::anyhow!("unsupported Bloom filter version {}", data[4]));
+        }
+        let num_hashes = u32::from_le_bytes(data[5..9].try_into()?);
+        let num_bits = u64::from_le_bytes(data[9..17].try_into()?);
+        let inserted = u64::from_le_bytes(data[17..25].try_into()?);
+        let words = &data[25..];
+        if num_bits < 64 || num_hashes == 0 || words.len() as u64 != num_bits.div_ceil(64) * 8 {
+            return Err(anyhow::anyhow!("corrupt Bloom filter data"));
+        }
+        let bits = words
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(BloomFilter {
+            bits,
+            num_bits,
+            num_hashes,
+            inserted,
+        })
+    }
+
+    /// Write the filter to a file
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        std::fs::write(path.as_ref(), self.to_bytes())
+            .map_err(|e| anyhow::anyhow!("Failed to write Bloom filter {:?}: {}", path.as_ref(), e))
+    }
+
+    /// Read a filter written by `save`
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<BloomFilter> {
+        let data = std::fs::read(path.as_ref())
+            .map_err(|e| anyhow::anyhow!("Failed to read Bloom filter {:?}: {}", path.as_ref(), e))?;
+        BloomFilter::from_bytes(&data)
+    }
+}
+
+/// A Count-Min sketch for approximate frequency counts
+///
+/// Estimates never undercount; they overcount by at most `epsilon * total`
+/// with probability `1 - delta`.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    counters: Vec<u64>,
+    total: u64,
+}
+
+#[cfg(feature = "hash")]
+impl CountMinSketch {
+    /// Create a sketch with `depth` rows of `width` counters
+    pub fn new(width: usize, depth: usize) -> Self {
+        let (width, depth) = (width.max(1), depth.max(1));
+        CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+            total: 0,
+        }
+    }
+
+    /// Create a sketch whose error is at most `epsilon * total` with probability `1 - delta`
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        let width = (std::f64::consts::E / epsilon.clamp(1e-9, 1.0)).ceil() as usize;
+        let depth = (1.0 / delta.clamp(1e-12, 0.999)).ln().ceil() as usize;
+        CountMinSketch::new(width, depth)
+    }
+
+    /// Add `count` occurrences of `item`
+    pub fn add(&mut self, item: &[u8], count: u64) {
+        let (h1, h2) = double_hash(item);
+        for row in 0..self.depth {
+            let index = self.index(row, h1, h2);
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Estimated number of occurrences of `item`
+    pub fn estimate(&self, item: &[u8]) -> u64 {
+        let (h1, h2) = double_hash(item);
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, h1, h2)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Sum of all counts added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Add the counts of `other` into this sketch
+    ///
+    /// # Errors
+    /// * Returns an error if the sketches have different dimensions
+    pub fn merge(&mut self, other: &CountMinSketch) -> anyhow::Result<()> {
+        if self.width != other.width || self.depth != other.depth {
+            return Err(anyhow::anyhow!("cannot merge Count-Min sketches with different dimensions"));
+        }
+        for (a, b) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *a = a.saturating_add(*b);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    fn index(&self, row: usize, h1: u64, h2: u64) -> usize {
+        let column = h1.wrapping_add((row as u64).wrapping_mul(h2)) % self.width as u64;
+        row * self.width + column as usize
+    }
+}
+
+/// A HyperLogLog cardinality estimator
+///
+/// Uses `2^precision` one-byte registers; the standard error is about
+/// `1.04 / sqrt(2^precision)` (0.81% at the default precision of 14).
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+#[cfg(feature = "hash")]
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::new(14)
+    }
+}
+
+#[cfg(feature = "hash")]
+impl HyperLogLog {
+    /// Create an estimator with the given precision, clamped to 4..=18
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(4, 18);
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Record an item
+    pub fn add(&mut self, item: &[u8]) {
+        let hash = shard_hash(item);
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Rank of the first set bit in the remaining bits, with a sentinel to bound it
+        let rest = (hash << self.precision) | (1u64 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Estimated number of distinct items recorded
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let estimate = alpha * m * m / sum;
+
+        // Small ranges are more accurate with linear counting
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+
+    /// Combine with another estimator so that `count` covers both inputs
+    ///
+    /// # Errors
+    /// * Returns an error if the precisions differ
+    pub fn merge(&mut self, other: &HyperLogLog) -> anyhow::Result<()> {
+        if self.precision != other.precision {
+            return Err(anyhow::anyhow!("cannot merge HyperLogLog estimators with different precision"));
+        }
+        for (a, b) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *a = (*a).max(*b);
+        }
+        Ok(())
+    }
+}
+
+// Gear table for content-defined chunking, generated with splitmix64 so it is
+// identical on every platform and release
+#[cfg(feature = "hash")]
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6163_6F76_6F5F_6364; // "acovo_cd"
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// Size limits and chunk hash for content-defined chunking
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkerConfig {
+    /// No chunk is smaller than this, except the last one
+    pub min_size: usize,
+    /// Target average chunk size; must be a power of two
+    pub avg_size: usize,
+    /// No chunk is larger than this
+    pub max_size: usize,
+    /// Strong hash recorded for each chunk
+    pub algorithm: HashAlgorithm,
+}
+
+#[cfg(feature = "hash")]
+impl Default for ChunkerConfig {
+    /// 2 KiB minimum, 8 KiB average, 64 KiB maximum, BLAKE3 chunk hashes
+    fn default() -> Self {
+        ChunkerConfig {
+            min_size: 2 * 1024,
+            avg_size: 8 * 1024,
+            max_size: 64 * 1024,
+            algorithm: HashAlgorithm::Blake3,
+        }
+    }
+}
+
+#[cfg(feature = "hash")]
+impl ChunkerConfig {
+    /// Create a config with the given sizes and BLAKE3 chunk hashes
+    ///
+    /// # Errors
+    /// * Returns an error unless `0 < min_size <= avg_size <= max_size` and `avg_size` is a power of two
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> anyhow::Result<Self> {
+        let config = ChunkerConfig {
+            min_size,
+            avg_size,
+            max_size,
+            algorithm: HashAlgorithm::Blake3,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Use a different strong hash for chunk identity
+    pub fn with_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.min_size == 0 || self.min_size > self.avg_size || self.avg_size > self.max_size {
+            return Err(anyhow::anyhow!(
+                "chunk sizes must satisfy 0 < min ({}) <= avg ({}) <= max ({})",
+                self.min_size,
+                self.avg_size,
+                self.max_size
+            ));
+        }
+        if !self.avg_size.is_power_of_two() || self.avg_size < 64 {
+            return Err(anyhow::anyhow!("average chunk size {} must be a power of two >= 64", self.avg_size));
+        }
+        Ok(())
+    }
+
+    // FastCDC normalized chunking masks: stricter before the average size, looser after
+    fn masks(&self) -> (u64, u64) {
+        let bits = self.avg_size.trailing_zeros();
+        let high_ones = |n: u32| if n == 0 { 0 } else { u64::MAX << (64 - n) };
+        (high_ones(bits + 1), high_ones(bits - 1))
+    }
+
+    // Length of the next chunk at the start of `data`
+    fn cut_point(&self, data: &[u8]) -> usize {
+        if data.len() <= self.min_size {
+            return data.len();
+        }
+        let (mask_small, mask_large) = self.masks();
+        let end = data.len().min(self.max_size);
+        let normal = end.min(self.avg_size);
+
+        let mut hash: u64 = 0;
+        let mut i = self.min_size;
+        while i < normal {
+            hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+            if hash & mask_small == 0 {
+                return i + 1;
+            }
+            i += 1;
+        }
+        while i < end {
+            hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+            if hash & mask_large == 0 {
+                return i + 1;
+            }
+            i += 1;
+        }
+        end
+    }
+}
+
+/// A content-defined chunk of a stream
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Byte offset of the chunk in the stream
+    pub offset: u64,
+    /// Chunk length in bytes
+    pub length: usize,
+    /// Strong hash of the chunk contents
+    pub digest: Digest,
+}
+
+/// Split a byte slice into content-defined chunks (FastCDC with a Gear rolling hash)
+///
+/// Chunk boundaries depend only on nearby content, so an edit only changes the
+/// chunks around it and the rest of the file still produces the same chunks.
+#[cfg(feature = "hash")]
+pub fn chunk_bytes(data: &[u8], config: &ChunkerConfig) -> anyhow::Result<Vec<Chunk>> {
+    chunk_reader(data, config)
+}
+
+/// Split everything read from `reader` into content-defined chunks
+///
+/// Only about `2 * max_size` bytes are buffered at a time.
+#[cfg(feature = "hash")]
+pub fn chunk_reader<R: std::io::Read>(mut reader: R, config: &ChunkerConfig) -> anyhow::Result<Vec<Chunk>> {
+    config.validate()?;
+
+    let mut chunks = Vec::new();
+    let mut buffer: Vec<u8> = Vec::with_capacity(config.max_size * 2);
+    let mut offset = 0u64;
+    let mut eof = false;
+    loop {
+        // Top up the buffer so a full-size chunk can always be cut
+        while !eof && buffer.len() < config.max_size {
+            let start = buffer.len();
+            buffer.resize(config.max_size * 2, 0);
+            match reader.read(&mut buffer[start..]) {
+                Ok(0) => {
+                    buffer.truncate(start);
+                    eof = true;
+                }
+                Ok(n) => buffer.truncate(start + n),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => buffer.truncate(start),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if buffer.is_empty() {
+            break;
+        }
+
+        let length = config.cut_point(&buffer);
+        chunks.push(Chunk {
+            offset,
+            length,
+            digest: hash_bytes(config.algorithm, &buffer[..length]),
+        });
+        offset += length as u64;
+        buffer.drain(..length);
+    }
+    Ok(chunks)
+}
+
+/// Split a file into content-defined chunks
+#[cfg(feature = "hash")]
+pub fn chunk_file<P: AsRef<std::path::Path>>(path: P, config: &ChunkerConfig) -> anyhow::Result<Vec<Chunk>> {
+    let file = std::fs::File::open(path.as_ref())
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?} for chunking: {}", path.as_ref(), e))?;
+    chunk_reader(std::io::BufReader::new(file), config)
+}
+
+/// One step in rebuilding the new version of a file
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaOp {
+    /// Copy `length` bytes from `old_offset` in the old version the receiver already has
+    Copy { old_offset: u64, length: u64 },
+    /// Take the next `length` bytes of literal data, read from `new_offset` in the new version
+    Literal { new_offset: u64, length: u64 },
+}
+
+/// The chunk-level difference between two versions of a file
+///
+/// The sender computes it from both chunk lists, streams the literal ranges with
+/// `write_literals`, and the receiver rebuilds the new version with `apply`.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkDelta {
+    /// Operations in new-file order; adjacent operations are merged
+    pub ops: Vec<DeltaOp>,
+}
+
+#[cfg(feature = "hash")]
+impl ChunkDelta {
+    /// Compute the delta that turns the version chunked as `old` into the one chunked as `new`
+    /// Both chunk lists must use the same chunk hash algorithm
+    pub fn between(old: &[Chunk], new: &[Chunk]) -> ChunkDelta {
+        let mut known: std::collections::HashMap<&Digest, u64> = std::collections::HashMap::new();
+        for chunk in old {
+            known.entry(&chunk.digest).or_insert(chunk.offset);
+        }
+
+        let mut ops: Vec<DeltaOp> = Vec::new();
+        for chunk in new {
+            let length = chunk.length as u64;
+            let op = match known.get(&chunk.digest) {
+                Some(old_offset) => DeltaOp::Copy { old_offset: *old_offset, length },
+                None => DeltaOp::Literal { new_offset: chunk.offset, length },
+            };
+            // Merge with the previous operation when the ranges are contiguous
+            match (ops.last_mut(), op) {
+                (
+                    Some(DeltaOp::Copy { old_offset: prev, length: prev_len }),
+                    DeltaOp::Copy { old_offset, length },
+                ) if *prev + *prev_len == old_offset => *prev_len += length,
+                (
+                    Some(DeltaOp::Literal { new_offset: prev, length: prev_len }),
+                    DeltaOp::Literal { new_offset, length },
+                ) if *prev + *prev_len == new_offset => *prev_len += length,
+                _ => ops.push(op),
+            }
+        }
+        ChunkDelta { ops }
+    }
+
+    /// Bytes the receiver can reuse from the old version
+    pub fn copied_bytes(&self) -> u64 {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                DeltaOp::Copy { length, .. } => *length,
+                DeltaOp::Literal { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// Bytes that have to be transferred
+    pub fn literal_bytes(&self) -> u64 {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                DeltaOp::Literal { length, .. } => *length,
+                DeltaOp::Copy { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// Sender side: write the literal ranges of the new version to `out`, in operation order
+    pub fn write_literals<R, W>(&self, new: &mut R, out: &mut W) -> std::io::Result<u64>
+    where
+        R: std::io::Read + std::io::Seek,
+        W: std::io::Write,
+    {
+        use std::io::{Read, SeekFrom};
+
+        let mut written = 0;
+        for op in &self.ops {
+            if let DeltaOp::Literal { new_offset, length } = op {
+                new.seek(SeekFrom::Start(*new_offset))?;
+                written += std::io::copy(&mut new.by_ref().take(*length), out)?;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Receiver side: rebuild the new version from the old version and the literal stream
+    pub fn apply<R, L, W>(&self, old: &mut R, literals: &mut L, out: &mut W) -> std::io::Result<u64>
+    where
+        R: std::io::Read + std::io::Seek,
+        L: std::io::Read,
+        W: std::io::Write,
+    {
+        use std::io::{Read, SeekFrom};
+
+        let mut written = 0;
+        for op in &self.ops {
+            let (copied, expected) = match op {
+                DeltaOp::Copy { old_offset, length } => {
+                    old.seek(SeekFrom::Start(*old_offset))?;
+                    (std::io::copy(&mut old.by_ref().take(*length), out)?, *length)
+                }
+                DeltaOp::Literal { length, .. } => {
+                    (std::io::copy(&mut literals.by_ref().take(*length), out)?, *length)
+                }
+            };
+            if copied != expected {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "delta source ended early",
+                ));
+            }
+            written += copied;
+        }
+        Ok(written)
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "hash")]
 mod tests {
@@ -156,4 +2303,631 @@ mod tests {
         let result = crc64_str("\n\t\n\t", None);
         assert_eq!(result, "77A9E4696E2AE9F5");
     }
+
+    #[test]
+    fn test_hash_bytes_known_vectors() {
+        let cases = [
+            (HashAlgorithm::Crc32, "123456789", "cbf43926"),
+            (HashAlgorithm::Md5, "abc", "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                HashAlgorithm::Sha256,
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "",
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (HashAlgorithm::XxHash64, "", "ef46db3751d8e999"),
+            (HashAlgorithm::Xxh3, "", "2d06800538d394c2"),
+        ];
+        for (algorithm, input, expected) in cases {
+            let digest = hash_bytes(algorithm, input.as_bytes());
+            assert_eq!(digest.to_hex(), expected, "{}", algorithm);
+            assert_eq!(digest.as_bytes().len(), algorithm.output_len());
+        }
+    }
+
+    #[test]
+    fn test_hash_crc64_matches_crc64_str() {
+        let digest = hash_bytes(HashAlgorithm::Crc64, b"hello");
+        assert_eq!(digest.to_hex_upper(), crc64_str("hello", None));
+        assert_eq!(digest.to_u64(), Some(0xD6B0DD10CB6676A2));
+    }
+
+    #[test]
+    fn test_hasher_streaming_matches_one_shot() {
+        use std::io::Write;
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        for algorithm in HashAlgorithm::ALL {
+            let mut hasher = Hasher::new(algorithm);
+            for chunk in data.chunks(7777) {
+                hasher.write_all(chunk).unwrap();
+            }
+            assert_eq!(hasher.finalize(), hash_bytes(algorithm, &data), "{}", algorithm);
+
+            let from_reader = hash_reader(algorithm, std::io::Cursor::new(&data)).unwrap();
+            assert_eq!(from_reader, hash_bytes(algorithm, &data));
+        }
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let digest = hash_file(HashAlgorithm::Sha256, &path).unwrap();
+        assert_eq!(digest, hash_bytes(HashAlgorithm::Sha256, b"abc"));
+        assert!(hash_file(HashAlgorithm::Sha256, dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_digest_encodings() {
+        let digest = hash_bytes(HashAlgorithm::Md5, b"abc");
+        assert_eq!(digest.to_base64(), "kAFQmDzST7DWlj99KOF/cg==");
+        assert_eq!(digest.to_string(), digest.to_hex());
+        assert_eq!(digest.to_hex_upper(), digest.to_hex().to_uppercase());
+        assert_eq!(digest.to_u64(), None);
+        assert_eq!(digest.clone().into_bytes(), digest.as_bytes());
+    }
+
+    #[test]
+    fn test_hash_algorithm_from_str() {
+        assert_eq!("SHA-256".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::Sha256);
+        assert_eq!("xxhash64".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::XxHash64);
+        assert!("sha3".parse::<HashAlgorithm>().is_err());
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(algorithm.name().parse::<HashAlgorithm>().unwrap(), algorithm);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn create_bundle() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("bin/lib")).unwrap();
+        std::fs::write(dir.path().join("README"), b"release notes").unwrap();
+        std::fs::write(dir.path().join("bin/app"), b"binary").unwrap();
+        std::fs::write(dir.path().join("bin/lib/core.so"), b"library").unwrap();
+        dir
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_manifest_generate() {
+        let dir = create_bundle();
+        let manifest = Manifest::generate_with_threads(dir.path(), HashAlgorithm::Sha256, 2).unwrap();
+
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["README", "bin/app", "bin/lib/core.so"]);
+        assert_eq!(manifest.files[1].digest, hash_bytes(HashAlgorithm::Sha256, b"binary").to_hex());
+        assert_eq!(manifest.files[1].size, Some(6));
+        assert!(manifest.files[1].modified.is_some());
+        assert!(Manifest::generate(&dir.path().join("README"), HashAlgorithm::Sha256).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_manifest_text_and_json_round_trip() {
+        let dir = create_bundle();
+        let manifest = Manifest::generate(dir.path(), HashAlgorithm::Sha256).unwrap();
+
+        let text = manifest.to_checksum_text();
+        assert!(text.lines().all(|line| line.len() > 66 && &line[64..66] == "  "));
+        let from_text = Manifest::from_checksum_text(&text, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(from_text.files.len(), 3);
+        assert_eq!(from_text.files[0].digest, manifest.files[0].digest);
+        assert_eq!(from_text.files[0].size, None);
+        assert!(Manifest::from_checksum_text("abc  file", HashAlgorithm::Sha256).is_err());
+
+        // Manifests are written outside the tree they describe
+        let out = tempfile::tempdir().unwrap();
+        let json_path = out.path().join("manifest.json");
+        manifest.write_json_file(&json_path).unwrap();
+        assert_eq!(Manifest::load(&json_path).unwrap(), manifest);
+
+        let text_path = out.path().join("manifest.sha256");
+        manifest.write_checksum_file(&text_path).unwrap();
+        assert_eq!(Manifest::load(&text_path).unwrap(), from_text);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_manifest_checksum_text_escaped_paths() {
+        let digest = hash_bytes(HashAlgorithm::Sha256, b"x").to_hex();
+        // As printed by GNU sha256sum for "a\\b" and "line\nbreak"
+        let text = format!("\\{d}  a\\\\b\n\\{d} *line\\nbreak\n{d}  plain\n", d = digest);
+        let manifest = Manifest::from_checksum_text(&text, HashAlgorithm::Sha256).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a\\b", "line\nbreak", "plain"]);
+        assert_eq!(manifest.files[0].digest, digest);
+
+        // Written back the same way and read again unchanged
+        let written = manifest.to_checksum_text();
+        assert!(written.contains(&format!("\\{}  a\\\\b\n", digest)));
+        assert!(written.contains(&format!("\\{}  line\\nbreak\n", digest)));
+        assert_eq!(Manifest::from_checksum_text(&written, HashAlgorithm::Sha256).unwrap(), manifest);
+
+        let bad = format!("\\{}  a\\x\n", digest);
+        assert!(Manifest::from_checksum_text(&bad, HashAlgorithm::Sha256).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_manifest_verify() {
+        let dir = create_bundle();
+        let manifest = Manifest::generate(dir.path(), HashAlgorithm::Blake3).unwrap();
+
+        let report = manifest.verify(dir.path()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.matched, 3);
+
+        // Same size, different content; different size; removed; added
+        std::fs::write(dir.path().join("bin/app"), b"BINARY").unwrap();
+        std::fs::write(dir.path().join("README"), b"changed release notes").unwrap();
+        std::fs::remove_file(dir.path().join("bin/lib/core.so")).unwrap();
+        std::fs::write(dir.path().join("bin/extra"), b"x").unwrap();
+
+        let report = manifest.verify(dir.path()).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.modified, vec!["README", "bin/app"]);
+        assert_eq!(report.missing, vec!["bin/lib/core.so"]);
+        assert_eq!(report.extra, vec!["bin/extra"]);
+        assert_eq!(report.matched, 0);
+    }
+
+    #[test]
+    fn test_hmac_rfc4231_vectors() {
+        // RFC 4231 test case 2
+        let key = b"Jefe";
+        let data = b"what do ya want for nothing?";
+        assert_eq!(
+            hmac_sha256_hex(key, data),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hmac_sha512_hex(key, data),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn test_hmac_verify() {
+        let tag = hmac_bytes(HmacAlgorithm::Sha256, b"key", b"message");
+        assert!(hmac_verify(HmacAlgorithm::Sha256, b"key", b"message", &tag));
+        assert!(!hmac_verify(HmacAlgorithm::Sha256, b"other", b"message", &tag));
+        assert!(!hmac_verify(HmacAlgorithm::Sha512, b"key", b"message", &tag));
+        assert!(!hmac_verify(HmacAlgorithm::Sha256, b"key", b"message", &tag[..16]));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+
+    fn symbols(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("SYM{:05}", i)).collect()
+    }
+
+    fn count_by_node<S: NodeSelector>(selector: &S, keys: &[String]) -> std::collections::HashMap<String, usize> {
+        let mut counts = std::collections::HashMap::new();
+        for key in keys {
+            *counts.entry(selector.node_for(key).unwrap().to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_consistent_ring_distribution_and_weights() {
+        let keys = symbols(20_000);
+        let mut ring = ConsistentRing::new(200);
+        assert_eq!(ring.get("AAPL"), None);
+
+        ring.add_node("a", 1).unwrap();
+        ring.add_node("b", 1).unwrap();
+        ring.add_node("c", 2).unwrap();
+        assert_eq!(ring.len(), 3);
+
+        let counts = count_by_node(&ring, &keys);
+        // Weight 2 should get roughly half of the keys
+        let c_share = counts["c"] as f64 / keys.len() as f64;
+        assert!((0.4..0.6).contains(&c_share), "share {}", c_share);
+        assert!(counts["a"] > 3_000 && counts["b"] > 3_000);
+
+        // Assignment is stable across identical rings
+        let mut other = ConsistentRing::new(200);
+        other.add_node("c", 2).unwrap();
+        other.add_node("b", 1).unwrap();
+        other.add_node("a", 1).unwrap();
+        assert!(moved_keys(&keys, &ring, &other).is_empty());
+    }
+
+    #[test]
+    fn test_consistent_ring_minimal_movement() {
+        let keys = symbols(10_000);
+        let mut before = ConsistentRing::new(200);
+        for node in ["a", "b", "c", "d"] {
+            before.add_node(node, 1).unwrap();
+        }
+
+        // Joining: keys only move to the new node, about 1/5 of them
+        let mut joined = before.clone();
+        joined.add_node("e", 1).unwrap();
+        let moves = moved_keys(&keys, &before, &joined);
+        assert!(moves.iter().all(|m| m.to.as_deref() == Some("e")));
+        assert!(moves.len() > 1_000 && moves.len() < 3_000, "moved {}", moves.len());
+
+        // Leaving: only keys owned by the removed node move
+        let mut left = before.clone();
+        assert!(left.remove_node("b"));
+        assert!(!left.remove_node("b"));
+        let moves = moved_keys(&keys, &before, &left);
+        assert!(moves.iter().all(|m| m.from.as_deref() == Some("b")));
+        assert_eq!(moves.len(), keys.iter().filter(|k| before.get(k) == Some("b")).count());
+    }
+
+    #[test]
+    fn test_consistent_ring_get_n() {
+        let mut ring = ConsistentRing::new(50);
+        ring.add_node("a", 1).unwrap();
+        ring.add_node("b", 1).unwrap();
+        ring.add_node("c", 1).unwrap();
+
+        let replicas = ring.get_n("AAPL", 2);
+        assert_eq!(replicas.len(), 2);
+        assert_ne!(replicas[0], replicas[1]);
+        assert_eq!(replicas[0], ring.get("AAPL").unwrap());
+        assert_eq!(ring.get_n("AAPL", 10).len(), 3);
+
+        ring.add_node("a", 0).unwrap();
+        assert_eq!(ring.len(), 2);
+    }
+
+    #[test]
+    fn test_consistent_ring_weights_and_limits() {
+        let keys = symbols(5_000);
+        let mut ring = ConsistentRing::new(100);
+        assert!(ring.add_node("huge", u32::MAX).is_err());
+        assert!(ring.add_node("huge", MAX_RING_WEIGHT + 1).is_err());
+        assert!(ring.is_empty());
+
+        // Changing weights incrementally matches a ring built with the final weights
+        ring.add_node("a", 3).unwrap();
+        ring.add_node("b", 1).unwrap();
+        ring.add_node("a", 1).unwrap();
+        ring.add_node("c", 2).unwrap();
+        assert!(ring.remove_node("c"));
+        let mut fresh = ConsistentRing::new(100);
+        fresh.add_node("b", 1).unwrap();
+        fresh.add_node("a", 1).unwrap();
+        assert!(moved_keys(&keys, &ring, &fresh).is_empty());
+        assert_eq!(ring.ring.len(), 200);
+    }
+
+    #[test]
+    fn test_rendezvous_hasher() {
+        let keys = symbols(10_000);
+        let mut hrw = RendezvousHasher::new();
+        assert_eq!(hrw.get("AAPL"), None);
+        for node in ["a", "b", "c", "d"] {
+            hrw.add_node(node, 1.0);
+        }
+        let counts = count_by_node(&hrw, &keys);
+        assert!(counts.values().all(|c| *c > 2_000 && *c < 3_000), "{:?}", counts);
+
+        // Only keys owned by the removed node move
+        let mut left = hrw.clone();
+        left.remove_node("c");
+        let moves = moved_keys(&keys, &hrw, &left);
+        assert!(moves.iter().all(|m| m.from.as_deref() == Some("c")));
+        assert_eq!(moves.len(), counts["c"]);
+
+        let top = hrw.get_n("AAPL", 2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0], hrw.get("AAPL").unwrap());
+    }
+
+    #[test]
+    fn test_rendezvous_hasher_weights() {
+        let keys = symbols(10_000);
+        let mut hrw = RendezvousHasher::new();
+        hrw.add_node("small", 1.0);
+        hrw.add_node("large", 3.0);
+        let counts = count_by_node(&hrw, &keys);
+        let large_share = counts["large"] as f64 / keys.len() as f64;
+        assert!((0.7..0.8).contains(&large_share), "share {}", large_share);
+    }
+
+    #[test]
+    fn test_bloom_filter_membership_and_fp_rate() {
+        let mut bloom = BloomFilter::with_false_positive_rate(10_000, 0.01);
+        assert!(bloom.is_empty());
+        for i in 0..10_000 {
+            bloom.insert(format!("line-{}", i).as_bytes());
+        }
+        // No false negatives
+        assert!((0..10_000).all(|i| bloom.contains(format!("line-{}", i).as_bytes())));
+
+        let false_positives = (0..10_000)
+            .filter(|i| bloom.contains(format!("other-{}", i).as_bytes()))
+            .count();
+        assert!(false_positives < 200, "false positives {}", false_positives);
+        assert!(bloom.estimated_false_positive_rate() < 0.02);
+        assert!(!bloom.insert(b"line-1"));
+    }
+
+    #[test]
+    fn test_bloom_filter_serialization() {
+        let mut bloom = BloomFilter::new(1024, 4);
+        bloom.insert(b"AAPL");
+        bloom.insert(b"MSFT");
+
+        let restored = BloomFilter::from_bytes(&bloom.to_bytes()).unwrap();
+        assert_eq!(restored, bloom);
+        assert!(BloomFilter::from_bytes(b"ACBF").is_err());
+        let mut corrupt = bloom.to_bytes();
+        corrupt.pop();
+        assert!(BloomFilter::from_bytes(&corrupt).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seen.bloom");
+        bloom.save(&path).unwrap();
+        let loaded = BloomFilter::load(&path).unwrap();
+        assert!(loaded.contains(b"AAPL") && loaded.contains(b"MSFT"));
+        assert_eq!(loaded.len(), 2);
+    }
+
+    #[test]
+    fn test_bloom_filter_merge() {
+        let mut a = BloomFilter::new(2048, 3);
+        let mut b = BloomFilter::new(2048, 3);
+        a.insert(b"x");
+        b.insert(b"y");
+        a.merge(&b).unwrap();
+        assert!(a.contains(b"x") && a.contains(b"y"));
+        assert_eq!(a.len(), 2);
+        assert!(a.merge(&BloomFilter::new(4096, 3)).is_err());
+
+        // Overlapping sets are not counted twice
+        let mut left = BloomFilter::with_false_positive_rate(10_000, 0.01);
+        let mut right = BloomFilter::with_false_positive_rate(10_000, 0.01);
+        for i in 0..1_000u32 {
+            left.insert(&i.to_le_bytes());
+            right.insert(&(i + 500).to_le_bytes());
+        }
+        left.merge(&right).unwrap();
+        assert!((1_400..=1_600).contains(&left.len()), "len {}", left.len());
+
+        a.clear();
+        assert!(a.is_empty());
+        assert!(!a.contains(b"x"));
+    }
+
+    #[test]
+    fn test_count_min_sketch() {
+        let mut cms = CountMinSketch::with_error(0.001, 0.01);
+        for i in 0..1_000u64 {
+            cms.add(format!("ticker-{}", i % 100).as_bytes(), 1);
+        }
+        cms.add(b"hot", 500);
+
+        assert_eq!(cms.total(), 1_500);
+        // Never undercounts, and overcount stays within epsilon * total
+        let hot = cms.estimate(b"hot");
+        assert!((500..=502).contains(&hot), "estimate {}", hot);
+        let ticker = cms.estimate(b"ticker-7");
+        assert!((10..=12).contains(&ticker), "estimate {}", ticker);
+
+        let mut other = CountMinSketch::with_error(0.001, 0.01);
+        other.add(b"hot", 5);
+        cms.merge(&other).unwrap();
+        assert!(cms.estimate(b"hot") >= 505);
+        assert!(cms.merge(&CountMinSketch::new(10, 2)).is_err());
+    }
+
+    #[test]
+    fn test_hyperloglog_cardinality() {
+        for expected in [100u64, 10_000, 200_000] {
+            let mut hll = HyperLogLog::default();
+            for i in 0..expected {
+                hll.add(format!("event-{}", i).as_bytes());
+                // Duplicates do not change the estimate
+                hll.add(format!("event-{}", i / 2).as_bytes());
+            }
+            let estimate = hll.count() as f64;
+            let error = (estimate - expected as f64).abs() / expected as f64;
+            assert!(error < 0.03, "expected {} got {}", expected, estimate);
+        }
+        assert_eq!(HyperLogLog::new(10).count(), 0);
+    }
+
+    #[test]
+    fn test_hyperloglog_merge() {
+        let mut a = HyperLogLog::new(12);
+        let mut b = HyperLogLog::new(12);
+        for i in 0..5_000 {
+            a.add(format!("a-{}", i).as_bytes());
+            b.add(format!("b-{}", i).as_bytes());
+        }
+        a.merge(&b).unwrap();
+        let estimate = a.count() as f64;
+        assert!((estimate - 10_000.0).abs() / 10_000.0 < 0.05, "estimate {}", estimate);
+        assert!(a.merge(&HyperLogLog::new(13)).is_err());
+    }
+
+    // Deterministic pseudo-random bytes for chunking tests
+    fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunker_config_validation() {
+        assert!(ChunkerConfig::new(1024, 4096, 16384).is_ok());
+        assert!(ChunkerConfig::new(0, 4096, 16384).is_err());
+        assert!(ChunkerConfig::new(8192, 4096, 16384).is_err());
+        assert!(ChunkerConfig::new(1024, 5000, 16384).is_err());
+        assert!(ChunkerConfig::new(1024, 4096, 2048).is_err());
+    }
+
+    #[test]
+    fn test_chunk_bytes_sizes_and_coverage() {
+        let config = ChunkerConfig::new(1024, 4096, 16384).unwrap();
+        let data = random_bytes(500_000, 1);
+        let chunks = chunk_bytes(&data, &config).unwrap();
+
+        // Chunks cover the input exactly, in order
+        let mut offset = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.offset, offset);
+            assert!(chunk.length <= config.max_size);
+            if i + 1 < chunks.len() {
+                assert!(chunk.length >= config.min_size);
+            }
+            let range = chunk.offset as usize..chunk.offset as usize + chunk.length;
+            assert_eq!(chunk.digest, hash_bytes(HashAlgorithm::Blake3, &data[range]));
+            offset += chunk.length as u64;
+        }
+        assert_eq!(offset, data.len() as u64);
+
+        let average = data.len() / chunks.len();
+        assert!(average > 2_000 && average < 10_000, "average {}", average);
+        assert!(chunk_bytes(&[], &config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chunk_reader_matches_chunk_file() {
+        let config = ChunkerConfig::default().with_algorithm(HashAlgorithm::Sha256);
+        let data = random_bytes(300_000, 2);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, &data).unwrap();
+
+        assert_eq!(chunk_file(&path, &config).unwrap(), chunk_bytes(&data, &config).unwrap());
+    }
+
+    #[test]
+    fn test_chunk_delta_after_insertion() {
+        use std::io::Cursor;
+
+        let config = ChunkerConfig::new(1024, 4096, 16384).unwrap();
+        let old = random_bytes(400_000, 3);
+        let mut new = old.clone();
+        // Insert a changed row in the middle and edit near the end
+        new.splice(200_000..200_000, b"inserted row\n".iter().copied());
+        new[350_000] ^= 0xFF;
+
+        let old_chunks = chunk_bytes(&old, &config).unwrap();
+        let new_chunks = chunk_bytes(&new, &config).unwrap();
+        let delta = ChunkDelta::between(&old_chunks, &new_chunks);
+
+        assert_eq!(delta.copied_bytes() + delta.literal_bytes(), new.len() as u64);
+        assert!(delta.literal_bytes() < 60_000, "literal {}", delta.literal_bytes());
+
+        let mut literals = Vec::new();
+        let sent = delta.write_literals(&mut Cursor::new(&new), &mut literals).unwrap();
+        assert_eq!(sent, delta.literal_bytes());
+
+        let mut rebuilt = Vec::new();
+        delta
+            .apply(&mut Cursor::new(&old), &mut Cursor::new(&literals), &mut rebuilt)
+            .unwrap();
+        assert_eq!(rebuilt, new);
+
+        // A truncated literal stream is an error
+        let mut short = Vec::new();
+        let result = delta.apply(&mut Cursor::new(&old), &mut Cursor::new(&literals[..10]), &mut short);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_chunk_delta_identical_files() {
+        let config = ChunkerConfig::default();
+        let data = random_bytes(100_000, 4);
+        let chunks = chunk_bytes(&data, &config).unwrap();
+        let delta = ChunkDelta::between(&chunks, &chunks);
+        assert_eq!(delta.literal_bytes(), 0);
+        assert_eq!(delta.ops, vec![DeltaOp::Copy { old_offset: 0, length: 100_000 }]);
+    }
+
+    #[test]
+    fn test_crc_catalog_check_values() {
+        for params in CRC_CATALOG {
+            let crc = Crc::new(params).unwrap();
+            assert_eq!(crc.verify_check(), Some(true), "{}", params.name);
+        }
+    }
+
+    #[test]
+    fn test_crc_slice_by_8_matches_bytewise() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 % 256) as u8).collect();
+        for params in CRC_CATALOG {
+            let crc = Crc::new(params).unwrap();
+            // Feeding one byte at a time never takes the 8-byte path
+            let mut digest = crc.digest();
+            for byte in &data {
+                digest.update(std::slice::from_ref(byte));
+            }
+            assert_eq!(digest.finalize(), crc.checksum(&data), "{}", params.name);
+        }
+    }
+
+    #[test]
+    fn test_crc_matches_existing_implementations() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        let crc32 = Crc::new(CRC_32_ISO_HDLC).unwrap();
+        assert_eq!(crc32.checksum(data), crc32fast::hash(data) as u64);
+        assert_eq!(
+            Crc::new(CRC_64_REDIS).unwrap().checksum(b"hello"),
+            hash_bytes(HashAlgorithm::Crc64, b"hello").to_u64().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_crc_custom_params() {
+        // CRC-8/SMBUS: width=8 poly=0x07 init=0x00 refin=false refout=false xorout=0x00 check=0xf4
+        let crc8 = Crc::new(CrcParams::new(8, 0x07, 0, false, false, 0)).unwrap();
+        assert_eq!(crc8.checksum(b"123456789"), 0xF4);
+        assert_eq!(crc8.verify_check(), None);
+
+        // CRC-12/UMTS reflects the output but not the input: check=0xdaf
+        let crc12 = Crc::new(CrcParams::new(12, 0x80F, 0, false, true, 0)).unwrap();
+        assert_eq!(crc12.checksum(b"123456789"), 0xDAF);
+
+        assert!(Crc::new(CrcParams::new(0, 0x07, 0, false, false, 0)).is_err());
+        assert!(Crc::new(CrcParams::new(8, 0x107, 0, false, false, 0)).is_err());
+    }
+
+    #[test]
+    fn test_crc_params_by_name() {
+        assert_eq!(CrcParams::by_name("crc-32c"), Some(CRC_32_ISCSI));
+        assert_eq!(CrcParams::by_name("CRC-16/CCITT-FALSE"), Some(CRC_16_IBM_3740));
+        assert_eq!(CrcParams::by_name("CRC-64/Jones"), Some(CRC_64_REDIS));
+        assert_eq!(CrcParams::by_name("CRC-16/MODBUS"), Some(CRC_16_MODBUS));
+        assert_eq!(CrcParams::by_name("CRC-99"), None);
+    }
+
+    #[test]
+    fn test_crc_digest_streaming_write() {
+        use std::io::Write;
+
+        let crc = Crc::new(CRC_64_XZ).unwrap();
+        let mut digest = crc.digest();
+        digest.write_all(b"12345").unwrap();
+        digest.write_all(b"6789").unwrap();
+        assert_eq!(digest.finalize(), 0x995D_C9BB_DF19_39FA);
+    }
 }
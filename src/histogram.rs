@@ -0,0 +1,57 @@
+// Bucket counting shared by metrics::Histogram and time::DurationHistogram
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Counts observations by bucket upper bound, plus an overflow bucket
+// Each bucket counts values less than or equal to its bound.
+#[derive(Debug)]
+pub(crate) struct BucketCounts {
+    bounds: Vec<f64>,
+    // One counter per bound plus the overflow bucket, not cumulative
+    counts: Vec<AtomicU64>,
+    count: AtomicU64,
+    // f64 bit pattern
+    sum: AtomicU64,
+}
+
+impl BucketCounts {
+    // Creates empty counts; the bounds must be strictly increasing and not NaN
+    pub(crate) fn new(bounds: Vec<f64>) -> Self {
+        let counts = (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect();
+        BucketCounts {
+            bounds,
+            counts,
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    // Returns the bucket upper bounds, without the overflow bucket
+    pub(crate) fn bounds(&self) -> &[f64] {
+        &self.bounds
+    }
+
+    // Records one observation
+    pub(crate) fn observe(&self, value: f64) {
+        let index = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[index].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self.sum.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + value).to_bits())
+        });
+    }
+
+    // Returns the number of observations
+    pub(crate) fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    // Returns the sum of all observations
+    pub(crate) fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+
+    // Returns the per-bucket counts, not cumulative, ending with the overflow bucket
+    pub(crate) fn counts(&self) -> Vec<u64> {
+        self.counts.iter().map(|c| c.load(Ordering::Relaxed)).collect()
+    }
+}
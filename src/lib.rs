@@ -10,12 +10,18 @@ pub mod fs;
 #[cfg(feature = "time")]
 pub mod time;
 
+#[cfg(any(feature = "time", feature = "metrics"))]
+mod histogram;
+
 #[cfg(feature = "hash")]
 pub mod hash;
 
 #[cfg(feature = "trace")]
 pub mod trace;
 
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
 #[cfg(feature = "proto")]
 pub mod proto;
 
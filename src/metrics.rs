@@ -0,0 +1,1174 @@
+// External crate imports
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result as AnyResult};
+
+/// Default histogram bucket upper bounds, in seconds (5ms to 10s)
+#[cfg(feature = "metrics")]
+pub const DEFAULT_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Content type of the Prometheus text exposition format
+#[cfg(feature = "metrics")]
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Create `count` bucket bounds starting at `start`, each `factor` times the previous
+///
+/// # Errors
+/// Returns an error if `count` is zero, `start` is not positive or `factor` is not above 1.
+///
+/// # Example
+/// ```
+/// use acovo::metrics::exponential_buckets;
+///
+/// assert_eq!(exponential_buckets(1.0, 2.0, 4).unwrap(), vec![1.0, 2.0, 4.0, 8.0]);
+/// ```
+#[cfg(feature = "metrics")]
+pub fn exponential_buckets(start: f64, factor: f64, count: usize) -> AnyResult<Vec<f64>> {
+    if count == 0 || start.is_nan() || start <= 0.0 || factor.is_nan() || factor <= 1.0 {
+        return Err(anyhow!(
+            "invalid exponential buckets: start {} factor {} count {}",
+            start,
+            factor,
+            count
+        ));
+    }
+    Ok((0..count).scan(start, |next, _| {
+        let bound = *next;
+        *next *= factor;
+        Some(bound)
+    }).collect())
+}
+
+/// Create `count` bucket bounds starting at `start`, `width` apart
+///
+/// # Errors
+/// Returns an error if `count` is zero or `width` is not positive.
+#[cfg(feature = "metrics")]
+pub fn linear_buckets(start: f64, width: f64, count: usize) -> AnyResult<Vec<f64>> {
+    if count == 0 || !start.is_finite() || width.is_nan() || width <= 0.0 {
+        return Err(anyhow!(
+            "invalid linear buckets: start {} width {} count {}",
+            start,
+            width,
+            count
+        ));
+    }
+    Ok((0..count).map(|i| start + width * i as f64).collect())
+}
+
+/// A monotonically increasing count, such as requests served
+///
+/// Clones share the same value.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    value: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "metrics")]
+impl Counter {
+    /// Create a counter that is not attached to any registry
+    pub fn new() -> Self {
+        Counter::default()
+    }
+
+    /// Add one
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    /// Add `n`
+    pub fn inc_by(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Current value
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// A value that can go up and down, such as queue depth or temperature
+///
+/// Clones share the same value.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default)]
+pub struct Gauge {
+    // f64 bit pattern
+    bits: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "metrics")]
+impl Gauge {
+    /// Create a gauge that is not attached to any registry
+    pub fn new() -> Self {
+        Gauge::default()
+    }
+
+    /// Set the value
+    pub fn set(&self, value: f64) {
+        self.bits.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Add `delta`, which may be negative
+    pub fn add(&self, delta: f64) {
+        let _ = self.bits.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + delta).to_bits())
+        });
+    }
+
+    /// Subtract `delta`
+    pub fn sub(&self, delta: f64) {
+        self.add(-delta);
+    }
+
+    /// Add one
+    pub fn inc(&self) {
+        self.add(1.0);
+    }
+
+    /// Subtract one
+    pub fn dec(&self) {
+        self.add(-1.0);
+    }
+
+    /// Current value
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+}
+
+/// A distribution of observed values, such as request latency in seconds
+///
+/// Each bucket counts observations less than or equal to its upper bound.
+/// Clones share the same data. The counting is shared with
+/// `time::DurationHistogram`.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    core: Arc<crate::histogram::BucketCounts>,
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    /// Create a histogram with the given bucket upper bounds
+    ///
+    /// A trailing `+Inf` bound is accepted and ignored; the `+Inf` bucket is
+    /// always present.
+    ///
+    /// # Errors
+    /// Returns an error if the bounds are empty, contain NaN or are not strictly increasing.
+    pub fn new(buckets: &[f64]) -> AnyResult<Self> {
+        let mut bounds = buckets.to_vec();
+        if bounds.last() == Some(&f64::INFINITY) {
+            bounds.pop();
+        }
+        if bounds.is_empty() {
+            return Err(anyhow!("histogram needs at least one finite bucket"));
+        }
+        if bounds.iter().any(|b| b.is_nan()) || bounds.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow!("histogram buckets must be strictly increasing: {:?}", buckets));
+        }
+        Ok(Histogram {
+            core: Arc::new(crate::histogram::BucketCounts::new(bounds)),
+        })
+    }
+
+    /// Record one observation
+    pub fn observe(&self, value: f64) {
+        self.core.observe(value);
+    }
+
+    /// Record a duration in seconds
+    pub fn observe_duration(&self, elapsed: Duration) {
+        self.observe(elapsed.as_secs_f64());
+    }
+
+    /// Start a timer that records the elapsed seconds when dropped
+    pub fn start_timer(&self) -> HistogramTimer {
+        HistogramTimer {
+            histogram: self.clone(),
+            start: Instant::now(),
+            armed: true,
+        }
+    }
+
+    /// Number of observations
+    pub fn count(&self) -> u64 {
+        self.core.count()
+    }
+
+    /// Sum of all observations
+    pub fn sum(&self) -> f64 {
+        self.core.sum()
+    }
+
+    /// Cumulative bucket counts as (upper bound, count), ending with `+Inf`
+    pub fn buckets(&self) -> Vec<(f64, u64)> {
+        let mut seen = 0;
+        self.core
+            .bounds()
+            .iter()
+            .copied()
+            .chain(std::iter::once(f64::INFINITY))
+            .zip(self.core.counts())
+            .map(|(bound, count)| {
+                seen += count;
+                (bound, seen)
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(feature = "metrics", feature = "time"))]
+impl crate::time::DurationRecorder for Histogram {
+    fn record(&self, _name: &str, elapsed: Duration) {
+        self.observe_duration(elapsed);
+    }
+}
+
+/// A guard that records the time until it is dropped into a histogram
+#[cfg(feature = "metrics")]
+#[derive(Debug)]
+pub struct HistogramTimer {
+    histogram: Histogram,
+    start: Instant,
+    armed: bool,
+}
+
+#[cfg(feature = "metrics")]
+impl HistogramTimer {
+    /// Record now and return the elapsed time
+    pub fn observe(mut self) -> Duration {
+        self.armed = false;
+        let elapsed = self.start.elapsed();
+        self.histogram.observe_duration(elapsed);
+        elapsed
+    }
+
+    /// Drop the timer without recording anything
+    pub fn discard(mut self) {
+        self.armed = false;
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for HistogramTimer {
+    fn drop(&mut self) {
+        if self.armed {
+            self.histogram.observe_duration(self.start.elapsed());
+        }
+    }
+}
+
+/// An instrument that can live in a `MetricVec` and be rendered by a `Registry`
+///
+/// Implemented by `Counter`, `Gauge` and `Histogram`.
+#[cfg(feature = "metrics")]
+pub trait Metric: Clone + Send + Sync + 'static {
+    /// Prometheus type name, e.g. "counter"
+    fn metric_type() -> &'static str;
+
+    /// A new, empty instrument with the same configuration (e.g. buckets)
+    fn new_like(&self) -> Self;
+
+    /// Append the sample lines for one label set to `out`
+    ///
+    /// `labels` holds the already escaped `name="value"` pairs.
+    fn encode(&self, name: &str, labels: &[String], out: &mut String);
+}
+
+#[cfg(feature = "metrics")]
+impl Metric for Counter {
+    fn metric_type() -> &'static str {
+        "counter"
+    }
+
+    fn new_like(&self) -> Self {
+        Counter::new()
+    }
+
+    fn encode(&self, name: &str, labels: &[String], out: &mut String) {
+        let _ = writeln!(out, "{}{} {}", name, label_block(labels, None), self.get());
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Metric for Gauge {
+    fn metric_type() -> &'static str {
+        "gauge"
+    }
+
+    fn new_like(&self) -> Self {
+        Gauge::new()
+    }
+
+    fn encode(&self, name: &str, labels: &[String], out: &mut String) {
+        let _ = writeln!(out, "{}{} {}", name, label_block(labels, None), format_value(self.get()));
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Metric for Histogram {
+    fn metric_type() -> &'static str {
+        "histogram"
+    }
+
+    fn new_like(&self) -> Self {
+        Histogram {
+            core: Arc::new(crate::histogram::BucketCounts::new(self.core.bounds().to_vec())),
+        }
+    }
+
+    fn encode(&self, name: &str, labels: &[String], out: &mut String) {
+        for (bound, count) in self.buckets() {
+            let le = format!("le=\"{}\"", format_value(bound));
+            let _ = writeln!(out, "{}_bucket{} {}", name, label_block(labels, Some(&le)), count);
+        }
+        let block = label_block(labels, None);
+        let _ = writeln!(out, "{}_sum{} {}", name, block, format_value(self.sum()));
+        let _ = writeln!(out, "{}_count{} {}", name, block, self.count());
+    }
+}
+
+#[cfg(feature = "metrics")]
+struct VecCore<M> {
+    label_names: Vec<String>,
+    template: M,
+    children: Mutex<BTreeMap<Vec<String>, M>>,
+}
+
+/// A family of instruments of one type, keyed by label values
+///
+/// # Example
+/// ```
+/// use acovo::metrics::Registry;
+///
+/// let registry = Registry::new();
+/// let requests = registry
+///     .counter_vec("app_requests_total", "Requests served", &["method", "status"])
+///     .unwrap();
+/// requests.with_label_values(&["GET", "200"]).inc();
+/// assert_eq!(requests.with_label_values(&["GET", "200"]).get(), 1);
+/// ```
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+pub struct MetricVec<M: Metric> {
+    core: Arc<VecCore<M>>,
+}
+
+/// Counters keyed by label values
+#[cfg(feature = "metrics")]
+pub type CounterVec = MetricVec<Counter>;
+
+/// Gauges keyed by label values
+#[cfg(feature = "metrics")]
+pub type GaugeVec = MetricVec<Gauge>;
+
+/// Histograms keyed by label values
+#[cfg(feature = "metrics")]
+pub type HistogramVec = MetricVec<Histogram>;
+
+#[cfg(feature = "metrics")]
+impl<M: Metric> MetricVec<M> {
+    fn new(label_names: Vec<String>, template: M) -> Self {
+        MetricVec {
+            core: Arc::new(VecCore {
+                label_names,
+                template,
+                children: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    fn children(&self) -> MutexGuard<'_, BTreeMap<Vec<String>, M>> {
+        self.core.children.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Label names, in declaration order
+    pub fn label_names(&self) -> &[String] {
+        &self.core.label_names
+    }
+
+    /// The instrument for these label values, created on first use
+    ///
+    /// # Errors
+    /// Returns an error if the number of values does not match the label names.
+    pub fn try_with_label_values(&self, values: &[&str]) -> AnyResult<M> {
+        if values.len() != self.core.label_names.len() {
+            return Err(anyhow!(
+                "expected {} label values {:?}, got {}",
+                self.core.label_names.len(),
+                self.core.label_names,
+                values.len()
+            ));
+        }
+        let key: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        Ok(self
+            .children()
+            .entry(key)
+            .or_insert_with(|| self.core.template.new_like())
+            .clone())
+    }
+
+    /// The instrument for these label values, created on first use
+    ///
+    /// # Panics
+    /// Panics if the number of values does not match the label names.
+    pub fn with_label_values(&self, values: &[&str]) -> M {
+        self.try_with_label_values(values).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Remove the instrument for these label values; returns true if it existed
+    pub fn remove_label_values(&self, values: &[&str]) -> bool {
+        let key: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        self.children().remove(&key).is_some()
+    }
+
+    /// Remove every label set
+    pub fn reset(&self) {
+        self.children().clear();
+    }
+
+    fn encode(&self, name: &str, out: &mut String) {
+        for (values, metric) in self.children().iter() {
+            let labels: Vec<String> = self
+                .core
+                .label_names
+                .iter()
+                .zip(values)
+                .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+                .collect();
+            metric.encode(name, &labels, out);
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+enum FamilyKind {
+    Counter(CounterVec),
+    Gauge(GaugeVec),
+    Histogram(HistogramVec),
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+struct Family {
+    help: String,
+    kind: FamilyKind,
+}
+
+#[cfg(feature = "metrics")]
+impl Family {
+    fn metric_type(&self) -> &'static str {
+        match self.kind {
+            FamilyKind::Counter(_) => Counter::metric_type(),
+            FamilyKind::Gauge(_) => Gauge::metric_type(),
+            FamilyKind::Histogram(_) => Histogram::metric_type(),
+        }
+    }
+
+    fn label_names(&self) -> &[String] {
+        match &self.kind {
+            FamilyKind::Counter(v) => v.label_names(),
+            FamilyKind::Gauge(v) => v.label_names(),
+            FamilyKind::Histogram(v) => v.label_names(),
+        }
+    }
+}
+
+/// A named collection of metric families that renders as Prometheus text
+///
+/// Registering a name again with the same type and labels returns the
+/// existing family, so instruments can be looked up wherever they are used.
+/// Clones share the same families; `global()` is the process-wide registry.
+///
+/// # Example
+/// ```
+/// use acovo::metrics::Registry;
+///
+/// let registry = Registry::new();
+/// let depth = registry.gauge("app_queue_depth", "Jobs waiting").unwrap();
+/// depth.set(3.0);
+/// assert!(registry.render().contains("app_queue_depth 3"));
+/// ```
+#[cfg(feature = "metrics")]
+#[derive(Clone, Default)]
+pub struct Registry {
+    families: Arc<Mutex<BTreeMap<String, Family>>>,
+}
+
+#[cfg(feature = "metrics")]
+impl Registry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    fn families(&self) -> MutexGuard<'_, BTreeMap<String, Family>> {
+        self.families.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Look up or insert a family, checking the type and labels of an existing one
+    fn register(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        metric_type: &'static str,
+        make: impl FnOnce(Vec<String>) -> FamilyKind,
+    ) -> AnyResult<FamilyKind> {
+        check_metric_name(name)?;
+        for label in labels {
+            check_label_name(label)?;
+            if metric_type == Histogram::metric_type() && *label == "le" {
+                return Err(anyhow!("label name {:?} is reserved for {}", label, name));
+            }
+        }
+        let label_names: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+        if (1..label_names.len()).any(|i| label_names[..i].contains(&label_names[i])) {
+            return Err(anyhow!("duplicate label names {:?}", labels));
+        }
+
+        let mut families = self.families();
+        if let Some(family) = families.get(name) {
+            if family.metric_type() != metric_type || family.label_names() != label_names.as_slice() {
+                return Err(anyhow!(
+                    "metric {} is already registered as {} with labels {:?}",
+                    name,
+                    family.metric_type(),
+                    family.label_names()
+                ));
+            }
+            return Ok(family.kind.clone());
+        }
+        let kind = make(label_names);
+        families.insert(
+            name.to_string(),
+            Family {
+                help: help.to_string(),
+                kind: kind.clone(),
+            },
+        );
+        Ok(kind)
+    }
+
+    /// Register a counter family with the given label names
+    ///
+    /// # Errors
+    /// Returns an error for an invalid metric or label name, or if `name` is
+    /// already registered with a different type or labels.
+    pub fn counter_vec(&self, name: &str, help: &str, labels: &[&str]) -> AnyResult<CounterVec> {
+        match self.register(name, help, labels, Counter::metric_type(), |names| {
+            FamilyKind::Counter(MetricVec::new(names, Counter::new()))
+        })? {
+            FamilyKind::Counter(v) => Ok(v),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Register a counter without labels
+    ///
+    /// # Errors
+    /// See `counter_vec`.
+    pub fn counter(&self, name: &str, help: &str) -> AnyResult<Counter> {
+        Ok(self.counter_vec(name, help, &[])?.with_label_values(&[]))
+    }
+
+    /// Register a gauge family with the given label names
+    ///
+    /// # Errors
+    /// See `counter_vec`.
+    pub fn gauge_vec(&self, name: &str, help: &str, labels: &[&str]) -> AnyResult<GaugeVec> {
+        match self.register(name, help, labels, Gauge::metric_type(), |names| {
+            FamilyKind::Gauge(MetricVec::new(names, Gauge::new()))
+        })? {
+            FamilyKind::Gauge(v) => Ok(v),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Register a gauge without labels
+    ///
+    /// # Errors
+    /// See `counter_vec`.
+    pub fn gauge(&self, name: &str, help: &str) -> AnyResult<Gauge> {
+        Ok(self.gauge_vec(name, help, &[])?.with_label_values(&[]))
+    }
+
+    /// Register a histogram family with the given label names and bucket bounds
+    ///
+    /// The label name `le` is reserved for buckets. Re-registering an existing
+    /// histogram keeps its original buckets.
+    ///
+    /// # Errors
+    /// See `counter_vec`; also returns an error for invalid buckets.
+    pub fn histogram_vec(&self, name: &str, help: &str, labels: &[&str], buckets: &[f64]) -> AnyResult<HistogramVec> {
+        let template = Histogram::new(buckets)?;
+        match self.register(name, help, labels, Histogram::metric_type(), |names| {
+            FamilyKind::Histogram(MetricVec::new(names, template))
+        })? {
+            FamilyKind::Histogram(v) => Ok(v),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Register a histogram without labels
+    ///
+    /// # Errors
+    /// See `histogram_vec`.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> AnyResult<Histogram> {
+        Ok(self.histogram_vec(name, help, &[], buckets)?.with_label_values(&[]))
+    }
+
+    /// Remove a family; returns true if it was registered
+    ///
+    /// Handles already obtained keep working but are no longer rendered.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.families().remove(name).is_some()
+    }
+
+    /// Names of all registered families, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.families().keys().cloned().collect()
+    }
+
+    /// Render every family in the Prometheus text exposition format (version 0.0.4)
+    ///
+    /// Families are sorted by name and label sets by value, so the output is
+    /// stable between scrapes.
+    pub fn render(&self) -> String {
+        // Copy the families so rendering does not block registration
+        let families: Vec<(String, Family)> = self.families().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        let mut out = String::new();
+        for (name, family) in families {
+            if !family.help.is_empty() {
+                let _ = writeln!(out, "# HELP {} {}", name, escape_help(&family.help));
+            }
+            let _ = writeln!(out, "# TYPE {} {}", name, family.metric_type());
+            match &family.kind {
+                FamilyKind::Counter(v) => v.encode(&name, &mut out),
+                FamilyKind::Gauge(v) => v.encode(&name, &mut out),
+                FamilyKind::Histogram(v) => v.encode(&name, &mut out),
+            }
+        }
+        out
+    }
+}
+
+/// The process-wide registry, which also holds the built-in instruments
+#[cfg(feature = "metrics")]
+pub fn global() -> &'static Registry {
+    static GLOBAL: OnceLock<Registry> = OnceLock::new();
+    GLOBAL.get_or_init(Registry::new)
+}
+
+#[cfg(feature = "metrics")]
+fn check_metric_name(name: &str) -> AnyResult<()> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("invalid metric name {:?}", name))
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn check_label_name(name: &str) -> AnyResult<()> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__");
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("invalid label name {:?}", name))
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(feature = "metrics")]
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(feature = "metrics")]
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// `{a="1",le="0.5"}`, or nothing when there are no labels
+#[cfg(feature = "metrics")]
+fn label_block(labels: &[String], extra: Option<&str>) -> String {
+    let all: Vec<&str> = labels.iter().map(|l| l.as_str()).chain(extra).collect();
+    if all.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", all.join(","))
+    }
+}
+
+/// A minimal HTTP listener serving a registry at `GET /metrics`
+///
+/// A background thread accepts connections and serves each one on its own
+/// thread, at most `MAX_METRICS_CONNECTIONS` at a time; further connections are
+/// closed until one finishes. The request line and headers are limited to
+/// `MAX_METRICS_REQUEST_BYTES`. Dropping the server stops the accept thread.
+///
+/// # Example
+/// ```no_run
+/// use acovo::metrics::{global, MetricsServer};
+///
+/// let server = MetricsServer::start("127.0.0.1:9464", global().clone()).unwrap();
+/// println!("scrape http://{}/metrics", server.local_addr());
+/// ```
+#[cfg(feature = "metrics")]
+pub struct MetricsServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsServer {
+    /// Bind `addr` and start serving `registry`
+    ///
+    /// Use port 0 to pick a free port, then read it back with `local_addr`.
+    ///
+    /// # Errors
+    /// Returns an error if the address cannot be bound or the thread cannot start.
+    pub fn start<A: ToSocketAddrs>(addr: A, registry: Registry) -> AnyResult<MetricsServer> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let handle = thread::Builder::new()
+            .name("acovo-metrics".to_string())
+            .spawn(move || {
+                let active = Arc::new(AtomicUsize::new(0));
+                for stream in listener.incoming() {
+                    if stop_flag.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    // A slow client must not hold up other scrapes, but the thread count stays bounded
+                    if active.fetch_add(1, Ordering::SeqCst) >= MAX_METRICS_CONNECTIONS {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        continue;
+                    }
+                    let registry = registry.clone();
+                    let connection_active = active.clone();
+                    let spawned = thread::Builder::new()
+                        .name("acovo-metrics-conn".to_string())
+                        .spawn(move || {
+                            let _ = serve_connection(stream, &registry);
+                            connection_active.fetch_sub(1, Ordering::SeqCst);
+                        });
+                    if spawned.is_err() {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            })?;
+        Ok(MetricsServer {
+            addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// The bound address
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop listening and wait for the server thread to exit
+    pub fn shutdown(mut self) {
+        self.stop_thread();
+    }
+
+    fn stop_thread(&mut self) {
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept with a local connection
+        let mut wake = self.addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(if wake.is_ipv4() {
+                Ipv4Addr::LOCALHOST.into()
+            } else {
+                Ipv6Addr::LOCALHOST.into()
+            });
+        }
+        if TcpStream::connect_timeout(&wake, Duration::from_secs(1)).is_ok() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// Maximum number of connections `MetricsServer` serves at the same time
+#[cfg(feature = "metrics")]
+pub const MAX_METRICS_CONNECTIONS: usize = 8;
+
+/// Maximum size of the request line and headers read by `MetricsServer`
+#[cfg(feature = "metrics")]
+pub const MAX_METRICS_REQUEST_BYTES: u64 = 8 * 1024;
+
+/// Serve the global registry on `addr`; see `MetricsServer`
+///
+/// # Errors
+/// Returns an error if the address cannot be bound.
+#[cfg(feature = "metrics")]
+pub fn serve<A: ToSocketAddrs>(addr: A) -> AnyResult<MetricsServer> {
+    MetricsServer::start(addr, global().clone())
+}
+
+#[cfg(feature = "metrics")]
+fn serve_connection(stream: TcpStream, registry: &Registry) -> AnyResult<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    // Reads stop at the limit, so an endless line cannot grow the buffers
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_METRICS_REQUEST_BYTES));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers; the body of a GET is ignored
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") | ("HEAD", "/metrics") => ("200 OK", PROMETHEUS_CONTENT_TYPE, registry.render()),
+        (_, "/metrics") => ("405 Method Not Allowed", "text/plain", "method not allowed\n".to_string()),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Name of the built-in HTTP request counter
+#[cfg(feature = "metrics")]
+pub const HTTP_REQUESTS_TOTAL: &str = "acovo_http_requests_total";
+/// Name of the built-in HTTP request duration histogram
+#[cfg(feature = "metrics")]
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "acovo_http_request_duration_seconds";
+/// Name of the built-in ping counter
+#[cfg(feature = "metrics")]
+pub const NET_PINGS_TOTAL: &str = "acovo_net_pings_total";
+/// Name of the built-in ping latency histogram
+#[cfg(feature = "metrics")]
+pub const NET_PING_DURATION_SECONDS: &str = "acovo_net_ping_duration_seconds";
+/// Name of the built-in syncall retry counter
+#[cfg(feature = "metrics")]
+pub const SYNCALL_RETRIES_TOTAL: &str = "acovo_syncall_retries_total";
+
+#[cfg(feature = "metrics")]
+struct Builtins {
+    http_requests: CounterVec,
+    http_duration: HistogramVec,
+    pings: CounterVec,
+    ping_duration: Histogram,
+    syncall_retries: CounterVec,
+}
+
+// The crate's own instruments, registered in the global registry on first use
+#[cfg(feature = "metrics")]
+fn builtins() -> &'static Builtins {
+    static BUILTINS: OnceLock<Builtins> = OnceLock::new();
+    BUILTINS.get_or_init(|| {
+        let registry = global();
+        let ping_buckets = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+        Builtins {
+            http_requests: registry
+                .counter_vec(HTTP_REQUESTS_TOTAL, "HTTP requests by method and status", &["method", "status"])
+                .expect("built-in metric"),
+            http_duration: registry
+                .histogram_vec(HTTP_REQUEST_DURATION_SECONDS, "HTTP request duration in seconds", &["method"], &DEFAULT_BUCKETS)
+                .expect("built-in metric"),
+            pings: registry
+                .counter_vec(NET_PINGS_TOTAL, "Pings sent by result", &["result"])
+                .expect("built-in metric"),
+            ping_duration: registry
+                .histogram(NET_PING_DURATION_SECONDS, "Round trip time of successful pings in seconds", &ping_buckets)
+                .expect("built-in metric"),
+            syncall_retries: registry
+                .counter_vec(SYNCALL_RETRIES_TOTAL, "Failed attempts retried by syncall macros", &["call"])
+                .expect("built-in metric"),
+        }
+    })
+}
+
+/// Record one HTTP request in the built-in instruments
+///
+/// acovo does not send HTTP requests itself; call this from the application's
+/// HTTP client or server to fill `acovo_http_requests_total` and
+/// `acovo_http_request_duration_seconds`.
+///
+/// # Arguments
+/// * `method` - Request method, e.g. "GET"
+/// * `status` - Response status code, or 0 if no response was received
+/// * `elapsed` - Time from sending the request to receiving the response
+#[cfg(feature = "metrics")]
+pub fn record_http_request(method: &str, status: u16, elapsed: Duration) {
+    let builtins = builtins();
+    builtins
+        .http_requests
+        .with_label_values(&[method, &status.to_string()])
+        .inc();
+    builtins.http_duration.with_label_values(&[method]).observe_duration(elapsed);
+}
+
+/// Record one ping in the built-in instruments; `None` marks a failed ping
+#[cfg(feature = "metrics")]
+pub fn record_ping(round_trip: Option<Duration>) {
+    let builtins = builtins();
+    match round_trip {
+        Some(elapsed) => {
+            builtins.pings.with_label_values(&["ok"]).inc();
+            builtins.ping_duration.observe_duration(elapsed);
+        }
+        None => builtins.pings.with_label_values(&["error"]).inc(),
+    }
+}
+
+/// Record one failed attempt retried by a syncall macro, e.g. "atomic_call"
+#[cfg(feature = "metrics")]
+pub fn record_syncall_retry(call: &str) {
+    builtins().syncall_retries.with_label_values(&[call]).inc();
+}
+
+#[cfg(test)]
+#[cfg(feature = "metrics")]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_counter_and_gauge() {
+        let counter = Counter::new();
+        counter.inc();
+        counter.inc_by(4);
+        assert_eq!(counter.clone().get(), 5);
+
+        let gauge = Gauge::new();
+        gauge.set(2.5);
+        gauge.inc();
+        gauge.sub(0.5);
+        gauge.dec();
+        assert_eq!(gauge.get(), 2.0);
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = Histogram::new(&[0.1, 1.0, f64::INFINITY]).unwrap();
+        for value in [0.05, 0.1, 0.5, 3.0] {
+            histogram.observe(value);
+        }
+        assert_eq!(histogram.count(), 4);
+        assert!((histogram.sum() - 3.65).abs() < 1e-9);
+        assert_eq!(
+            histogram.buckets(),
+            vec![(0.1, 2), (1.0, 3), (f64::INFINITY, 4)]
+        );
+
+        let timer = histogram.start_timer();
+        assert!(timer.observe() < Duration::from_secs(1));
+        histogram.start_timer().discard();
+        assert_eq!(histogram.count(), 5);
+
+        assert!(Histogram::new(&[]).is_err());
+        assert!(Histogram::new(&[1.0, 1.0]).is_err());
+        assert!(Histogram::new(&[f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_bucket_helpers() {
+        assert_eq!(linear_buckets(0.0, 0.5, 3).unwrap(), vec![0.0, 0.5, 1.0]);
+        assert_eq!(exponential_buckets(0.001, 10.0, 3).unwrap().len(), 3);
+        assert!(exponential_buckets(0.0, 2.0, 3).is_err());
+        assert!(linear_buckets(0.0, 1.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_registry_reregister_and_conflicts() {
+        let registry = Registry::new();
+        let a = registry.counter_vec("jobs_total", "Jobs", &["queue"]).unwrap();
+        let b = registry.counter_vec("jobs_total", "Jobs", &["queue"]).unwrap();
+        a.with_label_values(&["fast"]).inc();
+        assert_eq!(b.with_label_values(&["fast"]).get(), 1);
+
+        assert!(registry.gauge("jobs_total", "").is_err());
+        assert!(registry.counter_vec("jobs_total", "", &["other"]).is_err());
+        assert!(registry.counter("bad-name", "").is_err());
+        assert!(registry.counter_vec("x_total", "", &["__reserved"]).is_err());
+        assert!(registry.counter_vec("x_total", "", &["a", "a"]).is_err());
+        assert!(registry.histogram_vec("h", "", &["le"], &DEFAULT_BUCKETS).is_err());
+        assert!(a.try_with_label_values(&[]).is_err());
+
+        assert!(a.remove_label_values(&["fast"]));
+        assert!(!a.remove_label_values(&["fast"]));
+        assert!(registry.unregister("jobs_total"));
+        assert!(registry.names().is_empty());
+    }
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let registry = Registry::new();
+        registry
+            .counter_vec("app_requests_total", "Requests\nserved", &["path"])
+            .unwrap()
+            .with_label_values(&["/a\"b\\"])
+            .inc_by(3);
+        registry.gauge("app_temperature", "").unwrap().set(-1.5);
+        registry
+            .histogram("app_latency_seconds", "Latency", &[0.5, 1.0])
+            .unwrap()
+            .observe(0.75);
+
+        let expected = "\
+# HELP app_latency_seconds Latency
+# TYPE app_latency_seconds histogram
+app_latency_seconds_bucket{le=\"0.5\"} 0
+app_latency_seconds_bucket{le=\"1\"} 1
+app_latency_seconds_bucket{le=\"+Inf\"} 1
+app_latency_seconds_sum 0.75
+app_latency_seconds_count 1
+# HELP app_requests_total Requests\\nserved
+# TYPE app_requests_total counter
+app_requests_total{path=\"/a\\\"b\\\\\"} 3
+# TYPE app_temperature gauge
+app_temperature -1.5
+";
+        assert_eq!(registry.render(), expected);
+    }
+
+    #[test]
+    fn test_render_histogram_with_labels() {
+        let registry = Registry::new();
+        let latency = registry
+            .histogram_vec("rpc_seconds", "", &["method"], &[1.0])
+            .unwrap();
+        latency.with_label_values(&["get"]).observe(2.0);
+        let text = registry.render();
+        assert!(text.contains("rpc_seconds_bucket{method=\"get\",le=\"1\"} 0\n"));
+        assert!(text.contains("rpc_seconds_bucket{method=\"get\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("rpc_seconds_count{method=\"get\"} 1\n"));
+    }
+
+    fn http_get(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_metrics_server() {
+        let registry = Registry::new();
+        registry.counter("served_total", "").unwrap().inc();
+        let server = MetricsServer::start("127.0.0.1:0", registry).unwrap();
+        let addr = server.local_addr();
+
+        let response = http_get(addr, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(PROMETHEUS_CONTENT_TYPE));
+        assert!(response.ends_with("served_total 1\n"));
+
+        let response = http_get(addr, "GET /other HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404"));
+        let response = http_get(addr, "POST /metrics HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405"));
+
+        server.shutdown();
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_metrics_server_slow_client() {
+        let registry = Registry::new();
+        registry.counter("served_total", "").unwrap().inc();
+        let server = MetricsServer::start("127.0.0.1:0", registry).unwrap();
+        let addr = server.local_addr();
+
+        // A client that never sends its request does not block other scrapes
+        let _stalled = TcpStream::connect(addr).unwrap();
+        let started = Instant::now();
+        let response = http_get(addr, "GET /metrics HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_builtin_instruments() {
+        record_http_request("GET", 200, Duration::from_millis(20));
+        record_ping(Some(Duration::from_millis(3)));
+        record_ping(None);
+        record_syncall_retry("atomic_call");
+
+        let text = global().render();
+        assert!(text.contains("# TYPE acovo_http_requests_total counter"));
+        assert!(text.contains("acovo_http_requests_total{method=\"GET\",status=\"200\"}"));
+        assert!(text.contains("acovo_net_pings_total{result=\"error\"}"));
+        assert!(text.contains("acovo_net_ping_duration_seconds_count"));
+        assert!(text.contains("acovo_syncall_retries_total{call=\"atomic_call\"}"));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_histogram_as_duration_recorder() {
+        use crate::time::ScopedTimer;
+
+        let histogram = Histogram::new(&DEFAULT_BUCKETS).unwrap();
+        drop(ScopedTimer::new("op").with_recorder(Arc::new(histogram.clone())));
+        assert_eq!(histogram.count(), 1);
+    }
+}
@@ -430,7 +430,15 @@ impl os_network for MacOSNetwork {
     }
 
     fn ping(_host: &str) -> AnyResult<PingResult> {
-        MacOSNetwork::ping_internal(_host, true)
+        let result = MacOSNetwork::ping_internal(_host, true);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ping(
+            result
+                .as_ref()
+                .ok()
+                .map(|r| std::time::Duration::from_secs_f32(r.duration.max(0.0) / 1000.0)),
+        );
+        result
     }
 
     fn nslookup(_host: &str) -> AnyResult<NsLookupResult> {
@@ -713,7 +721,15 @@ impl os_network for LinuxNetwork {
     }
 
     fn ping(_host: &str) -> AnyResult<PingResult> {
-        LinuxNetwork::ping_internal(_host, true)
+        let result = LinuxNetwork::ping_internal(_host, true);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ping(
+            result
+                .as_ref()
+                .ok()
+                .map(|r| std::time::Duration::from_secs_f32(r.duration.max(0.0) / 1000.0)),
+        );
+        result
     }
 
     fn nslookup(_host: &str) -> AnyResult<NsLookupResult> {
@@ -841,6 +857,18 @@ mod tests {
         println!("Route to 1.1.1.1: {:?}", route);
     }
 
+    #[test]
+    #[cfg(all(target_os = "linux", feature = "trace"))]
+    fn test_ping_internal_logs_error() {
+        use crate::trace::testing::capture;
+        use crate::trace::LogQuery;
+
+        // Fails whether or not a ping binary is installed
+        let (result, logs) = capture(|| LinuxNetwork::ping_internal("host.invalid", false));
+        assert!(result.is_err());
+        logs.assert_logged(&LogQuery::new().level(Level::ERROR).target("acovo::net"));
+    }
+
     #[test]
     #[cfg(target_os = "macos")]
     fn test_ping_internal() {
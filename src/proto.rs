@@ -4,6 +4,10 @@ use anyhow::{anyhow, Result};
 #[cfg(feature = "proto")]
 use serde_derive::{Deserialize, Serialize};
 
+// Binary wire formats and content-type negotiation
+#[cfg(feature = "codec")]
+pub mod codec;
+
 // RequestHeader represents the header structure for API requests
 // It contains metadata about the request such as version, action, signature, timestamp and sender
 #[cfg(feature = "proto")]
@@ -75,13 +79,13 @@ impl<T> Request<T> {
         }
     }
 
-    // Validates the request by ensuring it has a header with a signature
+    // Validates the request by ensuring it has a header with a non-blank signature
     pub fn validate(&self) -> Result<()> {
-        if *&self.head.is_none() || *(&self.head.as_ref().unwrap().sign.is_none()) {
+        let sign = self.head.as_ref().and_then(|head| head.sign.as_deref());
+        if sign.map(str::trim).unwrap_or("").is_empty() {
             return Err(anyhow!("sign data is required！"));
-        } else {
-            return Ok(());
         }
+        Ok(())
     }
 }
 
@@ -186,6 +190,843 @@ impl<T> Response<T> {
     }
 }
 
+// Status codes below this value mean success (0 and the HTTP 1xx/2xx range)
+#[cfg(feature = "proto")]
+const SUCCESS_CODE_LIMIT: u32 = 300;
+
+// Code of the standard success State
+#[cfg(feature = "proto")]
+pub const SUCCESS_CODE: u32 = 200;
+
+// Message of the standard success State
+#[cfg(feature = "proto")]
+pub const SUCCESS_MESSAGE: &str = "OK";
+
+// Implementation of State methods
+#[cfg(feature = "proto")]
+impl State {
+    // Creates the standard success State (200, "OK")
+    // Every successful response should carry it so clients can tell success from a missing state.
+    pub fn success() -> Self {
+        State {
+            ret_code: SUCCESS_CODE,
+            ret_message: Some(SUCCESS_MESSAGE.to_string()),
+        }
+    }
+
+    // Returns true if the code denotes success rather than an error
+    pub fn is_success(&self) -> bool {
+        self.ret_code < SUCCESS_CODE_LIMIT
+    }
+}
+
+// ErrorInfo describes one registered error code
+#[cfg(feature = "proto")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorInfo {
+    // Namespace grouping related codes, e.g. "order"
+    pub namespace: &'static str,
+    // Name of the code within its namespace, e.g. "NotFound"
+    pub name: &'static str,
+    // Value carried in State::ret_code, unique across all namespaces
+    pub code: u32,
+    // Message used when a State carries none
+    pub message: &'static str,
+    // HTTP status a gateway should answer with
+    pub http_status: u16,
+    // Whether the same request may succeed if sent again
+    pub retryable: bool,
+}
+
+// Implementation of ErrorInfo methods
+#[cfg(feature = "proto")]
+impl ErrorInfo {
+    // Returns the namespaced name, e.g. "order.NotFound"
+    pub fn key(&self) -> String {
+        format!("{}.{}", self.namespace, self.name)
+    }
+}
+
+// ErrorCode is implemented by the enums declared with error_codes!
+// Every variant maps to one ErrorInfo; codes must not be shared with other enums.
+#[cfg(feature = "proto")]
+pub trait ErrorCode: Copy + 'static {
+    // Returns every variant of the enum
+    fn all() -> &'static [Self];
+
+    // Returns the description of this variant
+    fn info(&self) -> ErrorInfo;
+
+    // Returns the numeric code carried in State::ret_code
+    fn code(&self) -> u32 {
+        self.info().code
+    }
+
+    // Returns the variant with the given numeric code
+    fn from_code(code: u32) -> Option<Self> {
+        Self::all().iter().copied().find(|e| e.code() == code)
+    }
+
+    // Returns the variant a State carries, if it belongs to this enum
+    fn from_state(state: &State) -> Option<Self> {
+        Self::from_code(state.ret_code)
+    }
+
+    // Returns a State with this code and its default message
+    fn state(&self) -> State {
+        let info = self.info();
+        State {
+            ret_code: info.code,
+            ret_message: Some(info.message.to_string()),
+        }
+    }
+
+    // Returns an ApiError with this code and a specific message
+    fn with_message(&self, msg: &str) -> ApiError {
+        ApiError::from(*self).with_message(msg)
+    }
+}
+
+// Declares an error code enum and implements ErrorCode, Display and Error for it
+//
+// acovo::error_codes! {
+//     pub enum OrderError in "order" {
+//         NotFound = 10001 { status: 404, retryable: false, message: "order not found" },
+//         Locked = 10002 { status: 409, retryable: true, message: "order is being updated" },
+//     }
+// }
+//
+// Register the enum with register_error_codes::<OrderError>() so that ApiError values
+// built from a bare State pick up its messages, HTTP statuses and retry flags.
+#[cfg(feature = "proto")]
+#[macro_export]
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident in $namespace:literal {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $code:literal {
+                    status: $status:literal,
+                    retryable: $retryable:literal,
+                    message: $message:literal $(,)?
+                }
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u32)]
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant = $code,
+            )*
+        }
+
+        impl $crate::proto::ErrorCode for $name {
+            fn all() -> &'static [Self] {
+                &[$($name::$variant),*]
+            }
+
+            fn info(&self) -> $crate::proto::ErrorInfo {
+                match self {
+                    $(
+                        $name::$variant => $crate::proto::ErrorInfo {
+                            namespace: $namespace,
+                            name: stringify!($variant),
+                            code: $code,
+                            message: $message,
+                            http_status: $status,
+                            retryable: $retryable,
+                        },
+                    )*
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str($crate::proto::ErrorCode::info(self).message)
+            }
+        }
+
+        impl ::std::error::Error for $name {}
+    };
+}
+
+error_codes! {
+    // CommonError holds the protocol-level codes shared by every service
+    // The codes equal their HTTP statuses; domain codes should stay above 999.
+    #[cfg(feature = "proto")]
+    pub enum CommonError in "common" {
+        BadRequest = 400 { status: 400, retryable: false, message: "bad request" },
+        Unauthorized = 401 { status: 401, retryable: false, message: "unauthorized" },
+        Forbidden = 403 { status: 403, retryable: false, message: "forbidden" },
+        NotFound = 404 { status: 404, retryable: false, message: "not found" },
+        Conflict = 409 { status: 409, retryable: false, message: "conflict" },
+        TooManyRequests = 429 { status: 429, retryable: true, message: "too many requests" },
+        Internal = 500 { status: 500, retryable: false, message: "internal error" },
+        NotImplemented = 501 { status: 501, retryable: false, message: "not implemented" },
+        Unavailable = 503 { status: 503, retryable: true, message: "service unavailable" },
+        Timeout = 504 { status: 504, retryable: true, message: "timeout" },
+    }
+}
+
+// ErrorRegistry maps numeric codes to their descriptions
+#[cfg(feature = "proto")]
+#[derive(Debug, Default)]
+pub struct ErrorRegistry {
+    codes: std::sync::RwLock<std::collections::BTreeMap<u32, ErrorInfo>>,
+}
+
+// Implementation of ErrorRegistry methods
+#[cfg(feature = "proto")]
+impl ErrorRegistry {
+    // Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds one code
+    // Registering the same description again is a no-op; reusing a code or a
+    // namespaced name for a different description is an error.
+    pub fn register(&self, info: ErrorInfo) -> Result<()> {
+        let mut codes = self.codes.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = codes.get(&info.code) {
+            if *existing == info {
+                return Ok(());
+            }
+            return Err(anyhow!(
+                "error code {} is already registered as {}",
+                info.code,
+                existing.key()
+            ));
+        }
+        if let Some(existing) = codes
+            .values()
+            .find(|e| e.namespace == info.namespace && e.name == info.name)
+        {
+            return Err(anyhow!(
+                "{} is already registered with code {}",
+                info.key(),
+                existing.code
+            ));
+        }
+        codes.insert(info.code, info);
+        Ok(())
+    }
+
+    // Adds every code of an error enum, stopping at the first conflict
+    pub fn register_all<E: ErrorCode>(&self) -> Result<()> {
+        for e in E::all() {
+            self.register(e.info())?;
+        }
+        Ok(())
+    }
+
+    // Returns the description of a code
+    pub fn lookup(&self, code: u32) -> Option<ErrorInfo> {
+        self.codes.read().unwrap_or_else(|e| e.into_inner()).get(&code).copied()
+    }
+
+    // Returns the codes of one namespace, ordered by code
+    pub fn namespace(&self, namespace: &str) -> Vec<ErrorInfo> {
+        self.all().into_iter().filter(|e| e.namespace == namespace).collect()
+    }
+
+    // Returns every registered code, ordered by code
+    pub fn all(&self) -> Vec<ErrorInfo> {
+        self.codes.read().unwrap_or_else(|e| e.into_inner()).values().copied().collect()
+    }
+}
+
+// Returns the process-wide registry, which always holds CommonError
+#[cfg(feature = "proto")]
+pub fn error_registry() -> &'static ErrorRegistry {
+    static REGISTRY: std::sync::OnceLock<ErrorRegistry> = std::sync::OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let registry = ErrorRegistry::new();
+        registry
+            .register_all::<CommonError>()
+            .expect("common error codes are unique");
+        registry
+    })
+}
+
+// Adds every code of an error enum to the process-wide registry
+#[cfg(feature = "proto")]
+pub fn register_error_codes<E: ErrorCode>() -> Result<()> {
+    error_registry().register_all::<E>()
+}
+
+// ApiError is an error State as a Rust error value
+// The description is taken from the process-wide registry when the code is registered.
+// An internal error converted from anyhow keeps the original error as source for
+// logging; it never becomes part of the State sent to clients.
+#[cfg(feature = "proto")]
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    code: u32,
+    message: String,
+    info: Option<ErrorInfo>,
+    source: Option<std::sync::Arc<anyhow::Error>>,
+}
+
+// Errors are equal when code and message match; the source is not compared
+#[cfg(feature = "proto")]
+impl PartialEq for ApiError {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.message == other.message && self.info == other.info
+    }
+}
+
+// Implementation of ApiError methods
+#[cfg(feature = "proto")]
+impl ApiError {
+    // Creates an error with the given code and message
+    // An empty message is replaced by the registered default message.
+    pub fn new(code: u32, msg: &str) -> Self {
+        let info = error_registry().lookup(code);
+        let message = match (msg, info) {
+            ("", Some(info)) => info.message.to_string(),
+            _ => msg.to_string(),
+        };
+        ApiError {
+            code,
+            message,
+            info,
+            source: None,
+        }
+    }
+
+    // Creates CommonError::Internal with the error text as message
+    // Use only where the text is safe to show to clients; From<anyhow::Error> keeps
+    // the generic message instead.
+    pub fn detailed(error: anyhow::Error) -> Self {
+        match error.downcast::<ApiError>() {
+            Ok(error) => error,
+            Err(error) => {
+                let mut api_error = CommonError::Internal.with_message(&error.to_string());
+                api_error.source = Some(std::sync::Arc::new(error));
+                api_error
+            }
+        }
+    }
+
+    // Creates an error from a response state
+    pub fn from_state(state: &State) -> Self {
+        ApiError::new(state.ret_code, state.ret_message.as_deref().unwrap_or(""))
+    }
+
+    // Replaces the message, keeping the code
+    pub fn with_message(mut self, msg: &str) -> Self {
+        self.message = msg.to_string();
+        self
+    }
+
+    // Returns the numeric code
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    // Returns the message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // Returns the registered description, if the code is known
+    pub fn info(&self) -> Option<ErrorInfo> {
+        self.info
+    }
+
+    // Returns the internal error this was converted from, for logging
+    pub fn internal_error(&self) -> Option<&anyhow::Error> {
+        self.source.as_deref()
+    }
+
+    // Returns the HTTP status for the error
+    // Unregistered codes in the 4xx/5xx range map to themselves, others to 500.
+    pub fn http_status(&self) -> u16 {
+        match self.info {
+            Some(info) => info.http_status,
+            None if (400..600).contains(&self.code) => self.code as u16,
+            None => 500,
+        }
+    }
+
+    // Returns true if the request may succeed if sent again
+    pub fn is_retryable(&self) -> bool {
+        self.info.map(|info| info.retryable).unwrap_or(false)
+    }
+
+    // Returns true if the error carries the given code
+    pub fn is<E: ErrorCode>(&self, code: E) -> bool {
+        self.code == code.code()
+    }
+
+    // Returns the variant of an error enum the code belongs to, for matching
+    pub fn kind<E: ErrorCode>(&self) -> Option<E> {
+        E::from_code(self.code)
+    }
+
+    // Returns the State carrying this error
+    pub fn state(&self) -> State {
+        State {
+            ret_code: self.code,
+            ret_message: Some(self.message.clone()),
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.info {
+            Some(info) => write!(f, "{} ({}): {}", info.key(), self.code, self.message),
+            None => write!(f, "error {}: {}", self.code, self.message),
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|error| error.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<E: ErrorCode> From<E> for ApiError {
+    fn from(code: E) -> Self {
+        let info = code.info();
+        ApiError {
+            code: info.code,
+            message: info.message.to_string(),
+            info: Some(info),
+            source: None,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<State> for ApiError {
+    fn from(state: State) -> Self {
+        ApiError::from_state(&state)
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<ApiError> for State {
+    fn from(error: ApiError) -> Self {
+        State {
+            ret_code: error.code,
+            ret_message: Some(error.message),
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<E: ErrorCode> From<E> for State {
+    fn from(code: E) -> Self {
+        code.state()
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<T> From<ApiError> for Response<T> {
+    fn from(error: ApiError) -> Self {
+        Response::from_error(error)
+    }
+}
+
+// Typed error handling on Response
+#[cfg(feature = "proto")]
+impl<T> Response<T> {
+    // Creates a Response without a body carrying the given error
+    pub fn from_error<E: Into<ApiError>>(error: E) -> Self {
+        Response {
+            state: Some(State::from(error.into())),
+            head: None,
+            body: None,
+        }
+    }
+
+    // Sets the response state to the given error, like raiseRequestError with a typed code
+    pub fn raise<E: Into<ApiError>>(&mut self, error: E) {
+        self.state = Some(State::from(error.into()));
+    }
+
+    // Returns the error carried in the state, or None on success or without a state
+    pub fn error(&self) -> Option<ApiError> {
+        self.state
+            .as_ref()
+            .filter(|state| !state.is_success())
+            .map(ApiError::from_state)
+    }
+
+    // Converts the response into its body or the error carried in the state
+    // Like is_success, a response without a state is not successful: it becomes
+    // CommonError::Internal.
+    pub fn into_result(self) -> std::result::Result<Option<T>, ApiError> {
+        match self.state {
+            Some(ref state) if state.is_success() => Ok(self.body),
+            Some(ref state) => Err(ApiError::from_state(state)),
+            None => Err(CommonError::Internal.with_message("response state is missing")),
+        }
+    }
+}
+
+// Result type of handlers that answer with a Response
+#[cfg(feature = "proto")]
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+// Conversions between Response and Result
+#[cfg(feature = "proto")]
+impl<T> Response<T> {
+    // Creates a successful Response with the standard success State and the given body
+    pub fn ok(body: T) -> Self {
+        Response {
+            state: Some(State::success()),
+            head: None,
+            body: Some(body),
+        }
+    }
+
+    // Creates a successful Response with the standard success State and no body
+    pub fn ok_empty() -> Self {
+        Response {
+            state: Some(State::success()),
+            head: None,
+            body: None,
+        }
+    }
+
+    // Builds a Response from a handler closure, so the handler body can use ?
+    pub fn handle<E, F>(f: F) -> Self
+    where
+        E: Into<ApiError>,
+        F: FnOnce() -> std::result::Result<T, E>,
+    {
+        Response::from(f())
+    }
+
+    // Returns true only if the response carries a success State
+    // A response without a state does not count as successful.
+    pub fn is_success(&self) -> bool {
+        self.state.as_ref().map(State::is_success).unwrap_or(false)
+    }
+
+    // Converts the response into its body, treating a missing body as an error
+    pub fn into_body(self) -> ApiResult<T> {
+        self.into_result()?
+            .ok_or_else(|| CommonError::Internal.with_message("response body is missing"))
+    }
+
+    // Transforms the body, keeping the state and header
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Response<U> {
+        Response {
+            state: self.state,
+            head: self.head,
+            body: self.body.map(f),
+        }
+    }
+
+    // Transforms the body with a fallible function
+    // An error response is passed through without calling f; if f fails, its error
+    // replaces the state and the body is dropped.
+    pub fn and_then<U, E, F>(self, f: F) -> Response<U>
+    where
+        E: Into<ApiError>,
+        F: FnOnce(T) -> std::result::Result<U, E>,
+    {
+        let body = match self.body {
+            Some(body) if self.error().is_none() => body,
+            _ => {
+                return Response {
+                    state: self.state,
+                    head: self.head,
+                    body: None,
+                }
+            }
+        };
+        match f(body) {
+            Ok(body) => Response {
+                state: self.state,
+                head: self.head,
+                body: Some(body),
+            },
+            Err(error) => Response {
+                state: Some(State::from(error.into())),
+                head: self.head,
+                body: None,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<T, E: Into<ApiError>> From<std::result::Result<T, E>> for Response<T> {
+    fn from(result: std::result::Result<T, E>) -> Self {
+        match result {
+            Ok(body) => Response::ok(body),
+            Err(error) => Response::from_error(error),
+        }
+    }
+}
+
+// Any other error becomes CommonError::Internal with the generic message, unless it
+// wraps an ApiError. The error text is kept only as source (see ApiError::internal_error)
+// so it is not leaked to clients; use ApiError::detailed to send it.
+#[cfg(feature = "proto")]
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<ApiError>() {
+            Ok(error) => error,
+            Err(error) => {
+                let mut api_error = ApiError::from(CommonError::Internal);
+                api_error.source = Some(std::sync::Arc::new(error));
+                api_error
+            }
+        }
+    }
+}
+
+// Helpers for turning errors and missing values into ApiError with ?
+#[cfg(feature = "proto")]
+pub trait ResultExt<T> {
+    // Replaces the error with the given code, keeping the error text as message
+    fn or_code<C: ErrorCode>(self, code: C) -> ApiResult<T>;
+}
+
+#[cfg(feature = "proto")]
+impl<T, E: std::fmt::Display> ResultExt<T> for std::result::Result<T, E> {
+    fn or_code<C: ErrorCode>(self, code: C) -> ApiResult<T> {
+        self.map_err(|e| code.with_message(&e.to_string()))
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<T> ResultExt<T> for Option<T> {
+    // Turns None into the given code with its default message
+    fn or_code<C: ErrorCode>(self, code: C) -> ApiResult<T> {
+        self.ok_or_else(|| ApiError::from(code))
+    }
+}
+
+// SignatureConfig holds the shared secret and policy used to sign and verify envelopes
+// The timestamp field is expected to hold UNIX epoch seconds (or milliseconds if 13+ digits)
+#[cfg(all(feature = "proto", feature = "hash"))]
+#[derive(Clone)]
+pub struct SignatureConfig {
+    // Shared secret key
+    pub key: Vec<u8>,
+    // HMAC variant used for the signature
+    pub algorithm: crate::hash::HmacAlgorithm,
+    // Maximum allowed difference between the timestamp field and the local clock
+    pub max_clock_skew: std::time::Duration,
+}
+
+// Debug output never shows the secret key, only its length
+#[cfg(all(feature = "proto", feature = "hash"))]
+impl std::fmt::Debug for SignatureConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignatureConfig")
+            .field("key", &"<redacted>")
+            .field("key_len", &self.key.len())
+            .field("algorithm", &self.algorithm)
+            .field("max_clock_skew", &self.max_clock_skew)
+            .finish()
+    }
+}
+
+// Implementation of SignatureConfig methods
+#[cfg(all(feature = "proto", feature = "hash"))]
+impl SignatureConfig {
+    // Creates a config using HMAC-SHA256 and a 5 minute clock skew tolerance
+    pub fn new(key: &[u8]) -> Self {
+        SignatureConfig {
+            key: key.to_vec(),
+            algorithm: crate::hash::HmacAlgorithm::Sha256,
+            max_clock_skew: std::time::Duration::from_secs(300),
+        }
+    }
+
+    // Selects the HMAC variant
+    pub fn with_algorithm(mut self, algorithm: crate::hash::HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    // Sets the clock skew tolerance applied when verifying
+    pub fn with_max_clock_skew(mut self, skew: std::time::Duration) -> Self {
+        self.max_clock_skew = skew;
+        self
+    }
+}
+
+// Appends one length-prefixed field ("<byte length>:<value>\n") to a canonical string
+// The prefix keeps a newline inside a value from shifting content into the next field.
+#[cfg(all(feature = "proto", feature = "hash"))]
+fn push_canonical_field(canonical: &mut String, value: &str) {
+    canonical.push_str(&format!("{}:{}\n", value.len(), value));
+}
+
+// Builds the canonical string that is signed:
+// "<scheme>\n" followed by the length-prefixed action, timestamp, sender or host and
+// sha256 hex of the JSON body. Missing fields are encoded as empty values. The body must
+// serialize deterministically (structs do; HashMap fields do not).
+#[cfg(all(feature = "proto", feature = "hash"))]
+fn canonical_string<T: serde::Serialize>(
+    algorithm: crate::hash::HmacAlgorithm,
+    action: &Option<String>,
+    timestamp: &Option<String>,
+    origin: &Option<String>,
+    body: &Option<T>,
+) -> Result<String> {
+    use crate::hash::{hash_bytes, HashAlgorithm};
+
+    let body_bytes = match body {
+        Some(body) => serde_json::to_vec(body)?,
+        None => Vec::new(),
+    };
+    let mut canonical = format!("ACOVO-{}\n", algorithm.name());
+    push_canonical_field(&mut canonical, action.as_deref().unwrap_or(""));
+    push_canonical_field(&mut canonical, timestamp.as_deref().unwrap_or(""));
+    push_canonical_field(&mut canonical, origin.as_deref().unwrap_or(""));
+    push_canonical_field(&mut canonical, &hash_bytes(HashAlgorithm::Sha256, &body_bytes).to_hex());
+    Ok(canonical)
+}
+
+// Returns the canonical string a request signature covers
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn canonical_request_string<T: serde::Serialize>(
+    request: &Request<T>,
+    algorithm: crate::hash::HmacAlgorithm,
+) -> Result<String> {
+    let head = request.head.as_ref().ok_or(anyhow!("sign data is required！"))?;
+    canonical_string(algorithm, &head.action, &head.timestamp, &head.sender, &request.body)
+}
+
+// Current time in UNIX epoch seconds
+#[cfg(all(feature = "proto", feature = "hash"))]
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// Checks the timestamp field against the local clock
+#[cfg(all(feature = "proto", feature = "hash"))]
+fn check_timestamp(timestamp: &Option<String>, max_skew: std::time::Duration) -> Result<()> {
+    let text = timestamp.as_deref().map(str::trim).unwrap_or("");
+    if text.is_empty() {
+        return Err(anyhow!("timestamp is required for signature verification"));
+    }
+    let value: i64 = text
+        .parse()
+        .map_err(|_| anyhow!("invalid timestamp '{}', expected UNIX epoch seconds", text))?;
+    // 13 or more digits are treated as milliseconds
+    let seconds = if text.len() >= 13 { value / 1000 } else { value };
+    let skew = (unix_now() - seconds).unsigned_abs();
+    if skew > max_skew.as_secs() {
+        return Err(anyhow!("timestamp is outside the allowed clock skew ({}s)", skew));
+    }
+    Ok(())
+}
+
+// Checks a hex signature against the expected tag in constant time
+#[cfg(all(feature = "proto", feature = "hash"))]
+fn check_signature(sign: &Option<String>, canonical: &str, config: &SignatureConfig) -> Result<()> {
+    let sign = sign.as_deref().map(str::trim).unwrap_or("");
+    if sign.is_empty() {
+        return Err(anyhow!("sign data is required！"));
+    }
+    let tag = hex::decode(sign).map_err(|_| anyhow!("signature is not valid hex"))?;
+    if !crate::hash::hmac_verify(config.algorithm, &config.key, canonical.as_bytes(), &tag) {
+        return Err(anyhow!("signature mismatch"));
+    }
+    Ok(())
+}
+
+// Signs a request in place
+// Creates the header if needed, sets the timestamp to the current time if it is missing,
+// and stores the hex HMAC of the canonical string in the sign field.
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn sign_request<T: serde::Serialize>(request: &mut Request<T>, config: &SignatureConfig) -> Result<()> {
+    let head = request.head.get_or_insert(RequestHeader {
+        version: None,
+        action: None,
+        sign: None,
+        timestamp: None,
+        sender: None,
+    });
+    if head.timestamp.is_none() {
+        head.timestamp = Some(unix_now().to_string());
+    }
+    let canonical = canonical_request_string(request, config.algorithm)?;
+    let tag = crate::hash::hmac_bytes(config.algorithm, &config.key, canonical.as_bytes());
+    request.head.as_mut().unwrap().sign = Some(hex::encode(tag));
+    Ok(())
+}
+
+// Verifies a request signature
+// Fails if the sign or timestamp field is missing or blank, if the timestamp is outside
+// the configured clock skew, or if the signature does not match.
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn verify_request<T: serde::Serialize>(request: &Request<T>, config: &SignatureConfig) -> Result<()> {
+    request.validate()?;
+    let head = request.head.as_ref().unwrap();
+    check_timestamp(&head.timestamp, config.max_clock_skew)?;
+    let canonical = canonical_request_string(request, config.algorithm)?;
+    check_signature(&head.sign, &canonical, config)
+}
+
+// Returns the canonical string a response signature covers (the host takes the place of the sender)
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn canonical_response_string<T: serde::Serialize>(
+    response: &Response<T>,
+    algorithm: crate::hash::HmacAlgorithm,
+) -> Result<String> {
+    let head = response.head.as_ref().ok_or(anyhow!("sign data is required！"))?;
+    let mut canonical = canonical_string(algorithm, &head.action, &head.timestamp, &head.host, &response.body)?;
+    // The state is part of the response and must not be altered in transit
+    if let Some(state) = &response.state {
+        push_canonical_field(&mut canonical, &state.ret_code.to_string());
+        push_canonical_field(&mut canonical, state.ret_message.as_deref().unwrap_or(""));
+    }
+    Ok(canonical)
+}
+
+// Signs a response in place, like sign_request
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn sign_response<T: serde::Serialize>(response: &mut Response<T>, config: &SignatureConfig) -> Result<()> {
+    let head = response.head.get_or_insert(ResponseHeader {
+        version: None,
+        action: None,
+        sign: None,
+        timestamp: None,
+        host: None,
+    });
+    if head.timestamp.is_none() {
+        head.timestamp = Some(unix_now().to_string());
+    }
+    let canonical = canonical_response_string(response, config.algorithm)?;
+    let tag = crate::hash::hmac_bytes(config.algorithm, &config.key, canonical.as_bytes());
+    response.head.as_mut().unwrap().sign = Some(hex::encode(tag));
+    Ok(())
+}
+
+// Verifies a response signature, like verify_request
+#[cfg(all(feature = "proto", feature = "hash"))]
+pub fn verify_response<T: serde::Serialize>(response: &Response<T>, config: &SignatureConfig) -> Result<()> {
+    let head = response.head.as_ref().ok_or(anyhow!("sign data is required！"))?;
+    check_timestamp(&head.timestamp, config.max_clock_skew)?;
+    let canonical = canonical_response_string(response, config.algorithm)?;
+    check_signature(&head.sign, &canonical, config)
+}
+
 // Unit tests for the proto module
 #[cfg(test)]
 #[cfg(feature = "proto")]
@@ -313,7 +1154,7 @@ mod tests {
             timestamp: None,
             sender: None,
         });
-        assert_eq!(req.validate().is_ok(), true);  // Empty string is still Some(String)
+        assert_eq!(req.validate().is_err(), true);  // An empty signature counts as missing
     }
 
     // Test Request::validate with whitespace-only signature
@@ -327,7 +1168,7 @@ mod tests {
             timestamp: None,
             sender: None,
         });
-        assert_eq!(req.validate().is_ok(), true);  // Whitespace string is still Some(String)
+        assert_eq!(req.validate().is_err(), true);  // A whitespace-only signature counts as missing
     }
 
     // Test Request::validate error message formatting
@@ -620,4 +1461,355 @@ mod tests {
         assert_eq!(state.ret_code, 999999);
         assert_eq!(state.ret_message, Some("High code test".to_string()));
     }
+
+    // Builds a signed request used by the signature tests
+    #[cfg(feature = "hash")]
+    fn signed_request(config: &SignatureConfig) -> Request<String> {
+        let mut req = Request::<String>::new(Some("payload".to_string()));
+        req.head = Some(RequestHeader {
+            version: None,
+            action: Some("order.create".to_string()),
+            sign: None,
+            timestamp: None,
+            sender: Some("client-1".to_string()),
+        });
+        sign_request(&mut req, config).unwrap();
+        req
+    }
+
+    // Test that a signed request verifies and that the header is filled in
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_sign_and_verify_request() {
+        let config = SignatureConfig::new(b"secret");
+        let req = signed_request(&config);
+
+        let head = req.head.as_ref().unwrap();
+        assert_eq!(head.sign.as_ref().unwrap().len(), 64);
+        assert!(head.timestamp.is_some());
+        assert!(verify_request(&req, &config).is_ok());
+
+        // A request without a header gets one
+        let mut bare = Request::<u32>::new(Some(7));
+        sign_request(&mut bare, &config).unwrap();
+        assert!(verify_request(&bare, &config).is_ok());
+    }
+
+    // Test that tampering with any signed field is detected
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_verify_request_detects_tampering() {
+        let config = SignatureConfig::new(b"secret");
+
+        let mut req = signed_request(&config);
+        req.body = Some("other payload".to_string());
+        assert!(verify_request(&req, &config).is_err());
+
+        let mut req = signed_request(&config);
+        req.head.as_mut().unwrap().action = Some("order.delete".to_string());
+        assert!(verify_request(&req, &config).is_err());
+
+        let mut req = signed_request(&config);
+        req.head.as_mut().unwrap().sender = Some("client-2".to_string());
+        assert!(verify_request(&req, &config).is_err());
+
+        let req = signed_request(&config);
+        assert!(verify_request(&req, &SignatureConfig::new(b"wrong")).is_err());
+        let sha512 = SignatureConfig::new(b"secret").with_algorithm(crate::hash::HmacAlgorithm::Sha512);
+        assert!(verify_request(&req, &sha512).is_err());
+    }
+
+    // Test that blank signatures are rejected by both validate and verification
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_verify_request_rejects_blank_signature() {
+        let config = SignatureConfig::new(b"secret");
+        let mut req = signed_request(&config);
+        req.head.as_mut().unwrap().sign = Some("   ".to_string());
+        assert!(req.validate().is_err());
+        assert!(verify_request(&req, &config).is_err());
+
+        req.head.as_mut().unwrap().sign = Some("not hex".to_string());
+        assert!(verify_request(&req, &config).is_err());
+    }
+
+    // Test that a newline cannot move content between signed fields
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_canonical_string_fields_are_delimited() {
+        let algorithm = crate::hash::HmacAlgorithm::Sha256;
+        let head = |action: &str, sender: &str| RequestHeader {
+            version: None,
+            action: Some(action.to_string()),
+            sign: None,
+            timestamp: Some("1700000000".to_string()),
+            sender: Some(sender.to_string()),
+        };
+        let mut a = Request::<u32>::new(None);
+        a.head = Some(head("order.create\n1700000000", "client-1"));
+        let mut b = Request::<u32>::new(None);
+        b.head = Some(head("order.create", "1700000000\nclient-1"));
+        assert_ne!(
+            canonical_request_string(&a, algorithm).unwrap(),
+            canonical_request_string(&b, algorithm).unwrap()
+        );
+
+        let config = SignatureConfig::new(b"secret");
+        a.head.as_mut().unwrap().timestamp = None;
+        sign_request(&mut a, &config).unwrap();
+        assert!(verify_request(&a, &config).is_ok());
+    }
+
+    // Test clock skew tolerance on the timestamp field
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_verify_request_clock_skew() {
+        let config = SignatureConfig::new(b"secret").with_max_clock_skew(std::time::Duration::from_secs(60));
+        let now = unix_now();
+
+        for (offset, ok) in [(-30, true), (30, true), (-120, false), (120, false)] {
+            let mut req = Request::<String>::new(Some("x".to_string()));
+            req.head = Some(RequestHeader::new_with_sign(String::new()));
+            req.head.as_mut().unwrap().timestamp = Some((now + offset).to_string());
+            sign_request(&mut req, &config).unwrap();
+            assert_eq!(verify_request(&req, &config).is_ok(), ok, "offset {}", offset);
+        }
+
+        // Millisecond timestamps are accepted
+        let mut req = Request::<String>::new(None);
+        req.head = Some(RequestHeader::new_with_sign(String::new()));
+        req.head.as_mut().unwrap().timestamp = Some((now * 1000).to_string());
+        sign_request(&mut req, &config).unwrap();
+        assert!(verify_request(&req, &config).is_ok());
+
+        // Non-numeric timestamps are rejected
+        req.head.as_mut().unwrap().timestamp = Some("20240101000000x".to_string());
+        assert!(verify_request(&req, &config).is_err());
+    }
+
+    // Test that Debug output does not leak the secret key
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_signature_config_debug_redacts_key() {
+        let config = SignatureConfig::new(b"top-secret-key");
+        let text = format!("{:?}", config);
+        assert!(!text.contains("top-secret-key"));
+        assert!(!text.contains("116, 111, 112"));
+        assert!(text.contains("<redacted>"));
+        assert!(text.contains("key_len: 14"));
+    }
+
+    // Test signing and verifying a response, including its state
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_sign_and_verify_response() {
+        let config = SignatureConfig::new(b"secret");
+        let mut resp = Response::<u32>::new_with_state(200, "OK");
+        resp.body = Some(42);
+        sign_response(&mut resp, &config).unwrap();
+        assert!(verify_response(&resp, &config).is_ok());
+
+        resp.state.as_mut().unwrap().ret_code = 500;
+        assert!(verify_response(&resp, &config).is_err());
+    }
+
+    crate::error_codes! {
+        // Error codes used by the registry tests
+        pub enum OrderError in "order" {
+            NotFound = 10001 { status: 404, retryable: false, message: "order not found" },
+            Locked = 10002 { status: 409, retryable: true, message: "order is being updated" },
+        }
+    }
+
+    // Test the metadata generated by error_codes!
+    #[test]
+    fn test_error_codes_macro() {
+        assert_eq!(OrderError::all(), &[OrderError::NotFound, OrderError::Locked]);
+        assert_eq!(OrderError::Locked.code(), 10002);
+        assert_eq!(OrderError::Locked as u32, 10002);
+        let info = OrderError::NotFound.info();
+        assert_eq!(info.key(), "order.NotFound");
+        assert_eq!(info.http_status, 404);
+        assert!(!info.retryable);
+        assert_eq!(OrderError::from_code(10002), Some(OrderError::Locked));
+        assert_eq!(OrderError::from_code(404), None);
+        assert_eq!(OrderError::NotFound.to_string(), "order not found");
+    }
+
+    // Test registering codes and rejecting conflicts
+    #[test]
+    fn test_error_registry() {
+        let registry = ErrorRegistry::new();
+        registry.register_all::<OrderError>().unwrap();
+        registry.register_all::<OrderError>().unwrap();
+        assert_eq!(registry.lookup(10002).unwrap().name, "Locked");
+        assert_eq!(registry.namespace("order").len(), 2);
+        assert!(registry.namespace("common").is_empty());
+
+        let mut clash = OrderError::NotFound.info();
+        clash.namespace = "payment";
+        assert!(registry.register(clash).is_err());
+        let mut renumbered = OrderError::NotFound.info();
+        renumbered.code = 10003;
+        assert!(registry.register(renumbered).is_err());
+
+        assert_eq!(error_registry().lookup(503).unwrap().key(), "common.Unavailable");
+    }
+
+    // Test conversions between error codes, State, ApiError and Response
+    #[test]
+    fn test_api_error_conversions() {
+        register_error_codes::<OrderError>().unwrap();
+
+        let state = State::from(OrderError::Locked);
+        assert_eq!(state.ret_code, 10002);
+        assert_eq!(state.ret_message.as_deref(), Some("order is being updated"));
+        assert_eq!(OrderError::from_state(&state), Some(OrderError::Locked));
+        assert!(!state.is_success());
+
+        // A bare state picks up the registered description
+        let error = ApiError::from(State { ret_code: 10002, ret_message: None });
+        assert!(error.is(OrderError::Locked));
+        assert!(error.is_retryable());
+        assert_eq!(error.http_status(), 409);
+        assert_eq!(error.message(), "order is being updated");
+        assert_eq!(error.kind::<OrderError>(), Some(OrderError::Locked));
+        assert_eq!(error.kind::<CommonError>(), None);
+        assert_eq!(error.to_string(), "order.Locked (10002): order is being updated");
+
+        // Unregistered codes still convert
+        let error = ApiError::new(777, "odd");
+        assert_eq!(error.info(), None);
+        assert_eq!(error.http_status(), 500);
+        assert_eq!(ApiError::new(418, "teapot").http_status(), 418);
+        assert_eq!(error.to_string(), "error 777: odd");
+
+        let error = OrderError::NotFound.with_message("order 42 not found");
+        assert_eq!(State::from(error.clone()).ret_message.as_deref(), Some("order 42 not found"));
+        let resp = Response::<u32>::from(error);
+        assert_eq!(resp.state.as_ref().unwrap().ret_code, 10001);
+        assert!(resp.into_result().unwrap_err().is(OrderError::NotFound));
+    }
+
+    // Test Response::raise, error and into_result
+    #[test]
+    fn test_response_into_result() {
+        // Without a state the response is not successful, matching is_success
+        let resp = Response::<u32>::new(Some(5));
+        assert!(!resp.is_success());
+        let error = resp.into_result().unwrap_err();
+        assert!(error.is(CommonError::Internal));
+        assert_eq!(error.message(), "response state is missing");
+
+        let mut resp = Response::<u32>::new(Some(5));
+        assert_eq!(resp.error(), None);
+        resp.state = Some(State { ret_code: 200, ret_message: Some("OK".to_string()) });
+        assert_eq!(resp.into_result().unwrap(), Some(5));
+
+        let mut resp = Response::<u32>::new(Some(5));
+        resp.raise(CommonError::Timeout);
+        let error = resp.error().unwrap();
+        assert!(error.is_retryable());
+        assert_eq!(error.http_status(), 504);
+        assert!(resp.into_result().is_err());
+
+        let resp = Response::<u32>::from_error(CommonError::Forbidden);
+        assert_eq!(resp.body, None);
+        match resp.into_result().unwrap_err().kind::<CommonError>() {
+            Some(CommonError::Forbidden) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    // Test the standard success State and Response::ok
+    #[test]
+    fn test_response_ok() {
+        let state = State::success();
+        assert_eq!(state.ret_code, SUCCESS_CODE);
+        assert_eq!(state.ret_message.as_deref(), Some(SUCCESS_MESSAGE));
+        assert!(state.is_success());
+
+        let resp = Response::ok(3u32);
+        assert!(resp.is_success());
+        assert_eq!(
+            serde_json::to_string(&resp).unwrap(),
+            r#"{"state":{"ret_code":200,"ret_message":"OK"},"body":3}"#
+        );
+        assert!(Response::<u32>::ok_empty().is_success());
+        // Without a state the outcome is unknown
+        assert!(!Response::<u32>::new(Some(3)).is_success());
+        assert_eq!(Response::ok(3u32).into_body().unwrap(), 3);
+        assert!(Response::<u32>::ok_empty().into_body().unwrap_err().is(CommonError::Internal));
+    }
+
+    // Test Response::from on Ok and Err results
+    #[test]
+    fn test_response_from_result() {
+        let resp = Response::from(Ok::<_, ApiError>("done".to_string()));
+        assert!(resp.is_success());
+        assert_eq!(resp.body.as_deref(), Some("done"));
+
+        let resp = Response::<String>::from(Err(CommonError::Conflict));
+        assert_eq!(resp.state.as_ref().unwrap().ret_code, 409);
+        assert_eq!(resp.body, None);
+
+        // The internal error text stays out of the response
+        let error = ApiError::from(anyhow!("disk full at /var/db"));
+        assert_eq!(error.internal_error().unwrap().to_string(), "disk full at /var/db");
+        assert_eq!(std::error::Error::source(&error).unwrap().to_string(), "disk full at /var/db");
+        let resp = Response::<u32>::from(Err(anyhow!("disk full at /var/db")));
+        assert_eq!(resp.state.as_ref().unwrap().ret_message.as_deref(), Some("internal error"));
+        let error = resp.into_result().unwrap_err();
+        assert!(error.is(CommonError::Internal));
+        assert_eq!(error.message(), "internal error");
+        assert_eq!(error.internal_error().map(|e| e.to_string()), None);
+
+        // Detailed messages are opt-in
+        let error = ApiError::detailed(anyhow!("disk full"));
+        assert!(error.is(CommonError::Internal));
+        assert_eq!(error.message(), "disk full");
+
+        // An ApiError wrapped in anyhow keeps its code
+        let wrapped = anyhow::Error::new(ApiError::from(CommonError::Unauthorized));
+        assert!(ApiError::from(wrapped).is(CommonError::Unauthorized));
+    }
+
+    // Test map and and_then on the body
+    #[test]
+    fn test_response_map_and_then() {
+        let resp = Response::ok(2u32).map(|n| n * 10);
+        assert_eq!(resp.body, Some(20));
+        assert!(resp.is_success());
+
+        let resp = Response::ok("42".to_string()).and_then(|s| s.parse::<u32>().or_code(CommonError::BadRequest));
+        assert_eq!(resp.body, Some(42));
+
+        let resp = Response::ok("x".to_string()).and_then(|s| s.parse::<u32>().or_code(CommonError::BadRequest));
+        assert_eq!(resp.body, None);
+        let error = resp.error().unwrap();
+        assert!(error.is(CommonError::BadRequest));
+        assert_eq!(error.message(), "invalid digit found in string");
+
+        let mut failed = Response::<u32>::new(Some(1));
+        failed.raise(CommonError::NotFound);
+        let resp = failed.and_then(|n| Ok::<_, ApiError>(n + 1));
+        assert_eq!(resp.body, None);
+        assert!(resp.error().unwrap().is(CommonError::NotFound));
+    }
+
+    // Test handlers written with ? via Response::handle
+    #[test]
+    fn test_response_handle() {
+        fn find(id: u32) -> ApiResult<String> {
+            let names = ["zero", "one"];
+            let name = names.get(id as usize).or_code(CommonError::NotFound)?;
+            let parsed: u32 = "7".parse().or_code(CommonError::BadRequest)?;
+            Ok(format!("{}-{}", name, parsed))
+        }
+
+        assert_eq!(Response::handle(|| find(1)).body.as_deref(), Some("one-7"));
+        let resp = Response::handle(|| find(5));
+        assert!(resp.error().unwrap().is(CommonError::NotFound));
+        assert_eq!(resp.into_result().unwrap_err().message(), "not found");
+    }
 }
@@ -0,0 +1,788 @@
+// Wire formats for Request and Response envelopes
+// JSON, MessagePack and CBOR encode the envelope as a map, bincode as a fixed sequence of
+// fields, and protobuf uses the ENVELOPE_PROTO schema with the body embedded as bytes.
+#[cfg(feature = "codec")]
+use anyhow::{anyhow, Result};
+#[cfg(feature = "codec")]
+use serde_derive::{Deserialize, Serialize};
+
+#[cfg(feature = "codec")]
+use super::{Request, RequestHeader, Response, ResponseHeader, State};
+
+// Protobuf schema of the envelope, for generating clients in other languages
+#[cfg(feature = "codec")]
+pub const ENVELOPE_PROTO: &str = r#"syntax = "proto3";
+
+package acovo.proto;
+
+message State {
+  uint32 ret_code = 1;
+  optional string ret_message = 2;
+}
+
+message RequestHeader {
+  optional string version = 1;
+  optional string action = 2;
+  optional string sign = 3;
+  optional string timestamp = 4;
+  optional string sender = 5;
+}
+
+// body holds the request body in the agreed body format (MessagePack by default)
+message Request {
+  RequestHeader head = 1;
+  optional bytes body = 2;
+}
+
+message ResponseHeader {
+  optional string version = 1;
+  optional string action = 2;
+  optional string sign = 3;
+  optional string timestamp = 4;
+  optional string host = 5;
+}
+
+// body holds the response body in the agreed body format (MessagePack by default)
+message Response {
+  State state = 1;
+  ResponseHeader head = 2;
+  optional bytes body = 3;
+}
+"#;
+
+// Codec encodes and decodes Request and Response envelopes in one wire format
+#[cfg(feature = "codec")]
+pub trait Codec {
+    // Returns the MIME type to send in the Content-Type header
+    fn content_type(&self) -> &'static str;
+
+    // Encodes a request envelope
+    fn encode_request<T: serde::Serialize>(&self, request: &Request<T>) -> Result<Vec<u8>>;
+
+    // Decodes a request envelope
+    fn decode_request<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Request<T>>;
+
+    // Encodes a response envelope
+    fn encode_response<T: serde::Serialize>(&self, response: &Response<T>) -> Result<Vec<u8>>;
+
+    // Decodes a response envelope
+    fn decode_response<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Response<T>>;
+}
+
+// WireFormat names a supported format and dispatches to its codec
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WireFormat {
+    Json,
+    MessagePack,
+    Cbor,
+    Bincode,
+    // Protobuf envelope with a MessagePack body
+    Protobuf,
+}
+
+// Implementation of WireFormat methods
+#[cfg(feature = "codec")]
+impl WireFormat {
+    // Every format, JSON first as the most widely understood
+    pub const ALL: [WireFormat; 5] = [
+        WireFormat::Json,
+        WireFormat::MessagePack,
+        WireFormat::Cbor,
+        WireFormat::Bincode,
+        WireFormat::Protobuf,
+    ];
+
+    // Returns the MIME type of the format
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            WireFormat::Json => "application/json",
+            WireFormat::MessagePack => "application/msgpack",
+            WireFormat::Cbor => "application/cbor",
+            WireFormat::Bincode => "application/x-bincode",
+            WireFormat::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    // Returns the format of a Content-Type value such as "application/json; charset=utf-8"
+    // Common aliases (application/x-msgpack, application/protobuf, ...) are accepted.
+    pub fn from_content_type(value: &str) -> Option<WireFormat> {
+        let media_type = value.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/json" | "text/json" => Some(WireFormat::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(WireFormat::MessagePack)
+            }
+            "application/cbor" => Some(WireFormat::Cbor),
+            "application/x-bincode" | "application/bincode" => Some(WireFormat::Bincode),
+            "application/x-protobuf" | "application/protobuf" | "application/vnd.google.protobuf" => {
+                Some(WireFormat::Protobuf)
+            }
+            _ => None,
+        }
+    }
+
+    // Picks the response format for an Accept header value
+    // Each supported format gets the quality of the most specific matching media range
+    // (exact type, then "application/*", then "*/*"); the highest non-zero quality wins and
+    // ties go to the earlier entry of supported. An empty header accepts the first entry.
+    pub fn negotiate(accept: &str, supported: &[WireFormat]) -> Option<WireFormat> {
+        if accept.trim().is_empty() {
+            return supported.first().copied();
+        }
+
+        let ranges: Vec<(String, f32)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let media_range = parts.next()?.trim().to_ascii_lowercase();
+                if media_range.is_empty() {
+                    return None;
+                }
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .filter_map(|q| q.trim().parse::<f32>().ok())
+                    .next()
+                    .unwrap_or(1.0);
+                Some((media_range, quality))
+            })
+            .collect();
+
+        let mut best: Option<(WireFormat, f32)> = None;
+        for format in supported {
+            // Specificity 3 is an exact type (or alias), 2 "application/*", 1 "*/*"
+            let mut matched: Option<(u8, f32)> = None;
+            for (media_range, quality) in &ranges {
+                let specificity = if WireFormat::from_content_type(media_range) == Some(*format) {
+                    3
+                } else if media_range == "application/*" {
+                    2
+                } else if media_range == "*/*" {
+                    1
+                } else {
+                    continue;
+                };
+                if matched.map(|(s, _)| specificity > s).unwrap_or(true) {
+                    matched = Some((specificity, *quality));
+                }
+            }
+            if let Some((_, quality)) = matched {
+                if quality > 0.0 && best.map(|(_, q)| quality > q).unwrap_or(true) {
+                    best = Some((*format, quality));
+                }
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+}
+
+#[cfg(feature = "codec")]
+impl std::fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.content_type())
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Codec for WireFormat {
+    fn content_type(&self) -> &'static str {
+        WireFormat::content_type(self)
+    }
+
+    fn encode_request<T: serde::Serialize>(&self, request: &Request<T>) -> Result<Vec<u8>> {
+        match self {
+            WireFormat::Json => JsonCodec.encode_request(request),
+            WireFormat::MessagePack => MessagePackCodec.encode_request(request),
+            WireFormat::Cbor => CborCodec.encode_request(request),
+            WireFormat::Bincode => BincodeCodec.encode_request(request),
+            WireFormat::Protobuf => ProtobufCodec::default().encode_request(request),
+        }
+    }
+
+    fn decode_request<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Request<T>> {
+        match self {
+            WireFormat::Json => JsonCodec.decode_request(bytes),
+            WireFormat::MessagePack => MessagePackCodec.decode_request(bytes),
+            WireFormat::Cbor => CborCodec.decode_request(bytes),
+            WireFormat::Bincode => BincodeCodec.decode_request(bytes),
+            WireFormat::Protobuf => ProtobufCodec::default().decode_request(bytes),
+        }
+    }
+
+    fn encode_response<T: serde::Serialize>(&self, response: &Response<T>) -> Result<Vec<u8>> {
+        match self {
+            WireFormat::Json => JsonCodec.encode_response(response),
+            WireFormat::MessagePack => MessagePackCodec.encode_response(response),
+            WireFormat::Cbor => CborCodec.encode_response(response),
+            WireFormat::Bincode => BincodeCodec.encode_response(response),
+            WireFormat::Protobuf => ProtobufCodec::default().encode_response(response),
+        }
+    }
+
+    fn decode_response<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Response<T>> {
+        match self {
+            WireFormat::Json => JsonCodec.decode_response(bytes),
+            WireFormat::MessagePack => MessagePackCodec.decode_response(bytes),
+            WireFormat::Cbor => CborCodec.decode_response(bytes),
+            WireFormat::Bincode => BincodeCodec.decode_response(bytes),
+            WireFormat::Protobuf => ProtobufCodec::default().decode_response(bytes),
+        }
+    }
+}
+
+// Implements Codec for a format whose serde encoding handles the envelope directly
+#[cfg(feature = "codec")]
+macro_rules! serde_codec {
+    ($codec:ident, $content_type:expr) => {
+        #[cfg(feature = "codec")]
+        impl Codec for $codec {
+            fn content_type(&self) -> &'static str {
+                $content_type
+            }
+
+            fn encode_request<T: serde::Serialize>(&self, request: &Request<T>) -> Result<Vec<u8>> {
+                self.encode(request)
+            }
+
+            fn decode_request<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Request<T>> {
+                self.decode(bytes)
+            }
+
+            fn encode_response<T: serde::Serialize>(&self, response: &Response<T>) -> Result<Vec<u8>> {
+                self.encode(response)
+            }
+
+            fn decode_response<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Response<T>> {
+                self.decode(bytes)
+            }
+        }
+    };
+}
+
+// JsonCodec encodes envelopes as JSON, the format used by serde_json elsewhere in this module
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+// Implementation of JsonCodec methods
+#[cfg(feature = "codec")]
+impl JsonCodec {
+    // Encodes any serializable value
+    pub fn encode<V: serde::Serialize>(&self, value: &V) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).map_err(|e| anyhow!("JSON encoding failed: {}", e))
+    }
+
+    // Decodes any deserializable value
+    pub fn decode<V: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<V> {
+        serde_json::from_slice(bytes).map_err(|e| anyhow!("JSON decoding failed: {}", e))
+    }
+}
+
+serde_codec!(JsonCodec, "application/json");
+
+// MessagePackCodec encodes envelopes as MessagePack maps keyed by field name
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackCodec;
+
+// Implementation of MessagePackCodec methods
+#[cfg(feature = "codec")]
+impl MessagePackCodec {
+    // Encodes any serializable value; structs become maps so omitted fields stay decodable
+    pub fn encode<V: serde::Serialize>(&self, value: &V) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(value).map_err(|e| anyhow!("MessagePack encoding failed: {}", e))
+    }
+
+    // Decodes any deserializable value
+    pub fn decode<V: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<V> {
+        rmp_serde::from_slice(bytes).map_err(|e| anyhow!("MessagePack decoding failed: {}", e))
+    }
+}
+
+serde_codec!(MessagePackCodec, "application/msgpack");
+
+// CborCodec encodes envelopes as CBOR (RFC 8949)
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborCodec;
+
+// Implementation of CborCodec methods
+#[cfg(feature = "codec")]
+impl CborCodec {
+    // Encodes any serializable value
+    pub fn encode<V: serde::Serialize>(&self, value: &V) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(|e| anyhow!("CBOR encoding failed: {}", e))?;
+        Ok(bytes)
+    }
+
+    // Decodes any deserializable value
+    pub fn decode<V: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<V> {
+        ciborium::from_reader(bytes).map_err(|e| anyhow!("CBOR decoding failed: {}", e))
+    }
+}
+
+serde_codec!(CborCodec, "application/cbor");
+
+// BincodeCodec encodes envelopes with bincode
+// Bincode is not self-describing: the envelope is written with every field present, and
+// body types must not skip fields when serializing (no skip_serializing_if).
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeCodec;
+
+// Implementation of BincodeCodec methods
+#[cfg(feature = "codec")]
+impl BincodeCodec {
+    // Encodes any serializable value
+    pub fn encode<V: serde::Serialize>(&self, value: &V) -> Result<Vec<u8>> {
+        bincode::serialize(value).map_err(|e| anyhow!("bincode encoding failed: {}", e))
+    }
+
+    // Decodes any deserializable value
+    pub fn decode<V: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<V> {
+        bincode::deserialize(bytes).map_err(|e| anyhow!("bincode decoding failed: {}", e))
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Codec for BincodeCodec {
+    fn content_type(&self) -> &'static str {
+        "application/x-bincode"
+    }
+
+    fn encode_request<T: serde::Serialize>(&self, request: &Request<T>) -> Result<Vec<u8>> {
+        self.encode(&FlatRequestRef {
+            head: request.head.as_ref().map(PbRequestHeader::from),
+            body: &request.body,
+        })
+    }
+
+    fn decode_request<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Request<T>> {
+        let flat: FlatRequest<T> = self.decode(bytes)?;
+        Ok(Request {
+            head: flat.head.map(RequestHeader::from),
+            body: flat.body,
+        })
+    }
+
+    fn encode_response<T: serde::Serialize>(&self, response: &Response<T>) -> Result<Vec<u8>> {
+        self.encode(&FlatResponseRef {
+            state: response.state.as_ref().map(PbState::from),
+            head: response.head.as_ref().map(PbResponseHeader::from),
+            body: &response.body,
+        })
+    }
+
+    fn decode_response<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Response<T>> {
+        let flat: FlatResponse<T> = self.decode(bytes)?;
+        Ok(Response {
+            state: flat.state.map(State::from),
+            head: flat.head.map(ResponseHeader::from),
+            body: flat.body,
+        })
+    }
+}
+
+// ProtobufCodec encodes envelopes with the ENVELOPE_PROTO schema
+// The body is carried as bytes in the body format, MessagePack unless set with with_body_format.
+#[cfg(feature = "codec")]
+#[derive(Debug, Clone, Copy)]
+pub struct ProtobufCodec {
+    body_format: WireFormat,
+}
+
+#[cfg(feature = "codec")]
+impl Default for ProtobufCodec {
+    fn default() -> Self {
+        ProtobufCodec {
+            body_format: WireFormat::MessagePack,
+        }
+    }
+}
+
+// Implementation of ProtobufCodec methods
+#[cfg(feature = "codec")]
+impl ProtobufCodec {
+    // Creates a codec with MessagePack bodies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Selects the format of the embedded body; protobuf itself cannot be used
+    pub fn with_body_format(mut self, format: WireFormat) -> Self {
+        self.body_format = format;
+        self
+    }
+
+    // Returns the format of the embedded body
+    pub fn body_format(&self) -> WireFormat {
+        self.body_format
+    }
+
+    // Encodes a body in the body format
+    fn encode_body<T: serde::Serialize>(&self, body: &Option<T>) -> Result<Option<Vec<u8>>> {
+        let Some(body) = body else { return Ok(None) };
+        let bytes = match self.body_format {
+            WireFormat::Json => JsonCodec.encode(body)?,
+            WireFormat::MessagePack => MessagePackCodec.encode(body)?,
+            WireFormat::Cbor => CborCodec.encode(body)?,
+            WireFormat::Bincode => BincodeCodec.encode(body)?,
+            WireFormat::Protobuf => return Err(anyhow!("protobuf cannot be used as the body format")),
+        };
+        Ok(Some(bytes))
+    }
+
+    // Decodes a body in the body format
+    fn decode_body<T: serde::de::DeserializeOwned>(&self, bytes: Option<Vec<u8>>) -> Result<Option<T>> {
+        let Some(bytes) = bytes else { return Ok(None) };
+        let body = match self.body_format {
+            WireFormat::Json => JsonCodec.decode(&bytes)?,
+            WireFormat::MessagePack => MessagePackCodec.decode(&bytes)?,
+            WireFormat::Cbor => CborCodec.decode(&bytes)?,
+            WireFormat::Bincode => BincodeCodec.decode(&bytes)?,
+            WireFormat::Protobuf => return Err(anyhow!("protobuf cannot be used as the body format")),
+        };
+        Ok(Some(body))
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Codec for ProtobufCodec {
+    fn content_type(&self) -> &'static str {
+        "application/x-protobuf"
+    }
+
+    fn encode_request<T: serde::Serialize>(&self, request: &Request<T>) -> Result<Vec<u8>> {
+        let message = PbRequest {
+            head: request.head.as_ref().map(PbRequestHeader::from),
+            body: self.encode_body(&request.body)?,
+        };
+        Ok(prost::Message::encode_to_vec(&message))
+    }
+
+    fn decode_request<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Request<T>> {
+        let message = <PbRequest as prost::Message>::decode(bytes)
+            .map_err(|e| anyhow!("protobuf decoding failed: {}", e))?;
+        Ok(Request {
+            head: message.head.map(RequestHeader::from),
+            body: self.decode_body(message.body)?,
+        })
+    }
+
+    fn encode_response<T: serde::Serialize>(&self, response: &Response<T>) -> Result<Vec<u8>> {
+        let message = PbResponse {
+            state: response.state.as_ref().map(PbState::from),
+            head: response.head.as_ref().map(PbResponseHeader::from),
+            body: self.encode_body(&response.body)?,
+        };
+        Ok(prost::Message::encode_to_vec(&message))
+    }
+
+    fn decode_response<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<Response<T>> {
+        let message = <PbResponse as prost::Message>::decode(bytes)
+            .map_err(|e| anyhow!("protobuf decoding failed: {}", e))?;
+        Ok(Response {
+            state: message.state.map(State::from),
+            head: message.head.map(ResponseHeader::from),
+            body: self.decode_body(message.body)?,
+        })
+    }
+}
+
+// Protobuf messages of ENVELOPE_PROTO; the header and state messages also serve as the
+// fixed-layout (never skipped) serde form used by bincode
+#[cfg(feature = "codec")]
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct PbState {
+    #[prost(uint32, tag = "1")]
+    ret_code: u32,
+    #[prost(string, optional, tag = "2")]
+    ret_message: Option<String>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct PbRequestHeader {
+    #[prost(string, optional, tag = "1")]
+    version: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    action: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    sign: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    timestamp: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    sender: Option<String>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Clone, PartialEq, prost::Message)]
+struct PbRequest {
+    #[prost(message, optional, tag = "1")]
+    head: Option<PbRequestHeader>,
+    #[prost(bytes = "vec", optional, tag = "2")]
+    body: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct PbResponseHeader {
+    #[prost(string, optional, tag = "1")]
+    version: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    action: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    sign: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    timestamp: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    host: Option<String>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Clone, PartialEq, prost::Message)]
+struct PbResponse {
+    #[prost(message, optional, tag = "1")]
+    state: Option<PbState>,
+    #[prost(message, optional, tag = "2")]
+    head: Option<PbResponseHeader>,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    body: Option<Vec<u8>>,
+}
+
+// Fixed-layout envelopes for bincode
+#[cfg(feature = "codec")]
+#[derive(Serialize)]
+struct FlatRequestRef<'a, T> {
+    head: Option<PbRequestHeader>,
+    body: &'a Option<T>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Deserialize)]
+struct FlatRequest<T> {
+    head: Option<PbRequestHeader>,
+    body: Option<T>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Serialize)]
+struct FlatResponseRef<'a, T> {
+    state: Option<PbState>,
+    head: Option<PbResponseHeader>,
+    body: &'a Option<T>,
+}
+
+#[cfg(feature = "codec")]
+#[derive(Deserialize)]
+struct FlatResponse<T> {
+    state: Option<PbState>,
+    head: Option<PbResponseHeader>,
+    body: Option<T>,
+}
+
+#[cfg(feature = "codec")]
+impl From<&State> for PbState {
+    fn from(state: &State) -> Self {
+        PbState {
+            ret_code: state.ret_code,
+            ret_message: state.ret_message.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+impl From<PbState> for State {
+    fn from(state: PbState) -> Self {
+        State {
+            ret_code: state.ret_code,
+            ret_message: state.ret_message,
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+impl From<&RequestHeader> for PbRequestHeader {
+    fn from(head: &RequestHeader) -> Self {
+        PbRequestHeader {
+            version: head.version.clone(),
+            action: head.action.clone(),
+            sign: head.sign.clone(),
+            timestamp: head.timestamp.clone(),
+            sender: head.sender.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+impl From<PbRequestHeader> for RequestHeader {
+    fn from(head: PbRequestHeader) -> Self {
+        RequestHeader {
+            version: head.version,
+            action: head.action,
+            sign: head.sign,
+            timestamp: head.timestamp,
+            sender: head.sender,
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+impl From<&ResponseHeader> for PbResponseHeader {
+    fn from(head: &ResponseHeader) -> Self {
+        PbResponseHeader {
+            version: head.version.clone(),
+            action: head.action.clone(),
+            sign: head.sign.clone(),
+            timestamp: head.timestamp.clone(),
+            host: head.host.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+impl From<PbResponseHeader> for ResponseHeader {
+    fn from(head: PbResponseHeader) -> Self {
+        ResponseHeader {
+            version: head.version,
+            action: head.action,
+            sign: head.sign,
+            timestamp: head.timestamp,
+            host: head.host,
+        }
+    }
+}
+
+// Unit tests for the codec module
+#[cfg(test)]
+#[cfg(feature = "codec")]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Order {
+        id: u64,
+        items: Vec<String>,
+        note: Option<String>,
+    }
+
+    fn sample_request() -> Request<Order> {
+        let mut req = Request::new(Some(Order {
+            id: 42,
+            items: vec!["apple".to_string(), "pear".to_string()],
+            note: None,
+        }));
+        req.head = Some(RequestHeader::new_with_sign_action("abc".to_string(), "order.create".to_string()));
+        req
+    }
+
+    fn sample_response() -> Response<Order> {
+        let mut resp = Response::ok(Order {
+            id: 7,
+            items: vec![],
+            note: Some("gift".to_string()),
+        });
+        resp.head = Some(ResponseHeader::new_with_sign("def".to_string()));
+        resp
+    }
+
+    // Test that every format round-trips requests and responses, with and without optional parts
+    #[test]
+    fn test_round_trip_all_formats() {
+        for format in WireFormat::ALL {
+            let req = sample_request();
+            let bytes = format.encode_request(&req).unwrap();
+            assert_eq!(format.decode_request::<Order>(&bytes).unwrap(), req, "{}", format);
+
+            let bare = Request::<Order>::new(None);
+            let bytes = format.encode_request(&bare).unwrap();
+            assert_eq!(format.decode_request::<Order>(&bytes).unwrap(), bare, "{}", format);
+
+            let resp = sample_response();
+            let bytes = format.encode_response(&resp).unwrap();
+            assert_eq!(format.decode_response::<Order>(&bytes).unwrap(), resp, "{}", format);
+
+            let failed = Response::<Order>::new_with_state(503, "busy");
+            let bytes = format.encode_response(&failed).unwrap();
+            assert_eq!(format.decode_response::<Order>(&bytes).unwrap(), failed, "{}", format);
+        }
+    }
+
+    // Test that JSON output is unchanged from plain serde_json and that binary formats are smaller
+    #[test]
+    fn test_json_compatibility_and_size() {
+        let resp = sample_response();
+        let json = JsonCodec.encode_response(&resp).unwrap();
+        assert_eq!(json, serde_json::to_vec(&resp).unwrap());
+        for format in [WireFormat::MessagePack, WireFormat::Cbor, WireFormat::Bincode, WireFormat::Protobuf] {
+            let bytes = format.encode_response(&resp).unwrap();
+            assert!(bytes.len() < json.len(), "{} is {} bytes, JSON {}", format, bytes.len(), json.len());
+        }
+        assert!(JsonCodec.decode_response::<Order>(b"not json").is_err());
+        assert!(ProtobufCodec::new().decode_response::<Order>(&[0xff, 0xff]).is_err());
+    }
+
+    // Test the protobuf envelope layout and the configurable body format
+    #[test]
+    fn test_protobuf_envelope() {
+        let resp = Response::<u32>::new_with_state(404, "missing");
+        let bytes = ProtobufCodec::new().encode_response(&resp).unwrap();
+        // Field 1 (state), length-delimited, holding ret_code 404 and ret_message
+        assert_eq!(&bytes[..5], &[0x0a, 0x0c, 0x08, 0x94, 0x03]);
+
+        let codec = ProtobufCodec::new().with_body_format(WireFormat::Json);
+        assert_eq!(codec.body_format(), WireFormat::Json);
+        let bytes = codec.encode_request(&sample_request()).unwrap();
+        let message = <PbRequest as prost::Message>::decode(bytes.as_slice()).unwrap();
+        let body: Order = serde_json::from_slice(&message.body.unwrap()).unwrap();
+        assert_eq!(body.id, 42);
+        assert_eq!(message.head.unwrap().action.as_deref(), Some("order.create"));
+
+        let nested = ProtobufCodec::new().with_body_format(WireFormat::Protobuf);
+        assert!(nested.encode_request(&sample_request()).is_err());
+        assert!(ENVELOPE_PROTO.contains("message Response"));
+    }
+
+    // Test Content-Type parsing
+    #[test]
+    fn test_from_content_type() {
+        for format in WireFormat::ALL {
+            assert_eq!(WireFormat::from_content_type(format.content_type()), Some(format));
+            assert_eq!(Codec::content_type(&format), format.content_type());
+        }
+        assert_eq!(WireFormat::from_content_type("Application/JSON; charset=utf-8"), Some(WireFormat::Json));
+        assert_eq!(WireFormat::from_content_type("application/x-msgpack"), Some(WireFormat::MessagePack));
+        assert_eq!(WireFormat::from_content_type("application/protobuf"), Some(WireFormat::Protobuf));
+        assert_eq!(WireFormat::from_content_type("text/html"), None);
+        assert_eq!(JsonCodec.content_type(), "application/json");
+        assert_eq!(ProtobufCodec::new().content_type(), "application/x-protobuf");
+    }
+
+    // Test Accept header negotiation
+    #[test]
+    fn test_negotiate() {
+        let all = &WireFormat::ALL;
+        assert_eq!(WireFormat::negotiate("", all), Some(WireFormat::Json));
+        assert_eq!(WireFormat::negotiate("application/cbor", all), Some(WireFormat::Cbor));
+        assert_eq!(
+            WireFormat::negotiate("application/json;q=0.5, application/x-protobuf", all),
+            Some(WireFormat::Protobuf)
+        );
+        assert_eq!(
+            WireFormat::negotiate("application/msgpack;q=0.9, application/json;q=0.9", all),
+            Some(WireFormat::Json)
+        );
+        // An exact range overrides a wildcard
+        assert_eq!(
+            WireFormat::negotiate("*/*;q=0.8, application/json;q=0", all),
+            Some(WireFormat::MessagePack)
+        );
+        assert_eq!(
+            WireFormat::negotiate("application/*", &[WireFormat::Bincode, WireFormat::Json]),
+            Some(WireFormat::Bincode)
+        );
+        assert_eq!(WireFormat::negotiate("text/html", all), None);
+        assert_eq!(WireFormat::negotiate("application/cbor", &[WireFormat::Json]), None);
+    }
+}
@@ -3,13 +3,14 @@
 #[macro_export]
 macro_rules! syncall_with_timeout {
     ($r:expr,$s:expr,$e:expr,$t:expr) => {
-        let tm_start = Instant::now();
-        while (tm_start.elapsed().as_secs() <= $t as u64) {
+        let stopwatch = $crate::time::Stopwatch::start_new();
+        while (stopwatch.elapsed().as_secs() <= $t as u64) {
             let ret = $e;
             if ret.is_ok() {
                 $r = ret;
                 break;
             } else {
+                // Not counted in the retry metric: this loop spins without a retry interval
                 continue;
             }
         }
@@ -29,9 +30,15 @@ macro_rules! syncall_with_signal_timeout {
             //Mutex::unlock(value);
         }
 
-        let tm_start = Instant::now();
+        let stopwatch = $crate::time::Stopwatch::start_new();
+        let mut attempts = 0u32;
 
-        while tm_start.elapsed().as_secs() <= $t as u64 {
+        while stopwatch.elapsed().as_secs() <= $t as u64 {
+            // Only an attempt that follows a failed one counts as a retry
+            if attempts > 0 {
+                $crate::syncall::record_retry("syncall_with_signal_timeout");
+            }
+            attempts += 1;
             $r = $e;
 
             let lock = unsafe { $s.lock().unwrap() };
@@ -64,6 +71,7 @@ macro_rules! atomic_call_imt {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call_imt");
                         retry -= 1;
                     }
                 }
@@ -87,6 +95,7 @@ macro_rules! atomic_call {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call");
                         retry -= 1;
                     }
                 }
@@ -110,6 +119,7 @@ macro_rules! atomic_call_arg1 {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call_arg1");
                         retry -= 1;
                     }
                 }
@@ -133,6 +143,7 @@ macro_rules! atomic_call_arg2 {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call_arg2");
                         retry -= 1;
                     }
                 }
@@ -156,6 +167,7 @@ macro_rules! atomic_call_arg3 {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call_arg3");
                         retry -= 1;
                     }
                 }
@@ -179,6 +191,7 @@ macro_rules! atomic_call_arg4 {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("atomic_call_arg4");
                         retry -= 1;
                     }
                 }
@@ -201,6 +214,7 @@ macro_rules! state_call {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("state_call");
                         retry -= 1;
                     }
                 }
@@ -223,6 +237,7 @@ macro_rules! state_call_imt {
                     }
                     Err(e) => {
                         error!("BorrowFailed {}", retry);
+                        $crate::syncall::record_retry("state_call_imt");
                         retry -= 1;
                     }
                 }
@@ -231,6 +246,18 @@ macro_rules! state_call_imt {
     };
 }
 
+/// Count one failed attempt retried by a syncall macro
+///
+/// Called by the macros that wait or give up between attempts (not by
+/// `syncall_with_timeout`, which spins); with the `metrics` feature it increments
+/// `acovo_syncall_retries_total{call="<macro>"}`.
+#[cfg(feature = "syncall")]
+#[doc(hidden)]
+pub fn record_retry(call: &str) {
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_syncall_retry(call);
+}
+
 #[cfg(test)]
 #[cfg(feature = "syncall")]
 mod tests {
@@ -352,4 +379,57 @@ mod tests {
 
         println!("elapsed {:?}", timer.elapsed());
     }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_atomic_call_counts_retries() {
+        let cell: AtomicRefCell<Box<dyn MutableTrait>> = AtomicRefCell::new(Box::new(MutableSystem {
+            info: "".to_string(),
+        }));
+        let retries = crate::metrics::global()
+            .counter_vec(crate::metrics::SYNCALL_RETRIES_TOTAL, "", &["call"])
+            .unwrap()
+            .with_label_values(&["atomic_call"]);
+        let before = retries.get();
+
+        let held = cell.borrow();
+        let mut ret = 9;
+        atomic_call!(ret, cell, test);
+        drop(held);
+
+        assert_eq!(ret, 9);
+        assert!(retries.get() >= before + 3);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_syncall_with_signal_timeout_counts_retries() {
+        let exit = Mutex::new(false);
+        let retries = crate::metrics::global()
+            .counter_vec(crate::metrics::SYNCALL_RETRIES_TOTAL, "", &["call"])
+            .unwrap()
+            .with_label_values(&["syncall_with_signal_timeout"]);
+        let before = retries.get();
+
+        // Two failures then a success: two retries, however long the loop polls
+        let mut attempts = 0;
+        let mut ret: AnyResult<u8> = Ok(0);
+        syncall_with_signal_timeout!(
+            ret,
+            exit,
+            {
+                attempts += 1;
+                if attempts < 3 {
+                    Err(anyhow::anyhow!("busy"))
+                } else {
+                    Ok(1)
+                }
+            },
+            10,
+            0
+        );
+
+        assert_eq!(ret.unwrap(), 1);
+        assert_eq!(retries.get() - before, 2);
+    }
 }
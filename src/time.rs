@@ -94,7 +94,7 @@ pub struct LocalTimeFormatter;
 
 /// Implementation of FormatTime for LocalTimeFormatter
 /// Formats time as ISO 8601 with milliseconds (e.g., 2023-12-31T23:59:59.999)
-#[cfg(feature = "time")]
+#[cfg(all(feature = "time", feature = "trace"))]
 impl FormatTime for LocalTimeFormatter {
     fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
         write!(w, "{}", Local::now().format("%FT%T%.3f"))
@@ -103,6 +103,9 @@ impl FormatTime for LocalTimeFormatter {
 
 /// Parse a 14-character timestamp string into a DateTime<Local>
 /// Expected format: YYYYMMDDHHMMSS (e.g., 20231231235959)
+///
+/// Local times that are ambiguous or skipped because of a DST transition are
+/// rejected; use `timestamp_from_char14_zone` to pick a policy for them.
 /// 
 /// # Arguments
 /// * `timestamp` - A string slice containing the 14-character timestamp
@@ -112,11 +115,951 @@ impl FormatTime for LocalTimeFormatter {
 /// 
 /// # Errors
 /// * Returns an error if the timestamp string is not in the expected format
+/// * Returns an error if the local time is ambiguous or does not exist
 #[cfg(feature = "time")]
 pub fn timestamp_from_char14(timestamp: &str) -> AnyResult<DateTime<Local>> {
-    match Local.datetime_from_str(timestamp, "%Y%m%d%H%M%S") {
-        Ok(dt) => Ok(dt),
-        Err(e) => Err(anyhow!("{}", e)),
+    let naive = parse_char14(timestamp)?;
+    Ok(resolve_local_time(&Local, naive, DstPolicy::Error)?.datetime)
+}
+
+/// Parse a 14-character timestamp string as wall-clock time in the given timezone
+/// Expected format: YYYYMMDDHHMMSS (e.g., 20231231235959)
+///
+/// # Arguments
+/// * `timestamp` - A string slice containing the 14-character timestamp
+/// * `zone_name` - The timezone the timestamp was recorded in (e.g., "America/New_York")
+/// * `policy` - How to resolve times that are ambiguous or skipped around DST transitions
+///
+/// # Returns
+/// * A Result containing the resolved time and how it was resolved
+///
+/// # Errors
+/// * Returns an error if the timestamp or zone name is invalid
+/// * Returns an error if the time is ambiguous or skipped and `policy` is `DstPolicy::Error`
+#[cfg(feature = "time")]
+pub fn timestamp_from_char14_zone(
+    timestamp: &str,
+    zone_name: &str,
+    policy: DstPolicy,
+) -> AnyResult<ResolvedTime<Tz>> {
+    let tz: Tz = zone_name
+        .parse()
+        .map_err(|e| anyhow!("invalid timezone '{}': {}", zone_name, e))?;
+    resolve_local_time(&tz, parse_char14(timestamp)?, policy)
+}
+
+/// Parse YYYYMMDDHHMMSS into a naive wall-clock time
+#[cfg(feature = "time")]
+fn parse_char14(timestamp: &str) -> AnyResult<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d%H%M%S")
+        .map_err(|e| anyhow!("invalid timestamp '{}': {}", timestamp, e))
+}
+
+/// Policy for wall-clock times that do not map to exactly one instant
+///
+/// Around a "fall back" transition a local time occurs twice (ambiguous);
+/// around a "spring forward" transition a range of local times never occurs (gap).
+#[cfg(feature = "time")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DstPolicy {
+    /// Ambiguous: the earlier instant. Gap: shift back by the gap length (02:30 -> 01:30)
+    Earliest,
+    /// Ambiguous: the later instant. Gap: shift forward by the gap length (02:30 -> 03:30)
+    Latest,
+    /// Reject ambiguous and gap times with an error
+    Error,
+    /// Ambiguous: the earlier instant. Gap: the first instant after the gap (02:30 -> 03:00)
+    ShiftForward,
+}
+
+/// How a wall-clock time was mapped to an instant
+#[cfg(feature = "time")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalTimeKind {
+    /// The local time maps to exactly one instant
+    Unique,
+    /// The local time occurs twice; one was chosen by the policy
+    Ambiguous,
+    /// The local time does not exist; it was moved by the policy
+    Gap,
+}
+
+/// The result of resolving a wall-clock time in a timezone
+#[cfg(feature = "time")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTime<Z: TimeZone> {
+    /// The resolved instant
+    pub datetime: DateTime<Z>,
+    /// The wall-clock time that was requested
+    pub requested: chrono::NaiveDateTime,
+    /// Whether the requested time was unique, ambiguous or in a gap
+    pub kind: LocalTimeKind,
+}
+
+/// Resolve a wall-clock time in a timezone according to a DST policy
+///
+/// # Arguments
+/// * `tz` - The timezone (e.g., `chrono::Local` or a `chrono_tz::Tz`)
+/// * `naive` - The wall-clock time to resolve
+/// * `policy` - How to handle ambiguous and gap times
+///
+/// # Returns
+/// * A Result containing the resolved time and how it was resolved
+///
+/// # Errors
+/// * Returns an error if the time is ambiguous or in a gap and `policy` is `DstPolicy::Error`
+#[cfg(feature = "time")]
+pub fn resolve_local_time<Z: TimeZone>(
+    tz: &Z,
+    naive: chrono::NaiveDateTime,
+    policy: DstPolicy,
+) -> AnyResult<ResolvedTime<Z>> {
+    use chrono::{LocalResult, Offset};
+
+    let (datetime, kind) = match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => (dt, LocalTimeKind::Unique),
+        LocalResult::Ambiguous(earliest, latest) => {
+            let dt = match policy {
+                DstPolicy::Earliest | DstPolicy::ShiftForward => earliest,
+                DstPolicy::Latest => latest,
+                DstPolicy::Error => return Err(anyhow!("local time {} is ambiguous", naive)),
+            };
+            (dt, LocalTimeKind::Ambiguous)
+        }
+        LocalResult::None => {
+            // Offsets in effect a day either side of the gap
+            let offset_at = |probe: chrono::NaiveDateTime| {
+                tz.offset_from_local_datetime(&probe)
+                    .earliest()
+                    .map(|o| o.fix().local_minus_utc() as i64)
+            };
+            let before = offset_at(naive - chrono::Duration::days(1));
+            let after = offset_at(naive + chrono::Duration::days(1));
+            let with_offset = |offset: Option<i64>| {
+                offset
+                    .map(|o| tz.from_utc_datetime(&(naive - chrono::Duration::seconds(o))))
+                    .ok_or(anyhow!("cannot determine offset around local time {}", naive))
+            };
+            let dt = match policy {
+                DstPolicy::Earliest => with_offset(after)?,
+                DstPolicy::Latest => with_offset(before)?,
+                DstPolicy::ShiftForward => {
+                    // Binary search for the first instant whose wall clock passes the gap
+                    let mut lo = with_offset(after)?.timestamp();
+                    let mut hi = with_offset(before)?.timestamp();
+                    while hi - lo > 1 {
+                        let mid = lo + (hi - lo) / 2;
+                        if tz.timestamp_opt(mid, 0).unwrap().naive_local() < naive {
+                            lo = mid;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    tz.timestamp_opt(hi, 0).unwrap()
+                }
+                DstPolicy::Error => return Err(anyhow!("local time {} does not exist", naive)),
+            };
+            (dt, LocalTimeKind::Gap)
+        }
+    };
+
+    Ok(ResolvedTime {
+        datetime,
+        requested: naive,
+        kind,
+    })
+}
+
+/// Unit table used by the duration parser and formatters, ordered from largest to smallest
+/// Each entry is (suffix, length in nanoseconds)
+#[cfg(feature = "time")]
+const DURATION_UNITS: [(&str, u128); 8] = [
+    ("w", 7 * 86_400 * 1_000_000_000),
+    ("d", 86_400 * 1_000_000_000),
+    ("h", 3_600 * 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Parse a human-friendly duration string into a std::time::Duration
+/// Format: one or more `<number><unit>` pairs (e.g., "1h30m", "2d", "500ms", "1.5h")
+///
+/// Supported units are `w`, `d`, `h`, `m`, `s`, `ms`, `us` (or `µs`) and `ns`.
+/// Whitespace between pairs is ignored. A bare number without a unit is rejected
+/// so that the unit of every configured value is explicit.
+///
+/// # Arguments
+/// * `text` - A string slice containing the duration (e.g., "1h30m")
+///
+/// # Returns
+/// * A Result containing either the parsed Duration or an error
+///
+/// # Errors
+/// * Returns an error if the string is empty, has a missing or unknown unit,
+///   or overflows the Duration range
+#[cfg(feature = "time")]
+pub fn parse_duration(text: &str) -> AnyResult<std::time::Duration> {
+    let input = text.trim();
+    if input.is_empty() {
+        return Err(anyhow!("empty duration"));
+    }
+
+    let mut total_nanos: u128 = 0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+
+        // Split off the numeric part
+        let num_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if num_len == 0 {
+            return Err(anyhow!("expected a number in duration '{}'", text));
+        }
+        let (num_str, tail) = rest.split_at(num_len);
+
+        // Split off the unit part
+        let unit_len = tail
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        if unit.is_empty() {
+            return Err(anyhow!("missing unit after '{}' in duration '{}'", num_str, text));
+        }
+        let unit = if unit == "µs" { "us" } else { unit };
+        let unit_nanos = DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, nanos)| *nanos)
+            .ok_or(anyhow!("unknown unit '{}' in duration '{}'", unit, text))?;
+
+        let nanos = match num_str.split_once('.') {
+            None => num_str
+                .parse::<u128>()
+                .map_err(|e| anyhow!("invalid number '{}': {}", num_str, e))?
+                .checked_mul(unit_nanos)
+                .ok_or(anyhow!("duration '{}' is too large", text))?,
+            Some(_) => {
+                let value: f64 = num_str
+                    .parse()
+                    .map_err(|e| anyhow!("invalid number '{}': {}", num_str, e))?;
+                (value * unit_nanos as f64).round() as u128
+            }
+        };
+
+        total_nanos = total_nanos
+            .checked_add(nanos)
+            .ok_or(anyhow!("duration '{}' is too large", text))?;
+        rest = tail;
+    }
+
+    let secs = u64::try_from(total_nanos / 1_000_000_000)
+        .map_err(|_| anyhow!("duration '{}' is too large", text))?;
+    Ok(std::time::Duration::new(secs, (total_nanos % 1_000_000_000) as u32))
+}
+
+/// Parse a human-friendly duration string into a chrono::Duration
+/// Accepts the same format as `parse_duration`, plus an optional leading `-`
+/// for negative durations (e.g., "-15m")
+///
+/// # Arguments
+/// * `text` - A string slice containing the duration (e.g., "-1h30m")
+///
+/// # Returns
+/// * A Result containing either the parsed chrono::Duration or an error
+#[cfg(feature = "time")]
+pub fn parse_chrono_duration(text: &str) -> AnyResult<chrono::Duration> {
+    let input = text.trim();
+    let (negative, body) = match input.strip_prefix('-') {
+        Some(body) => (true, body),
+        None => (false, input),
+    };
+    let duration = chrono::Duration::from_std(parse_duration(body)?)
+        .map_err(|e| anyhow!("duration '{}' is out of range: {}", text, e))?;
+    Ok(if negative { -duration } else { duration })
+}
+
+/// Format a duration in compact form, listing every non-zero unit
+/// Format: e.g., "1h2m3s", "2d4h", "500ms", "0s"
+///
+/// The output can be read back with `parse_duration`.
+///
+/// # Arguments
+/// * `duration` - The duration to format
+///
+/// # Returns
+/// * A String containing the compact representation
+#[cfg(feature = "time")]
+pub fn format_duration_compact(duration: std::time::Duration) -> String {
+    let mut remaining = duration.as_nanos();
+    if remaining == 0 {
+        return "0s".to_string();
+    }
+
+    let mut result = String::new();
+    for (name, unit_nanos) in DURATION_UNITS.iter() {
+        let count = remaining / unit_nanos;
+        if count > 0 {
+            result.push_str(&format!("{}{}", count, name));
+            remaining -= count * unit_nanos;
+        }
+    }
+    result
+}
+
+/// Format a duration as a humanized string using its largest whole unit
+/// Format: e.g., "3 minutes", "1 day", "500 milliseconds"
+///
+/// # Arguments
+/// * `duration` - The duration to format
+///
+/// # Returns
+/// * A String containing the humanized representation
+#[cfg(feature = "time")]
+pub fn format_duration_human(duration: std::time::Duration) -> String {
+    const NAMES: [(&str, u128); 6] = [
+        ("week", 7 * 86_400 * 1_000_000_000),
+        ("day", 86_400 * 1_000_000_000),
+        ("hour", 3_600 * 1_000_000_000),
+        ("minute", 60 * 1_000_000_000),
+        ("second", 1_000_000_000),
+        ("millisecond", 1_000_000),
+    ];
+
+    let nanos = duration.as_nanos();
+    for (name, unit_nanos) in NAMES.iter() {
+        let count = nanos / unit_nanos;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{} {}{}", count, name, plural);
+        }
+    }
+    "0 seconds".to_string()
+}
+
+/// Format a signed offset relative to now as a humanized string
+/// A positive delta lies in the past ("3 minutes ago"), a negative one in the
+/// future ("in 2 days"); anything under one second is "just now"
+///
+/// # Arguments
+/// * `delta` - The elapsed time, i.e. `now - then`
+///
+/// # Returns
+/// * A String containing the relative representation
+#[cfg(feature = "time")]
+pub fn format_relative(delta: chrono::Duration) -> String {
+    let magnitude = delta.abs().to_std().unwrap_or_default();
+    if magnitude < std::time::Duration::from_secs(1) {
+        return "just now".to_string();
+    }
+
+    let human = format_duration_human(std::time::Duration::from_secs(magnitude.as_secs()));
+    if delta < chrono::Duration::zero() {
+        format!("in {}", human)
+    } else {
+        format!("{} ago", human)
+    }
+}
+
+/// Format a number of seconds in the past as a humanized string
+/// Takes the value returned by `fs::file_modified_seconds_ago` directly
+///
+/// # Arguments
+/// * `seconds` - Seconds elapsed since the event (negative for the future)
+///
+/// # Returns
+/// * A String such as "3 minutes ago" or "in 2 days"
+#[cfg(feature = "time")]
+pub fn format_seconds_ago(seconds: i64) -> String {
+    // Values beyond what chrono can represent saturate instead of panicking
+    let delta = chrono::Duration::try_seconds(seconds).unwrap_or(if seconds < 0 {
+        chrono::Duration::MIN
+    } else {
+        chrono::Duration::MAX
+    });
+    format_relative(delta)
+}
+
+/// Resolve a local wall-clock time in the given timezone
+/// Ambiguous times pick the earliest instant; times inside a DST gap are
+/// moved forward to the end of the gap. If the gap cannot be resolved (no offset
+/// is known around it), the time is read with `local_with_utc_offset`, so it
+/// keeps its local meaning instead of being taken as UTC.
+#[cfg(feature = "time")]
+fn resolve_local(tz: &Tz, naive: chrono::NaiveDateTime) -> DateTime<Tz> {
+    resolve_local_time(tz, naive, DstPolicy::ShiftForward)
+        .map(|resolved| resolved.datetime)
+        .unwrap_or_else(|_| local_with_utc_offset(tz, naive))
+}
+
+/// Read a wall-clock time with the offset the zone has at the same UTC instant
+/// Always succeeds; inside a DST gap the result is off by at most the gap length.
+#[cfg(feature = "time")]
+fn local_with_utc_offset(tz: &Tz, naive: chrono::NaiveDateTime) -> DateTime<Tz> {
+    use chrono::Offset;
+
+    let offset = tz.offset_from_utc_datetime(&naive).fix().local_minus_utc();
+    let utc = naive
+        .checked_sub_signed(chrono::Duration::seconds(offset as i64))
+        .unwrap_or(naive);
+    tz.from_utc_datetime(&utc)
+}
+
+/// Align a datetime down to the start of its bucket
+/// Buckets are counted on the local wall clock from local midnight, so 5-minute
+/// bars and 1-day buckets line up with what a reader in that timezone expects.
+/// Bucket sizes that do not divide a day evenly are aligned to the UNIX epoch instead.
+///
+/// # Arguments
+/// * `dt` - The datetime to align
+/// * `bucket` - The bucket size (e.g., 5 minutes); must be positive
+///
+/// # Returns
+/// * The start of the bucket containing `dt`
+#[cfg(feature = "time")]
+pub fn align_down(dt: &DateTime<Tz>, bucket: chrono::Duration) -> DateTime<Tz> {
+    let bucket_ms = bucket.num_milliseconds();
+    if bucket_ms <= 0 {
+        return *dt;
+    }
+
+    const DAY_MS: i64 = 86_400_000;
+    if bucket_ms <= DAY_MS && DAY_MS % bucket_ms == 0 {
+        let local = dt.naive_local();
+        let midnight = local.date().and_hms_opt(0, 0, 0).unwrap();
+        let offset_ms = (local - midnight).num_milliseconds();
+        let aligned = midnight + chrono::Duration::milliseconds(offset_ms - offset_ms % bucket_ms);
+        // Of two ambiguous instants, prefer the later one that does not pass dt
+        let resolved = match dt.timezone().from_local_datetime(&aligned) {
+            chrono::LocalResult::Single(x) => x,
+            chrono::LocalResult::Ambiguous(earliest, latest) => {
+                if latest <= *dt {
+                    latest
+                } else {
+                    earliest
+                }
+            }
+            chrono::LocalResult::None => resolve_local(&dt.timezone(), aligned),
+        };
+        // A boundary pushed forward out of a DST gap may land after dt
+        if resolved <= *dt {
+            return resolved;
+        }
+    }
+
+    let epoch_ms = dt.timestamp_millis();
+    let aligned_ms = epoch_ms - epoch_ms.rem_euclid(bucket_ms);
+    dt.timezone().timestamp_millis_opt(aligned_ms).unwrap()
+}
+
+/// Align a datetime up to the next bucket boundary
+/// Returns `dt` unchanged if it already sits on a boundary
+///
+/// # Arguments
+/// * `dt` - The datetime to align
+/// * `bucket` - The bucket size (e.g., 5 minutes); must be positive
+///
+/// # Returns
+/// * The first bucket boundary at or after `dt`, or None if that boundary lies
+///   beyond the latest representable datetime
+#[cfg(feature = "time")]
+pub fn align_up(dt: &DateTime<Tz>, bucket: chrono::Duration) -> Option<DateTime<Tz>> {
+    let down = align_down(dt, bucket);
+    if down == *dt {
+        Some(down)
+    } else {
+        down.checked_add_signed(bucket).map(|next| align_down(&next, bucket))
+    }
+}
+
+/// A half-open, timezone-aware time range `[start, end)`
+///
+/// The timezone of `start` is used when splitting by local days or hours and
+/// when aligning to bucket boundaries.
+#[cfg(feature = "time")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeRange {
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+}
+
+#[cfg(feature = "time")]
+impl TimeRange {
+    /// Create a new range from `start` (inclusive) to `end` (exclusive)
+    ///
+    /// # Errors
+    /// * Returns an error if `end` is before `start`
+    pub fn new(start: DateTime<Tz>, end: DateTime<Tz>) -> AnyResult<Self> {
+        if end < start {
+            return Err(anyhow!("range end {} is before start {}", end, start));
+        }
+        Ok(TimeRange { start, end: end.with_timezone(&start.timezone()) })
+    }
+
+    /// Create a range from two 14-character timestamps (YYYYMMDDHHMMSS) in the given timezone
+    ///
+    /// # Arguments
+    /// * `start` - The inclusive start, e.g. "20231231000000"
+    /// * `end` - The exclusive end, e.g. "20240101000000"
+    /// * `zone_name` - The timezone name (e.g., "Asia/Seoul")
+    ///
+    /// # Errors
+    /// * Returns an error if a timestamp or the zone name is invalid, or if `end` is before `start`
+    pub fn from_char14(start: &str, end: &str, zone_name: &str) -> AnyResult<Self> {
+        let tz: Tz = zone_name
+            .parse()
+            .map_err(|e| anyhow!("invalid timezone '{}': {}", zone_name, e))?;
+        TimeRange::new(
+            resolve_local(&tz, parse_char14(start)?),
+            resolve_local(&tz, parse_char14(end)?),
+        )
+    }
+
+    /// Inclusive start of the range
+    pub fn start(&self) -> DateTime<Tz> {
+        self.start
+    }
+
+    /// Exclusive end of the range
+    pub fn end(&self) -> DateTime<Tz> {
+        self.end
+    }
+
+    /// Length of the range
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+
+    /// Whether the range contains no instants
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Whether `dt` falls inside the range
+    pub fn contains(&self, dt: &DateTime<Tz>) -> bool {
+        self.start <= *dt && *dt < self.end
+    }
+
+    /// Whether the two ranges share at least one instant
+    pub fn overlaps(&self, other: &TimeRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// The instants shared by both ranges, or None if they do not overlap
+    pub fn intersection(&self, other: &TimeRange) -> Option<TimeRange> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(TimeRange {
+            start: self.start.max(other.start.with_timezone(&self.start.timezone())),
+            end: self.end.min(other.end.with_timezone(&self.start.timezone())),
+        })
+    }
+
+    /// The range covering both ranges, or None if they neither overlap nor touch
+    pub fn union(&self, other: &TimeRange) -> Option<TimeRange> {
+        if self.start > other.end || other.start > self.end {
+            return None;
+        }
+        Some(TimeRange {
+            start: self.start.min(other.start.with_timezone(&self.start.timezone())),
+            end: self.end.max(other.end.with_timezone(&self.start.timezone())),
+        })
+    }
+
+    /// Expand the range outward to bucket boundaries (see `align_down`)
+    /// The end stays in place if the next boundary is not representable
+    pub fn align(&self, bucket: chrono::Duration) -> TimeRange {
+        TimeRange {
+            start: align_down(&self.start, bucket),
+            end: align_up(&self.end, bucket).unwrap_or(self.end),
+        }
+    }
+
+    /// Iterate over instants from `start` in increments of `step`, stopping before `end`
+    /// A non-positive step yields nothing
+    pub fn iter_step(&self, step: chrono::Duration) -> impl Iterator<Item = DateTime<Tz>> {
+        let end = self.end;
+        let first = if step > chrono::Duration::zero() && !self.is_empty() {
+            Some(self.start)
+        } else {
+            None
+        };
+        std::iter::successors(first, move |current| {
+            current.checked_add_signed(step).filter(|next| *next < end)
+        })
+    }
+
+    /// Split the range into consecutive pieces of length `step`; the last piece may be shorter
+    pub fn split_by(&self, step: chrono::Duration) -> Vec<TimeRange> {
+        self.split_at(self.iter_step(step).skip(1).collect())
+    }
+
+    /// Split the range at local hour boundaries
+    pub fn split_by_hour(&self) -> Vec<TimeRange> {
+        let hour = chrono::Duration::hours(1);
+        let boundaries = match align_up(&self.start, hour) {
+            Some(first) => TimeRange { start: first, end: self.end.max(first) }
+                .iter_step(hour)
+                .collect(),
+            None => Vec::new(),
+        };
+        self.split_at(boundaries)
+    }
+
+    /// Split the range at local midnight boundaries
+    /// Days that are 23 or 25 hours long because of DST are kept whole
+    pub fn split_by_day(&self) -> Vec<TimeRange> {
+        let tz = self.start.timezone();
+        let mut boundaries = Vec::new();
+        let mut date = self.start.date_naive();
+        loop {
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+            let midnight = resolve_local(&tz, date.and_hms_opt(0, 0, 0).unwrap());
+            if midnight >= self.end {
+                break;
+            }
+            boundaries.push(midnight);
+        }
+        self.split_at(boundaries)
+    }
+
+    /// Cut the range at the given sorted boundaries, dropping boundaries outside it
+    fn split_at(&self, boundaries: Vec<DateTime<Tz>>) -> Vec<TimeRange> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut pieces = Vec::with_capacity(boundaries.len() + 1);
+        let mut current = self.start;
+        for boundary in boundaries {
+            if boundary > current && boundary < self.end {
+                pieces.push(TimeRange { start: current, end: boundary });
+                current = boundary;
+            }
+        }
+        pieces.push(TimeRange { start: current, end: self.end });
+        pieces
+    }
+}
+
+/// Displays the range as `[start, end)` in RFC 3339 format
+#[cfg(feature = "time")]
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {})", self.start.to_rfc3339(), self.end.to_rfc3339())
+    }
+}
+
+/// A monotonic stopwatch with laps, splits and pause/resume
+///
+/// Built on `std::time::Instant`, so it is unaffected by wall-clock changes.
+///
+/// # Example
+/// ```
+/// use acovo::time::Stopwatch;
+///
+/// let mut sw = Stopwatch::start_new();
+/// // ... load the first batch ...
+/// let first = sw.lap();
+/// sw.pause();
+/// // ... time spent here is not counted ...
+/// sw.resume();
+/// println!("first batch {:?}, total {:?}", first, sw.elapsed());
+/// ```
+#[cfg(feature = "time")]
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    // Start of the current running period, None while paused
+    running_since: Option<std::time::Instant>,
+    // Time accumulated over previous running periods
+    accumulated: std::time::Duration,
+    // Elapsed time at the end of the previous lap
+    last_lap_at: std::time::Duration,
+    laps: Vec<std::time::Duration>,
+}
+
+#[cfg(feature = "time")]
+impl Stopwatch {
+    /// Create a stopped stopwatch with no elapsed time
+    pub fn new() -> Self {
+        Stopwatch::default()
+    }
+
+    /// Create a stopwatch that is already running
+    pub fn start_new() -> Self {
+        let mut sw = Stopwatch::new();
+        sw.start();
+        sw
+    }
+
+    /// Start or resume timing; has no effect if already running
+    pub fn start(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(std::time::Instant::now());
+        }
+    }
+
+    /// Resume timing after `pause`
+    pub fn resume(&mut self) {
+        self.start();
+    }
+
+    /// Pause timing, keeping the elapsed time; has no effect if already paused
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.accumulated += since.elapsed();
+        }
+    }
+
+    /// Whether the stopwatch is currently running
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Total running time, excluding paused periods
+    pub fn elapsed(&self) -> std::time::Duration {
+        match self.running_since {
+            Some(since) => self.accumulated + since.elapsed(),
+            None => self.accumulated,
+        }
+    }
+
+    /// Total running time so far, without starting a new lap
+    pub fn split(&self) -> std::time::Duration {
+        self.elapsed()
+    }
+
+    /// Finish the current lap and return its length
+    pub fn lap(&mut self) -> std::time::Duration {
+        let now = self.elapsed();
+        let lap = now - self.last_lap_at;
+        self.last_lap_at = now;
+        self.laps.push(lap);
+        lap
+    }
+
+    /// All completed laps in order
+    pub fn laps(&self) -> &[std::time::Duration] {
+        &self.laps
+    }
+
+    /// Stop the stopwatch and clear elapsed time and laps
+    pub fn reset(&mut self) {
+        *self = Stopwatch::new();
+    }
+
+    /// Clear elapsed time and laps, then start running again
+    pub fn restart(&mut self) {
+        self.reset();
+        self.start();
+    }
+}
+
+/// A sink for elapsed times recorded by `ScopedTimer`
+#[cfg(feature = "time")]
+pub trait DurationRecorder: Send + Sync {
+    /// Record one measurement for the named operation
+    fn record(&self, name: &str, elapsed: std::time::Duration);
+}
+
+/// A thread-safe histogram of durations with fixed bucket upper bounds
+///
+/// Each bucket counts measurements less than or equal to its bound; a final
+/// overflow bucket counts everything larger. The counting is shared with
+/// `metrics::Histogram`, in seconds.
+#[cfg(feature = "time")]
+#[derive(Debug)]
+pub struct DurationHistogram {
+    bounds: Vec<std::time::Duration>,
+    core: crate::histogram::BucketCounts,
+}
+
+#[cfg(feature = "time")]
+impl DurationHistogram {
+    /// Create a histogram with the given bucket upper bounds (sorted automatically)
+    pub fn new(mut bounds: Vec<std::time::Duration>) -> Self {
+        bounds.sort();
+        bounds.dedup();
+        let core = crate::histogram::BucketCounts::new(bounds.iter().map(|b| b.as_secs_f64()).collect());
+        DurationHistogram { bounds, core }
+    }
+
+    /// Create a histogram with latency buckets from 1ms to 60s
+    pub fn latency() -> Self {
+        let millis = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000];
+        DurationHistogram::new(millis.iter().map(|ms| std::time::Duration::from_millis(*ms)).collect())
+    }
+
+    /// Record a measurement
+    pub fn observe(&self, elapsed: std::time::Duration) {
+        self.core.observe(elapsed.as_secs_f64());
+    }
+
+    /// Number of measurements recorded
+    pub fn count(&self) -> u64 {
+        self.core.count()
+    }
+
+    /// Sum of all measurements recorded
+    pub fn sum(&self) -> std::time::Duration {
+        std::time::Duration::try_from_secs_f64(self.core.sum()).unwrap_or(std::time::Duration::MAX)
+    }
+
+    /// Per-bucket counts as (upper bound, count); the overflow bucket uses `Duration::MAX`
+    pub fn buckets(&self) -> Vec<(std::time::Duration, u64)> {
+        self.bounds
+            .iter()
+            .copied()
+            .chain(std::iter::once(std::time::Duration::MAX))
+            .zip(self.core.counts())
+            .collect()
+    }
+
+    /// Upper bound of the bucket containing the given quantile (0.0 to 1.0)
+    /// Returns None if nothing has been recorded
+    pub fn quantile(&self, q: f64) -> Option<std::time::Duration> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+        let target = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= target {
+                return Some(bound);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "time")]
+impl DurationRecorder for DurationHistogram {
+    fn record(&self, _name: &str, elapsed: std::time::Duration) {
+        self.observe(elapsed);
+    }
+}
+
+/// A guard that measures the time until it is dropped
+///
+/// On drop the elapsed time is passed to the recorder, if any, and, with the
+/// `trace` feature, emitted as a `tracing` event under the `acovo::timing` target.
+/// Operations that take longer than the slow threshold are logged at WARN.
+///
+/// # Example
+/// ```
+/// use acovo::time::ScopedTimer;
+/// use std::time::Duration;
+///
+/// fn load_batch() {
+///     let _timer = ScopedTimer::new("load_batch").warn_after(Duration::from_secs(2));
+///     // ... work ...
+/// } // elapsed time is reported here
+/// ```
+#[cfg(feature = "time")]
+pub struct ScopedTimer {
+    name: String,
+    stopwatch: Stopwatch,
+    slow_threshold: Option<std::time::Duration>,
+    recorder: Option<std::sync::Arc<dyn DurationRecorder>>,
+    #[cfg(feature = "trace")]
+    level: tracing::Level,
+    armed: bool,
+}
+
+#[cfg(feature = "time")]
+impl ScopedTimer {
+    /// Start timing the named operation
+    pub fn new(name: &str) -> Self {
+        ScopedTimer {
+            name: name.to_string(),
+            stopwatch: Stopwatch::start_new(),
+            slow_threshold: None,
+            recorder: None,
+            #[cfg(feature = "trace")]
+            level: tracing::Level::DEBUG,
+            armed: true,
+        }
+    }
+
+    /// Log at WARN instead of the normal level when the operation takes longer than `threshold`
+    pub fn warn_after(mut self, threshold: std::time::Duration) -> Self {
+        self.slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Also pass the elapsed time to `recorder` on drop
+    pub fn with_recorder(mut self, recorder: std::sync::Arc<dyn DurationRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Level used for operations under the slow threshold (DEBUG by default)
+    #[cfg(feature = "trace")]
+    pub fn with_level(mut self, level: tracing::Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Time elapsed since the timer was created
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.stopwatch.elapsed()
+    }
+
+    /// Whether the elapsed time already exceeds the slow threshold
+    pub fn is_slow(&self) -> bool {
+        self.slow_threshold
+            .map(|threshold| self.elapsed() > threshold)
+            .unwrap_or(false)
+    }
+
+    /// Drop the timer without reporting anything
+    pub fn cancel(mut self) {
+        self.armed = false;
+    }
+}
+
+#[cfg(feature = "time")]
+impl Drop for ScopedTimer {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let elapsed = self.stopwatch.elapsed();
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&self.name, elapsed);
+        }
+
+        #[cfg(feature = "trace")]
+        {
+            let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+            let name = self.name.as_str();
+            match self.slow_threshold {
+                Some(threshold) if elapsed > threshold => {
+                    tracing::warn!(
+                        target: "acovo::timing",
+                        name,
+                        elapsed_ms,
+                        threshold_ms = threshold.as_secs_f64() * 1000.0,
+                        "slow operation"
+                    );
+                }
+                _ => match self.level {
+                    tracing::Level::ERROR => tracing::error!(target: "acovo::timing", name, elapsed_ms, "timing"),
+                    tracing::Level::WARN => tracing::warn!(target: "acovo::timing", name, elapsed_ms, "timing"),
+                    tracing::Level::INFO => tracing::info!(target: "acovo::timing", name, elapsed_ms, "timing"),
+                    tracing::Level::DEBUG => tracing::debug!(target: "acovo::timing", name, elapsed_ms, "timing"),
+                    _ => tracing::trace!(target: "acovo::timing", name, elapsed_ms, "timing"),
+                },
+            }
+        }
     }
 }
 
@@ -292,4 +1235,361 @@ mod tests {
             assert_eq!(timestamp.len(), 17);
         }
     }
+
+    #[test]
+    fn test_parse_duration_units() {
+        use std::time::Duration;
+
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(2 * 86_400));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1w").unwrap(), Duration::from_secs(7 * 86_400));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration(" 1m 30s ").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("250us").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_duration("7ns").unwrap(), Duration::from_nanos(7));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        // Bare numbers have no explicit unit
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+        assert!(parse_duration("99999999999999999999999w").is_err());
+    }
+
+    #[test]
+    fn test_parse_chrono_duration() {
+        assert_eq!(parse_chrono_duration("90s").unwrap(), chrono::Duration::seconds(90));
+        assert_eq!(parse_chrono_duration("-15m").unwrap(), chrono::Duration::minutes(-15));
+        assert!(parse_chrono_duration("-").is_err());
+    }
+
+    #[test]
+    fn test_format_duration_compact() {
+        use std::time::Duration;
+
+        assert_eq!(format_duration_compact(Duration::from_secs(3723)), "1h2m3s");
+        assert_eq!(format_duration_compact(Duration::from_millis(500)), "500ms");
+        assert_eq!(format_duration_compact(Duration::ZERO), "0s");
+        assert_eq!(format_duration_compact(Duration::from_secs(2 * 86_400 + 4 * 3600)), "2d4h");
+
+        // The compact form round-trips through the parser
+        let original = Duration::from_millis(93_784_005);
+        let text = format_duration_compact(original);
+        assert_eq!(parse_duration(&text).unwrap(), original);
+    }
+
+    #[test]
+    fn test_format_duration_human() {
+        use std::time::Duration;
+
+        assert_eq!(format_duration_human(Duration::from_secs(180)), "3 minutes");
+        assert_eq!(format_duration_human(Duration::from_secs(86_400)), "1 day");
+        assert_eq!(format_duration_human(Duration::from_millis(500)), "500 milliseconds");
+        assert_eq!(format_duration_human(Duration::ZERO), "0 seconds");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        assert_eq!(format_relative(chrono::Duration::minutes(3)), "3 minutes ago");
+        assert_eq!(format_relative(chrono::Duration::days(-2)), "in 2 days");
+        assert_eq!(format_relative(chrono::Duration::milliseconds(200)), "just now");
+        assert_eq!(format_seconds_ago(3600), "1 hour ago");
+        assert_eq!(format_seconds_ago(-61), "in 1 minute");
+        assert!(format_seconds_ago(i64::MAX).ends_with(" ago"));
+        assert!(format_seconds_ago(i64::MIN).starts_with("in "));
+    }
+
+    #[test]
+    fn test_time_range_overlap_and_intersection() {
+        let a = TimeRange::from_char14("20240101000000", "20240101120000", "UTC").unwrap();
+        let b = TimeRange::from_char14("20240101060000", "20240101180000", "UTC").unwrap();
+        let c = TimeRange::from_char14("20240101120000", "20240101130000", "UTC").unwrap();
+
+        assert!(a.overlaps(&b));
+        // Half-open ranges that only touch do not overlap
+        assert!(!a.overlaps(&c));
+        assert_eq!(a.intersection(&c), None);
+
+        let shared = a.intersection(&b).unwrap();
+        assert_eq!(shared.start().hour(), 6);
+        assert_eq!(shared.end().hour(), 12);
+
+        let joined = a.union(&c).unwrap();
+        assert_eq!(joined.duration(), chrono::Duration::hours(13));
+        let far = TimeRange::from_char14("20240102000000", "20240102010000", "UTC").unwrap();
+        assert_eq!(a.union(&far), None);
+    }
+
+    #[test]
+    fn test_time_range_invalid() {
+        assert!(TimeRange::from_char14("20240102000000", "20240101000000", "UTC").is_err());
+        assert!(TimeRange::from_char14("20240101000000", "20240102000000", "Nowhere/City").is_err());
+        assert!(TimeRange::from_char14("2024", "20240102000000", "UTC").is_err());
+    }
+
+    #[test]
+    fn test_time_range_contains() {
+        let range = TimeRange::from_char14("20240101000000", "20240101010000", "Asia/Seoul").unwrap();
+        assert!(range.contains(&range.start()));
+        assert!(!range.contains(&range.end()));
+        assert!(!range.is_empty());
+    }
+
+    #[test]
+    fn test_time_range_split_by_day_across_dst() {
+        // New York springs forward on 2024-03-10, so that day is only 23 hours long
+        let range = TimeRange::from_char14("20240309120000", "20240311120000", "America/New_York").unwrap();
+        let days = range.split_by_day();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].duration(), chrono::Duration::hours(12));
+        assert_eq!(days[1].duration(), chrono::Duration::hours(23));
+        assert_eq!(days[2].duration(), chrono::Duration::hours(12));
+        assert_eq!(days[1].start().hour(), 0);
+        assert_eq!(days[2].start().hour(), 0);
+    }
+
+    #[test]
+    fn test_time_range_split_by_hour() {
+        let range = TimeRange::from_char14("20240101003000", "20240101030000", "Asia/Kolkata").unwrap();
+        let hours = range.split_by_hour();
+        assert_eq!(hours.len(), 3);
+        assert_eq!(hours[0].duration(), chrono::Duration::minutes(30));
+        assert_eq!(hours[1].start().minute(), 0);
+        assert_eq!(hours[2].end(), range.end());
+    }
+
+    #[test]
+    fn test_time_range_iter_and_split_by() {
+        let range = TimeRange::from_char14("20240101000000", "20240101001200", "UTC").unwrap();
+        let steps: Vec<_> = range.iter_step(chrono::Duration::minutes(5)).collect();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[2].minute(), 10);
+
+        let pieces = range.split_by(chrono::Duration::minutes(5));
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[2].duration(), chrono::Duration::minutes(2));
+        assert_eq!(range.iter_step(chrono::Duration::zero()).count(), 0);
+    }
+
+    #[test]
+    fn test_align_to_buckets() {
+        let tz: Tz = "Asia/Seoul".parse().unwrap();
+        let dt = tz.with_ymd_and_hms(2024, 1, 1, 9, 7, 31).unwrap();
+        let five = chrono::Duration::minutes(5);
+
+        let down = align_down(&dt, five);
+        assert_eq!((down.hour(), down.minute(), down.second()), (9, 5, 0));
+        let up = align_up(&dt, five).unwrap();
+        assert_eq!((up.hour(), up.minute(), up.second()), (9, 10, 0));
+        assert_eq!(align_up(&down, five), Some(down));
+
+        // Daily buckets start at local midnight rather than UTC midnight
+        let day = align_down(&dt, chrono::Duration::days(1));
+        assert_eq!((day.day(), day.hour()), (1, 0));
+
+        let range = TimeRange::new(dt, dt + chrono::Duration::minutes(1)).unwrap().align(five);
+        assert_eq!(range.duration(), five);
+    }
+
+    #[test]
+    fn test_align_across_fall_back() {
+        // New York repeats 01:00-02:00 on 2024-11-03
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let first = Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap().with_timezone(&tz);
+        let second = first + chrono::Duration::hours(1);
+        assert_eq!(first.hour(), second.hour());
+
+        let hour = chrono::Duration::hours(1);
+        assert_eq!(align_down(&second, hour), second - chrono::Duration::minutes(30));
+        assert_eq!(align_up(&first, hour), Some(first + chrono::Duration::minutes(30)));
+    }
+
+    #[test]
+    fn test_align_and_iterate_near_max_datetime() {
+        let max = DateTime::<Utc>::MAX_UTC.with_timezone(&chrono_tz::UTC);
+        let five = chrono::Duration::minutes(5);
+        assert_eq!(align_up(&max, five), None);
+
+        let range = TimeRange::new(max - chrono::Duration::hours(1), max).unwrap();
+        assert_eq!(range.align(five).end(), max);
+        assert_eq!(range.iter_step(chrono::Duration::minutes(40)).count(), 2);
+        assert_eq!(range.iter_step(chrono::Duration::MAX).count(), 1);
+        assert_eq!(range.split_by_hour().len(), 2);
+    }
+
+    #[test]
+    fn test_timestamp_from_char14_zone_unique() {
+        let resolved = timestamp_from_char14_zone("20240115093000", "Asia/Seoul", DstPolicy::Error).unwrap();
+        assert_eq!(resolved.kind, LocalTimeKind::Unique);
+        assert_eq!(resolved.datetime.hour(), 9);
+        assert_eq!(resolved.datetime.with_timezone(&Utc).hour(), 0);
+        assert!(timestamp_from_char14_zone("20240115093000", "Nowhere/City", DstPolicy::Error).is_err());
+        assert!(timestamp_from_char14_zone("2024011509", "UTC", DstPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn test_timestamp_from_char14_zone_ambiguous() {
+        // 01:30 happens twice in New York on 2024-11-03 (EDT, then EST)
+        let text = "20241103013000";
+        let zone = "America/New_York";
+
+        let earliest = timestamp_from_char14_zone(text, zone, DstPolicy::Earliest).unwrap();
+        assert_eq!(earliest.kind, LocalTimeKind::Ambiguous);
+        assert_eq!(earliest.datetime.with_timezone(&Utc).hour(), 5);
+
+        let latest = timestamp_from_char14_zone(text, zone, DstPolicy::Latest).unwrap();
+        assert_eq!(latest.datetime.with_timezone(&Utc).hour(), 6);
+
+        let shifted = timestamp_from_char14_zone(text, zone, DstPolicy::ShiftForward).unwrap();
+        assert_eq!(shifted.datetime, earliest.datetime);
+
+        assert!(timestamp_from_char14_zone(text, zone, DstPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn test_timestamp_from_char14_zone_gap() {
+        // 02:30 never happens in New York on 2024-03-10 (02:00 jumps to 03:00)
+        let text = "20240310023000";
+        let zone = "America/New_York";
+
+        let earliest = timestamp_from_char14_zone(text, zone, DstPolicy::Earliest).unwrap();
+        assert_eq!(earliest.kind, LocalTimeKind::Gap);
+        assert_eq!((earliest.datetime.hour(), earliest.datetime.minute()), (1, 30));
+
+        let latest = timestamp_from_char14_zone(text, zone, DstPolicy::Latest).unwrap();
+        assert_eq!((latest.datetime.hour(), latest.datetime.minute()), (3, 30));
+
+        let shifted = timestamp_from_char14_zone(text, zone, DstPolicy::ShiftForward).unwrap();
+        assert_eq!((shifted.datetime.hour(), shifted.datetime.minute()), (3, 0));
+        assert_eq!(shifted.requested.hour(), 2);
+
+        assert!(timestamp_from_char14_zone(text, zone, DstPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn test_local_with_utc_offset_fallback() {
+        let naive = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        // Keeps the local wall clock rather than reading it as UTC
+        let seoul: Tz = "Asia/Seoul".parse().unwrap();
+        let dt = local_with_utc_offset(&seoul, naive("2024-01-01 09:00:00"));
+        assert_eq!(dt.naive_local(), naive("2024-01-01 09:00:00"));
+        assert_eq!(dt.naive_utc(), naive("2024-01-01 00:00:00"));
+
+        // Inside a DST gap it lands just past the gap, like ShiftForward
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        let dt = local_with_utc_offset(&new_york, naive("2024-03-10 02:30:00"));
+        assert_eq!(dt.naive_local(), naive("2024-03-10 03:30:00"));
+        assert_eq!(resolve_local(&new_york, naive("2024-03-10 02:30:00")).naive_local(), naive("2024-03-10 03:00:00"));
+    }
+
+    #[test]
+    fn test_resolve_local_time_with_fixed_offset() {
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let naive = parse_char14("20240101000000").unwrap();
+        let resolved = resolve_local_time(&tz, naive, DstPolicy::Error).unwrap();
+        assert_eq!(resolved.kind, LocalTimeKind::Unique);
+        assert_eq!(resolved.datetime.naive_local(), naive);
+    }
+
+    #[test]
+    fn test_stopwatch_pause_resume() {
+        use std::time::Duration;
+
+        let mut sw = Stopwatch::new();
+        assert!(!sw.is_running());
+        assert_eq!(sw.elapsed(), Duration::ZERO);
+
+        sw.start();
+        std::thread::sleep(Duration::from_millis(20));
+        sw.pause();
+        let paused_at = sw.elapsed();
+        assert!(paused_at >= Duration::from_millis(20));
+
+        // Time spent paused is not counted
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(sw.elapsed(), paused_at);
+
+        sw.resume();
+        assert!(sw.is_running());
+        assert!(sw.split() >= paused_at);
+    }
+
+    #[test]
+    fn test_stopwatch_laps() {
+        use std::time::Duration;
+
+        let mut sw = Stopwatch::start_new();
+        std::thread::sleep(Duration::from_millis(10));
+        let first = sw.lap();
+        std::thread::sleep(Duration::from_millis(10));
+        let second = sw.lap();
+
+        assert!(first >= Duration::from_millis(10));
+        assert!(second >= Duration::from_millis(10));
+        assert_eq!(sw.laps(), &[first, second]);
+        assert!(sw.elapsed() >= first + second);
+
+        sw.restart();
+        assert!(sw.laps().is_empty());
+        assert!(sw.is_running());
+        sw.reset();
+        assert!(!sw.is_running());
+    }
+
+    #[test]
+    fn test_duration_histogram() {
+        use std::time::Duration;
+
+        let hist = DurationHistogram::new(vec![Duration::from_millis(10), Duration::from_millis(1)]);
+        hist.observe(Duration::from_micros(500));
+        hist.observe(Duration::from_millis(1));
+        hist.observe(Duration::from_millis(5));
+        hist.observe(Duration::from_secs(1));
+
+        assert_eq!(hist.count(), 4);
+        let buckets = hist.buckets();
+        assert_eq!(buckets[0], (Duration::from_millis(1), 2));
+        assert_eq!(buckets[1], (Duration::from_millis(10), 1));
+        assert_eq!(buckets[2], (Duration::MAX, 1));
+        let sum = hist.sum().as_secs_f64();
+        assert!((sum - 1.0065).abs() < 1e-9, "sum {}", sum);
+        assert_eq!(hist.quantile(0.5), Some(Duration::from_millis(1)));
+        assert_eq!(hist.quantile(0.75), Some(Duration::from_millis(10)));
+        assert_eq!(DurationHistogram::latency().quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_scoped_timer_records_on_drop() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let hist = Arc::new(DurationHistogram::latency());
+        {
+            let timer = ScopedTimer::new("load").with_recorder(hist.clone());
+            std::thread::sleep(Duration::from_millis(5));
+            assert!(timer.elapsed() >= Duration::from_millis(5));
+        }
+        assert_eq!(hist.count(), 1);
+        assert!(hist.sum() >= Duration::from_millis(5));
+
+        // A cancelled timer reports nothing
+        ScopedTimer::new("skipped").with_recorder(hist.clone()).cancel();
+        assert_eq!(hist.count(), 1);
+    }
+
+    #[test]
+    fn test_scoped_timer_slow_threshold() {
+        use std::time::Duration;
+
+        let timer = ScopedTimer::new("slow").warn_after(Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(timer.is_slow());
+        assert!(!ScopedTimer::new("fast").is_slow());
+    }
 }
@@ -1,151 +1,2745 @@
 // External crate imports
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
 use std::thread;
 use std::time;
+use std::time::Instant;
 
 // Internal crate imports
-use crate::time::LocalTimeFormatter;
+use crate::time::{format_duration_compact, parse_duration, LocalTimeFormatter};
 use crate::fs::get_exe_dir;
 
 // Tracing imports
 use tracing::{info, error, Level};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter, Targets};
+use tracing::field::{Field, Visit};
+use tracing::span;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::fmt::{FmtContext, FormattedFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::{reload, Layer, Registry};
+
+use anyhow::{anyhow, Result as AnyResult};
+use chrono::{DateTime, Local};
+use serde_derive::{Deserialize, Serialize};
+use sink::{JournaldConfig, SyslogConfig};
+
+#[cfg(feature = "trace")]
+pub mod reader;
+
+#[cfg(feature = "trace")]
+pub mod sink;
+
+#[cfg(feature = "trace")]
+pub mod testing;
+
+// Lets the exported macros name tracing items without a direct dependency
+#[cfg(feature = "trace")]
+#[doc(hidden)]
+pub use tracing as __tracing;
+
+/// Environment variable holding the default level, e.g. "info"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_LEVEL: &str = "ACOVO_LOG_LEVEL";
+/// Environment variable holding filter directives, e.g. "info,hyper=warn"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_DIRECTIVES: &str = "RUST_LOG";
+/// Environment variable holding the log directory
+#[cfg(feature = "trace")]
+pub const ENV_LOG_DIR: &str = "ACOVO_LOG_DIR";
+/// Environment variable holding the log file prefix
+#[cfg(feature = "trace")]
+pub const ENV_LOG_FILE: &str = "ACOVO_LOG_FILE";
+/// Environment variable holding the rotation period
+#[cfg(feature = "trace")]
+pub const ENV_LOG_ROTATION: &str = "ACOVO_LOG_ROTATION";
+/// Environment variable switching ANSI colors on stdout
+#[cfg(feature = "trace")]
+pub const ENV_LOG_ANSI: &str = "ACOVO_LOG_ANSI";
+/// Environment variable switching stdout output
+#[cfg(feature = "trace")]
+pub const ENV_LOG_STDOUT: &str = "ACOVO_LOG_STDOUT";
+/// Environment variable switching thread ids in each line
+#[cfg(feature = "trace")]
+pub const ENV_LOG_THREAD_IDS: &str = "ACOVO_LOG_THREAD_IDS";
+/// Environment variable switching file and line numbers in each line
+#[cfg(feature = "trace")]
+pub const ENV_LOG_LINE_NUMBERS: &str = "ACOVO_LOG_LINE_NUMBERS";
+/// Environment variable holding the maximum log file size, e.g. "100MB"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_MAX_SIZE: &str = "ACOVO_LOG_MAX_SIZE";
+/// Environment variable holding the compression of rotated files
+#[cfg(feature = "trace")]
+pub const ENV_LOG_COMPRESSION: &str = "ACOVO_LOG_COMPRESSION";
+/// Environment variable holding the number of rotated files to keep
+#[cfg(feature = "trace")]
+pub const ENV_LOG_MAX_FILES: &str = "ACOVO_LOG_MAX_FILES";
+/// Environment variable holding the maximum age of rotated files, e.g. "7d"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_MAX_AGE: &str = "ACOVO_LOG_MAX_AGE";
+/// Environment variable switching the `<prefix>.current` symlink
+#[cfg(feature = "trace")]
+pub const ENV_LOG_SYMLINK: &str = "ACOVO_LOG_SYMLINK";
+/// Environment variable holding the stdout format, "text" or "json"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_STDOUT_FORMAT: &str = "ACOVO_LOG_STDOUT_FORMAT";
+/// Environment variable holding the log file format, "text" or "json"
+#[cfg(feature = "trace")]
+pub const ENV_LOG_FILE_FORMAT: &str = "ACOVO_LOG_FILE_FORMAT";
+/// Environment variable switching syslog output, see `TracingConfig::syslog`
+#[cfg(feature = "trace")]
+pub const ENV_LOG_SYSLOG: &str = "ACOVO_LOG_SYSLOG";
+/// Environment variable switching journald output, see `TracingConfig::journald`
+#[cfg(feature = "trace")]
+pub const ENV_LOG_JOURNALD: &str = "ACOVO_LOG_JOURNALD";
+/// Environment variable holding the syslog and journald program identifier
+#[cfg(feature = "trace")]
+pub const ENV_LOG_IDENTIFIER: &str = "ACOVO_LOG_IDENTIFIER";
+
+/// How often the log file is rotated
+///
+/// Combine a period with `TracingConfig::max_file_size` for hybrid rotation,
+/// or use `Never` with a size for purely size-based rotation.
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    /// A new file every minute
+    Minutely,
+    /// A new file every hour
+    Hourly,
+    /// A new file every day
+    #[default]
+    Daily,
+    /// A single file that is never rotated
+    Never,
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for Rotation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "minutely" => Ok(Rotation::Minutely),
+            "hourly" => Ok(Rotation::Hourly),
+            "daily" => Ok(Rotation::Daily),
+            "never" => Ok(Rotation::Never),
+            other => Err(anyhow!("unknown log rotation '{}'", other)),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Rotation {
+    // Suffix identifying the period containing `now`, as used by tracing-appender
+    fn period_key(&self, now: &DateTime<Local>) -> Option<String> {
+        match self {
+            Rotation::Minutely => Some(now.format("%Y-%m-%d-%H-%M").to_string()),
+            Rotation::Hourly => Some(now.format("%Y-%m-%d-%H").to_string()),
+            Rotation::Daily => Some(now.format("%Y-%m-%d").to_string()),
+            Rotation::Never => None,
+        }
+    }
+}
+
+/// Compression applied to rotated log files
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Rotated files are left as they are
+    #[default]
+    None,
+    /// Rotated files are replaced by `<name>.gz`
+    Gzip,
+    /// Rotated files are replaced by `<name>.zip`; requires the `compress` feature
+    Zip,
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zip" => Ok(Compression::Zip),
+            other => Err(anyhow!("unknown log compression '{}'", other)),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Compression {
+    // File name extension of compressed files
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zip => Some("zip"),
+        }
+    }
+}
+
+/// Line format of a log output
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable text lines
+    #[default]
+    Text,
+    /// One JSON object per line, see `JsonFormat` for the schema
+    Json,
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!("unknown log format '{}'", other)),
+        }
+    }
+}
+
+/// Parse a byte size such as "512", "64KB" or "20MB"
+///
+/// Units are binary: K/KB/KiB = 1024 bytes, M/MB/MiB = 1024 KiB, G/GB/GiB = 1024 MiB.
+///
+/// # Errors
+/// * Returns an error if the number or unit is invalid or the size overflows
+#[cfg(feature = "trace")]
+pub fn parse_size(text: &str) -> AnyResult<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().map_err(|_| anyhow!("invalid size '{}'", text))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(anyhow!("unknown size unit in '{}'", text)),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("size '{}' is too large", text))
+}
+
+// Read a size given as a byte count or as text accepted by `parse_size`
+#[cfg(feature = "trace")]
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match <Option<Size> as serde::Deserialize>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Size::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(Size::Text(text)) => parse_size(&text).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Settings for the tracing subscriber installed by `TracingConfig::init`
+///
+/// Every field has a default, so a config file only needs the keys it changes.
+/// Levels and directives use the `RUST_LOG` syntax: a bare level sets the default
+/// and `target=level` pairs override it per target.
+///
+/// # Example
+/// ```no_run
+/// use acovo::trace::{Rotation, TracingConfig};
+/// use tracing::Level;
+///
+/// let _guard = TracingConfig::new()
+///     .level(Level::INFO)
+///     .target("hyper", Level::WARN)
+///     .log_dir("/var/log/my_app")
+///     .file_prefix("my_app")
+///     .rotation(Rotation::Hourly)
+///     .init()
+///     .expect("Failed to initialize tracing");
+/// ```
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TracingConfig {
+    /// Default level for targets without a directive
+    pub level: String,
+    /// Extra filter directives such as "info,my_crate::net=trace"
+    pub directives: Option<String>,
+    /// Per-target levels, applied after `directives`
+    pub targets: BTreeMap<String, String>,
+    /// Directory for log files; defaults to `logs` next to the executable
+    pub log_dir: Option<PathBuf>,
+    /// Log file name prefix; no file is written when unset
+    pub file_prefix: Option<String>,
+    /// Rotation period of the log file
+    pub rotation: Rotation,
+    /// Start a new file once the current one reaches this size; config files may
+    /// give a byte count or a size such as "100MB" (see `parse_size`)
+    #[serde(deserialize_with = "deserialize_size")]
+    pub max_file_size: Option<u64>,
+    /// Compression of rotated files
+    pub compression: Compression,
+    /// Number of rotated files to keep, newest first
+    pub max_files: Option<usize>,
+    /// Delete rotated files older than this duration, e.g. "7d"
+    pub max_age: Option<String>,
+    /// Whether `<prefix>.current` links to the file being written (Unix only)
+    pub symlink: bool,
+    /// Whether stdout output uses ANSI colors
+    pub ansi: bool,
+    /// Whether each line includes the thread id
+    pub thread_ids: bool,
+    /// Whether each line includes the source file and line number
+    pub line_numbers: bool,
+    /// Whether events are written to stdout
+    pub stdout: bool,
+    /// Line format on stdout
+    pub stdout_format: LogFormat,
+    /// Line format in the log file
+    pub file_format: LogFormat,
+    /// Keep the last N events in memory, see `LogBuffer`
+    pub recent_events: Option<usize>,
+    /// Keep in memory only events from this recent duration, e.g. "15m"
+    pub recent_max_age: Option<String>,
+    /// Send events to the local syslog daemon (Unix only)
+    pub syslog: Option<SyslogConfig>,
+    /// Send events to systemd-journald (Linux only)
+    pub journald: Option<JournaldConfig>,
+}
+
+#[cfg(feature = "trace")]
+impl Default for TracingConfig {
+    /// DEBUG level, stdout only with ANSI colors, daily rotation once a file is set,
+    /// no compression and no retention limit
+    fn default() -> Self {
+        TracingConfig {
+            level: "debug".to_string(),
+            directives: None,
+            targets: BTreeMap::new(),
+            log_dir: None,
+            file_prefix: None,
+            rotation: Rotation::Daily,
+            max_file_size: None,
+            compression: Compression::None,
+            max_files: None,
+            max_age: None,
+            symlink: false,
+            ansi: true,
+            thread_ids: false,
+            line_numbers: false,
+            stdout: true,
+            stdout_format: LogFormat::Text,
+            file_format: LogFormat::Text,
+            recent_events: None,
+            recent_max_age: None,
+            syslog: None,
+            journald: None,
+        }
+    }
+}
+
+// Parse a boolean environment value
+#[cfg(feature = "trace")]
+fn parse_env_bool(name: &str, value: &str) -> AnyResult<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(anyhow!("{} must be a boolean, got '{}'", name, value)),
+    }
+}
+
+// Parse a level name such as "info" or "off"
+#[cfg(feature = "trace")]
+fn parse_level(value: &str) -> AnyResult<LevelFilter> {
+    LevelFilter::from_str(value.trim()).map_err(|_| anyhow!("unknown log level '{}'", value))
+}
+
+#[cfg(feature = "trace")]
+impl TracingConfig {
+    /// Create a config with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a config from a JSON file
+    ///
+    /// # Arguments
+    /// * `path` - Path of a JSON object whose keys are the field names of `TracingConfig`
+    ///
+    /// # Errors
+    /// * Returns an error if the file cannot be read or is not a valid config
+    ///
+    /// # Example
+    /// ```json
+    /// { "level": "info", "targets": { "hyper": "warn" }, "file_prefix": "my_app" }
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> AnyResult<Self> {
+        let text = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| anyhow!("failed to read {}: {}", path.as_ref().display(), e))?;
+        let config: TracingConfig = serde_json::from_str(&text)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Create a config from the defaults overridden by environment variables
+    ///
+    /// See `with_env` for the variables read.
+    pub fn from_env() -> AnyResult<Self> {
+        Self::default().with_env()
+    }
+
+    /// Override settings with the `ACOVO_LOG_*` and `RUST_LOG` environment variables
+    ///
+    /// Unset variables leave the current value in place, so this can be applied on
+    /// top of a config file.
+    ///
+    /// # Errors
+    /// * Returns an error if a variable holds an invalid level, rotation, size, format or boolean
+    pub fn with_env(self) -> AnyResult<Self> {
+        self.with_env_from(|name| std::env::var(name).ok())
+    }
+
+    /// Like `with_env`, but read the variables through `lookup`
+    ///
+    /// Useful to apply the same variables from another source, or in tests.
+    ///
+    /// # Errors
+    /// * Returns an error if a variable holds an invalid level, rotation, size, format or boolean
+    pub fn with_env_from<F>(mut self, lookup: F) -> AnyResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name: &str| lookup(name).filter(|v| !v.trim().is_empty());
+
+        if let Some(level) = var(ENV_LOG_LEVEL) {
+            parse_level(&level)?;
+            self.level = level;
+        }
+        if let Some(directives) = var(ENV_LOG_DIRECTIVES) {
+            self.directives = Some(directives);
+        }
+        if let Some(dir) = var(ENV_LOG_DIR) {
+            self.log_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(prefix) = var(ENV_LOG_FILE) {
+            self.file_prefix = Some(prefix);
+        }
+        if let Some(rotation) = var(ENV_LOG_ROTATION) {
+            self.rotation = rotation.parse()?;
+        }
+        if let Some(value) = var(ENV_LOG_ANSI) {
+            self.ansi = parse_env_bool(ENV_LOG_ANSI, &value)?;
+        }
+        if let Some(value) = var(ENV_LOG_STDOUT) {
+            self.stdout = parse_env_bool(ENV_LOG_STDOUT, &value)?;
+        }
+        if let Some(value) = var(ENV_LOG_THREAD_IDS) {
+            self.thread_ids = parse_env_bool(ENV_LOG_THREAD_IDS, &value)?;
+        }
+        if let Some(value) = var(ENV_LOG_LINE_NUMBERS) {
+            self.line_numbers = parse_env_bool(ENV_LOG_LINE_NUMBERS, &value)?;
+        }
+        if let Some(value) = var(ENV_LOG_MAX_SIZE) {
+            self.max_file_size = Some(parse_size(&value)?);
+        }
+        if let Some(value) = var(ENV_LOG_COMPRESSION) {
+            self.compression = value.parse()?;
+        }
+        if let Some(value) = var(ENV_LOG_MAX_FILES) {
+            let count = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{} must be a number, got '{}'", ENV_LOG_MAX_FILES, value))?;
+            self.max_files = Some(count);
+        }
+        if let Some(value) = var(ENV_LOG_MAX_AGE) {
+            self.max_age = Some(value);
+        }
+        if let Some(value) = var(ENV_LOG_SYMLINK) {
+            self.symlink = parse_env_bool(ENV_LOG_SYMLINK, &value)?;
+        }
+        if let Some(value) = var(ENV_LOG_STDOUT_FORMAT) {
+            self.stdout_format = value.parse()?;
+        }
+        if let Some(value) = var(ENV_LOG_FILE_FORMAT) {
+            self.file_format = value.parse()?;
+        }
+        if let Some(value) = var(ENV_LOG_SYSLOG) {
+            let enabled = parse_env_bool(ENV_LOG_SYSLOG, &value)?;
+            self.syslog = if enabled { self.syslog.or_else(|| Some(SyslogConfig::default())) } else { None };
+        }
+        if let Some(value) = var(ENV_LOG_JOURNALD) {
+            let enabled = parse_env_bool(ENV_LOG_JOURNALD, &value)?;
+            self.journald = if enabled { self.journald.or_else(|| Some(JournaldConfig::default())) } else { None };
+        }
+        if let Some(identifier) = var(ENV_LOG_IDENTIFIER) {
+            if let Some(syslog) = &mut self.syslog {
+                syslog.identifier = Some(identifier.clone());
+            }
+            if let Some(journald) = &mut self.journald {
+                journald.identifier = Some(identifier);
+            }
+        }
+        self.check()?;
+        Ok(self)
+    }
+
+    /// Set the default level
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level.to_string().to_lowercase();
+        self
+    }
+
+    /// Set filter directives in `RUST_LOG` syntax
+    pub fn directives(mut self, directives: &str) -> Self {
+        self.directives = Some(directives.to_string());
+        self
+    }
+
+    /// Set the level of one target and the modules below it
+    pub fn target(mut self, target: &str, level: Level) -> Self {
+        self.targets.insert(target.to_string(), level.to_string().to_lowercase());
+        self
+    }
+
+    /// Set the directory for log files
+    pub fn log_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
+    /// Write log files named after `prefix`
+    pub fn file_prefix(mut self, prefix: &str) -> Self {
+        self.file_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Set the rotation period of the log file
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Start a new file once the current one reaches `bytes`
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Compress rotated files
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Keep at most `count` rotated files
+    pub fn max_files(mut self, count: usize) -> Self {
+        self.max_files = Some(count);
+        self
+    }
+
+    /// Delete rotated files older than `age`
+    pub fn max_age(mut self, age: std::time::Duration) -> Self {
+        self.max_age = Some(format_duration_compact(age));
+        self
+    }
+
+    /// Maintain a `<prefix>.current` symlink to the file being written
+    pub fn symlink(mut self, symlink: bool) -> Self {
+        self.symlink = symlink;
+        self
+    }
+
+    /// Enable or disable ANSI colors on stdout
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
+    /// Include or omit thread ids
+    pub fn thread_ids(mut self, thread_ids: bool) -> Self {
+        self.thread_ids = thread_ids;
+        self
+    }
+
+    /// Include or omit source file and line numbers
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Enable or disable stdout output
+    pub fn stdout(mut self, stdout: bool) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Set the line format on stdout
+    pub fn stdout_format(mut self, format: LogFormat) -> Self {
+        self.stdout_format = format;
+        self
+    }
+
+    /// Set the line format in the log file
+    pub fn file_format(mut self, format: LogFormat) -> Self {
+        self.file_format = format;
+        self
+    }
+
+    /// Keep the last `count` events in memory, e.g. for crash reports
+    pub fn recent_events(mut self, count: usize) -> Self {
+        self.recent_events = Some(count);
+        self
+    }
+
+    /// Keep in memory only events from the last `age`
+    ///
+    /// Without `recent_events` the buffer holds up to `DEFAULT_BUFFER_EVENTS` events.
+    pub fn recent_max_age(mut self, age: std::time::Duration) -> Self {
+        self.recent_max_age = Some(format_duration_compact(age));
+        self
+    }
+
+    /// Also send events to the local syslog daemon
+    pub fn syslog(mut self, config: SyslogConfig) -> Self {
+        self.syslog = Some(config);
+        self
+    }
+
+    /// Also send events to systemd-journald
+    pub fn journald(mut self, config: JournaldConfig) -> Self {
+        self.journald = Some(config);
+        self
+    }
+
+    /// Build the event filter from `level`, `directives` and `targets`
+    ///
+    /// `directives` accept the full `EnvFilter` syntax, including span and field
+    /// filters such as `my_app[request{user=alice}]=trace`. For the same target,
+    /// `targets` override `directives`, which override `level`.
+    ///
+    /// # Errors
+    /// * Returns an error if a level or directive cannot be parsed
+    pub fn filter(&self) -> AnyResult<EnvFilter> {
+        parse_filter(&self.filter_directives()?)
+    }
+
+    // Directives of `filter`, in the order they are applied
+    fn filter_directives(&self) -> AnyResult<Vec<String>> {
+        let mut directives = vec![parse_level(&self.level)?.to_string()];
+        if let Some(extra) = &self.directives {
+            directives.push(extra.clone());
+        }
+        for (target, level) in &self.targets {
+            directives.push(format!("{}={}", target, parse_level(level)?));
+        }
+        Ok(directives)
+    }
+
+    // Validate the settings that are stored as text
+    fn check(&self) -> AnyResult<()> {
+        self.filter()?;
+        self.max_age_duration()?;
+        self.recent_max_age.as_deref().map(parse_duration).transpose()?;
+        Ok(())
+    }
+
+    // In-memory event buffer described by `recent_events` and `recent_max_age`
+    fn log_buffer(&self) -> AnyResult<Option<LogBuffer>> {
+        let max_age = self.recent_max_age.as_deref().map(parse_duration).transpose()?;
+        if self.recent_events.is_none() && max_age.is_none() {
+            return Ok(None);
+        }
+        let mut buffer = LogBuffer::new(self.recent_events.unwrap_or(DEFAULT_BUFFER_EVENTS));
+        if let Some(age) = max_age {
+            buffer = buffer.with_max_age(age);
+        }
+        Ok(Some(buffer))
+    }
+
+    // Parsed `max_age`
+    fn max_age_duration(&self) -> AnyResult<Option<std::time::Duration>> {
+        self.max_age.as_deref().map(parse_duration).transpose()
+    }
+
+    /// Directory the log files are written to
+    ///
+    /// # Errors
+    /// * Returns an error if no directory is configured and the executable directory is unknown
+    pub fn log_path(&self) -> AnyResult<PathBuf> {
+        match &self.log_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(PathBuf::from(get_exe_dir()?).join("logs")),
+        }
+    }
+
+    /// Build the subscriber without installing it
+    ///
+    /// Useful with `tracing::subscriber::with_default` to scope the configuration to
+    /// one thread or test. The returned guard must be kept alive while logging.
+    ///
+    /// # Errors
+    /// * Returns an error if the filter is invalid, the log directory cannot be created
+    ///   or the syslog or journald socket is not available
+    pub fn build(&self) -> AnyResult<(impl tracing::Subscriber + Send + Sync + 'static, TracingGuard)> {
+        let (filter, reload_handle) = reload::Layer::new(self.filter()?);
+        let mut layers: Vec<Box<dyn Layer<FilteredRegistry> + Send + Sync>> = Vec::new();
+        let mut guard = TracingGuard {
+            workers: Vec::new(),
+            filter: FilterHandle::new(reload_handle, self),
+            buffer: self.log_buffer()?,
+            log_path: self.log_path().ok(),
+        };
+
+        if let Some(buffer) = &guard.buffer {
+            layers.push(buffer.clone().boxed());
+        }
+
+        if self.stdout {
+            layers.push(self.output_layer(self.stdout_format, std::io::stdout, self.ansi));
+        }
+
+        if let Some(prefix) = &self.file_prefix {
+            let mut appender = RollingFileWriter::new(self.log_path()?, prefix, self.rotation)?
+                .with_compression(self.compression)?
+                .with_symlink(self.symlink);
+            if let Some(bytes) = self.max_file_size {
+                appender = appender.with_max_size(bytes);
+            }
+            if let Some(count) = self.max_files {
+                appender = appender.with_max_files(count);
+            }
+            if let Some(age) = self.max_age_duration()? {
+                appender = appender.with_max_age(age);
+            }
+            let (non_blocking, worker) = spawn_writer(appender)?;
+            guard.workers.push(worker);
+            layers.push(self.output_layer(self.file_format, non_blocking, false));
+        }
+
+        #[cfg(unix)]
+        {
+            if let Some(syslog) = &self.syslog {
+                layers.push(sink::SyslogLayer::new(syslog)?.boxed());
+            }
+            if let Some(journald) = &self.journald {
+                layers.push(sink::JournaldLayer::new(journald)?.boxed());
+            }
+        }
+        #[cfg(not(unix))]
+        if self.syslog.is_some() || self.journald.is_some() {
+            return Err(anyhow!("syslog and journald output are only available on Unix"));
+        }
+
+        let subscriber = tracing_subscriber::registry().with(filter).with(layers);
+        Ok((subscriber, guard))
+    }
+
+    // Formatting layer writing `format` lines to `writer`
+    fn output_layer<W>(&self, format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<FilteredRegistry> + Send + Sync>
+    where
+        W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+    {
+        match format {
+            LogFormat::Text => tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(ansi)
+                .with_level(true)
+                .with_target(true)
+                .with_line_number(self.line_numbers)
+                .with_file(self.line_numbers)
+                .with_thread_ids(self.thread_ids)
+                .with_timer(LocalTimeFormatter)
+                .boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .fmt_fields(JsonFields)
+                .event_format(
+                    JsonFormat::new()
+                        .with_line_numbers(self.line_numbers)
+                        .with_thread_ids(self.thread_ids),
+                )
+                .boxed(),
+        }
+    }
+
+    /// Build the subscriber and install it as the global default
+    ///
+    /// # Errors
+    /// * Returns an error if the config is invalid or a global subscriber is already set
+    pub fn init(&self) -> AnyResult<TracingGuard> {
+        let (subscriber, guard) = self.build()?;
+        tracing::subscriber::set_global_default(subscriber)
+            .map_err(|e| anyhow!("failed to set global subscriber: {}", e))?;
+        Ok(guard)
+    }
+
+    /// Install the subscriber globally and keep its guard for the whole process
+    ///
+    /// The queued lines are written out when the process exits through `main`
+    /// returning or `std::process::exit` (Unix only; elsewhere call `shutdown`).
+    /// Use `flush` to write out buffered lines earlier, e.g. from a panic hook.
+    ///
+    /// # Errors
+    /// * Returns an error if the config is invalid or a global subscriber is already set
+    pub fn init_global(&self) -> AnyResult<()> {
+        let guard = self.init()?;
+        *global_guard() = Some(guard);
+        #[cfg(unix)]
+        register_exit_hook();
+        Ok(())
+    }
+}
+
+// Maximum number of lines queued for the writer thread; further lines are dropped
+#[cfg(feature = "trace")]
+const WRITER_QUEUE_LINES: usize = 128_000;
+
+// How long `flush` waits for each writer thread to confirm
+#[cfg(feature = "trace")]
+const FLUSH_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+#[cfg(feature = "trace")]
+thread_local! {
+    // Set on the log writer threads, where flushing would wait on itself
+    static ON_WRITER_THREAD: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Messages from the log writers to the writer thread
+#[cfg(feature = "trace")]
+enum WriterMessage {
+    Line(Vec<u8>),
+    Flush(mpsc::Sender<()>),
+    Shutdown,
+}
+
+// Cloneable writer handing each formatted event to the writer thread
+// Logging never waits for the disk: when the queue is full the line is dropped and counted.
+#[cfg(feature = "trace")]
+#[derive(Clone)]
+struct NonBlockingWriter {
+    sender: mpsc::SyncSender<WriterMessage>,
+    dropped: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "trace")]
+impl Write for NonBlockingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.sender.try_send(WriterMessage::Line(buf.to_vec())) {
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            // Once the guard has shut the thread down, later lines are discarded
+            Ok(()) | Err(mpsc::TrySendError::Disconnected(_)) => {}
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "trace")]
+impl<'a> MakeWriter<'a> for NonBlockingWriter {
+    type Writer = NonBlockingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+// Handle to one writer thread
+#[cfg(feature = "trace")]
+struct WriterWorker {
+    sender: mpsc::SyncSender<WriterMessage>,
+    handle: Option<thread::JoinHandle<()>>,
+    dropped: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "trace")]
+impl WriterWorker {
+    // Ask the thread to flush and wait until `deadline` for it to confirm
+    fn flush_until(&self, deadline: Instant) -> bool {
+        let (ack, done) = mpsc::channel();
+        let mut message = WriterMessage::Flush(ack);
+        loop {
+            match self.sender.try_send(message) {
+                Ok(()) => break,
+                Err(mpsc::TrySendError::Full(returned)) if Instant::now() < deadline => {
+                    message = returned;
+                    thread::sleep(time::Duration::from_millis(1));
+                }
+                Err(_) => return false,
+            }
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        done.recv_timeout(remaining).is_ok()
+    }
+}
+
+// Start a thread that writes queued lines to `writer` in order
+#[cfg(feature = "trace")]
+fn spawn_writer<W: Write + Send + 'static>(writer: W) -> AnyResult<(NonBlockingWriter, WriterWorker)> {
+    spawn_writer_with_queue(writer, WRITER_QUEUE_LINES)
+}
+
+// Start a writer thread holding at most `queue` lines
+#[cfg(feature = "trace")]
+fn spawn_writer_with_queue<W: Write + Send + 'static>(
+    mut writer: W,
+    queue: usize,
+) -> AnyResult<(NonBlockingWriter, WriterWorker)> {
+    let (sender, receiver) = mpsc::sync_channel(queue);
+    let dropped = Arc::new(AtomicU64::new(0));
+    let handle = thread::Builder::new()
+        .name("acovo-log-writer".to_string())
+        .spawn(move || {
+            ON_WRITER_THREAD.with(|flag| flag.set(true));
+            for message in receiver {
+                match message {
+                    WriterMessage::Line(line) => {
+                        let _ = writer.write_all(&line);
+                    }
+                    WriterMessage::Flush(ack) => {
+                        let _ = writer.flush();
+                        let _ = ack.send(());
+                    }
+                    WriterMessage::Shutdown => break,
+                }
+            }
+            let _ = writer.flush();
+        })?;
+    let worker = WriterWorker {
+        sender: sender.clone(),
+        handle: Some(handle),
+        dropped: dropped.clone(),
+    };
+    Ok((NonBlockingWriter { sender, dropped }, worker))
+}
+
+/// Owns the background log writer threads
+///
+/// File output is queued and written by a background thread. Logging never
+/// blocks: if the disk falls behind and the queue fills up, further lines are
+/// dropped and counted (`dropped_lines`). `flush` waits until everything logged
+/// so far is on disk. Dropping the guard (or calling
+/// `shutdown`) writes out the queue and stops the threads; lines logged after
+/// that are discarded. Hold it for the lifetime of the program, e.g. in a
+/// variable in `main`, or use `TracingConfig::init_global`.
+#[cfg(feature = "trace")]
+#[must_use = "dropping the guard stops file logging"]
+pub struct TracingGuard {
+    workers: Vec<WriterWorker>,
+    filter: FilterHandle,
+    buffer: Option<LogBuffer>,
+    log_path: Option<PathBuf>,
+}
+
+#[cfg(feature = "trace")]
+impl TracingGuard {
+    /// Handle for changing the log filter at runtime
+    pub fn filter_handle(&self) -> FilterHandle {
+        self.filter.clone()
+    }
+
+    /// The in-memory event buffer, if `recent_events` or `recent_max_age` was configured
+    pub fn log_buffer(&self) -> Option<LogBuffer> {
+        self.buffer.clone()
+    }
+
+    /// Directory of the log files
+    ///
+    /// # Errors
+    /// * Returns an error if the directory could not be determined
+    pub fn log_path(&self) -> AnyResult<PathBuf> {
+        self.log_path
+            .clone()
+            .ok_or_else(|| anyhow!("log directory is unknown"))
+    }
+
+    /// Wait until every line logged before this call has been written and flushed
+    ///
+    /// Gives up after 5 seconds, e.g. when the disk stalls, and does nothing
+    /// when called on a log writer thread.
+    ///
+    /// # Returns
+    /// * `true` if every writer thread confirmed the flush
+    pub fn flush(&self) -> bool {
+        self.flush_timeout(FLUSH_TIMEOUT)
+    }
+
+    // Flush, waiting at most `timeout` in total
+    fn flush_timeout(&self, timeout: time::Duration) -> bool {
+        if ON_WRITER_THREAD.with(|flag| flag.get()) {
+            return false;
+        }
+        let deadline = Instant::now() + timeout;
+        self.workers.iter().all(|worker| worker.flush_until(deadline))
+    }
+
+    /// Number of lines dropped because the writer queue was full
+    pub fn dropped_lines(&self) -> u64 {
+        self.workers.iter().map(|worker| worker.dropped.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Write out all queued lines and stop the writer threads
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    // Stop every writer thread after it drains its queue
+    fn stop(&mut self) {
+        for worker in &mut self.workers {
+            let _ = worker.sender.send(WriterMessage::Shutdown);
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Debug for TracingGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TracingGuard").field("workers", &self.workers.len()).finish()
+    }
+}
+
+// Guard kept by `TracingConfig::init_global`
+#[cfg(feature = "trace")]
+static GLOBAL_GUARD: Mutex<Option<TracingGuard>> = Mutex::new(None);
+
+// Lock the guard kept by `TracingConfig::init_global`
+#[cfg(feature = "trace")]
+fn global_guard() -> MutexGuard<'static, Option<TracingGuard>> {
+    GLOBAL_GUARD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Flush the log files of the subscriber installed by `TracingConfig::init_global`
+///
+/// Never blocks for long: does nothing if no global guard is held, if the guard
+/// is in use by another call, or on a log writer thread, and waits at most 5
+/// seconds for the writer threads (see `TracingGuard::flush`).
+///
+/// # Returns
+/// * `true` if the log files were flushed
+#[cfg(feature = "trace")]
+pub fn flush() -> bool {
+    let guard = match GLOBAL_GUARD.try_lock() {
+        Ok(guard) => guard,
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(std::sync::TryLockError::WouldBlock) => return false,
+    };
+    guard.as_ref().map(|guard| guard.flush()).unwrap_or(false)
+}
+
+/// Filter handle of the subscriber installed by `TracingConfig::init_global`
+#[cfg(feature = "trace")]
+pub fn filter_handle() -> Option<FilterHandle> {
+    global_guard().as_ref().map(|guard| guard.filter_handle())
+}
+
+/// In-memory event buffer of the subscriber installed by `TracingConfig::init_global`
+#[cfg(feature = "trace")]
+pub fn log_buffer() -> Option<LogBuffer> {
+    global_guard().as_ref().and_then(|guard| guard.log_buffer())
+}
+
+/// Flush and stop the log writers installed by `TracingConfig::init_global`
+///
+/// Runs automatically at exit on Unix; later file output is discarded.
+#[cfg(feature = "trace")]
+pub fn shutdown() {
+    let guard = global_guard().take();
+    drop(guard);
+}
+
+// Write out the global log files when the process exits
+#[cfg(all(feature = "trace", unix))]
+extern "C" fn shutdown_at_exit() {
+    let mut slot = match GLOBAL_GUARD.try_lock() {
+        Ok(slot) => slot,
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(std::sync::TryLockError::WouldBlock) => return,
+    };
+    if let Some(guard) = slot.take() {
+        // Joining a stalled writer would hang the exit, so only stop drained writers
+        if guard.flush() {
+            drop(guard);
+        } else {
+            std::mem::forget(guard);
+        }
+    }
+}
+
+// Register `shutdown_at_exit` once per process
+#[cfg(all(feature = "trace", unix))]
+fn register_exit_hook() {
+    static REGISTERED: std::sync::Once = std::sync::Once::new();
+    REGISTERED.call_once(|| {
+        // A failed registration leaves `shutdown` as the only way to drain the queue
+        // SAFETY: the hook is a plain function that only touches process-wide statics
+        let _ = unsafe { libc::atexit(shutdown_at_exit) };
+    });
+}
+
+// Subscriber stack below the output layers
+#[cfg(feature = "trace")]
+type FilteredRegistry = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+// Parse comma-separated directive groups into one filter; later directives win
+#[cfg(feature = "trace")]
+fn parse_filter(directives: &[String]) -> AnyResult<EnvFilter> {
+    let joined = directives.join(",");
+    EnvFilter::builder()
+        .parse(&joined)
+        .map_err(|e| anyhow!("invalid log directives '{}': {}", joined, e))
+}
+
+// The target and level directives of a filter in `RUST_LOG` syntax
+#[cfg(feature = "trace")]
+fn static_targets(directives: &str) -> Targets {
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (i, c) in directives.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&directives[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&directives[start..]);
+    parts
+        .into_iter()
+        .filter(|d| !d.is_empty() && !d.contains('['))
+        .collect::<Vec<_>>()
+        .join(",")
+        .parse()
+        .unwrap_or_default()
+}
+
+// How often background reload threads check for work
+#[cfg(feature = "trace")]
+const RELOAD_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+// A temporary per-target level that reverts at `until`
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone)]
+struct Escalation {
+    target: String,
+    level: LevelFilter,
+    until: Instant,
+}
+
+// Filter settings shared by all clones of a FilterHandle
+#[cfg(feature = "trace")]
+struct FilterState {
+    initial: TracingConfig,
+    current: TracingConfig,
+    escalations: Vec<Escalation>,
+}
+
+#[cfg(feature = "trace")]
+struct FilterInner {
+    reload: reload::Handle<EnvFilter, Registry>,
+    state: Mutex<FilterState>,
+    // Whether the thread reverting expired escalations is running
+    expiry_started: AtomicBool,
+}
+
+/// Changes the log filter of a running subscriber
+///
+/// Obtained from `TracingGuard::filter_handle` or `trace::filter_handle`. Clones
+/// share the same filter. Changes apply to every output immediately. The handle
+/// has no effect once the subscriber is dropped, and reload threads started from
+/// it stop once all clones and the guard are dropped.
+///
+/// # Example
+/// ```no_run
+/// use acovo::trace::TracingConfig;
+/// use std::time::Duration;
+/// use tracing::Level;
+///
+/// let guard = TracingConfig::new().level(Level::INFO).init()?;
+/// let filter = guard.filter_handle();
+/// filter.set_target("my_app::db", Level::DEBUG)?;
+/// // TRACE for the network code for ten minutes, then back to normal
+/// filter.escalate("my_app::net", Level::TRACE, Duration::from_secs(600))?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[cfg(feature = "trace")]
+#[derive(Clone)]
+pub struct FilterHandle {
+    inner: Arc<FilterInner>,
+}
+
+#[cfg(feature = "trace")]
+impl FilterHandle {
+    // Wrap the reload handle of a subscriber built from `config`
+    fn new(reload: reload::Handle<EnvFilter, Registry>, config: &TracingConfig) -> Self {
+        let state = FilterState {
+            initial: config.clone(),
+            current: config.clone(),
+            escalations: Vec::new(),
+        };
+        FilterHandle {
+            inner: Arc::new(FilterInner {
+                reload,
+                state: Mutex::new(state),
+                expiry_started: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    // Lock the shared state, ignoring poisoning
+    fn state(&self) -> MutexGuard<'_, FilterState> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Rebuild the filter from `state`, dropping expired escalations
+    fn apply(&self, state: &mut FilterState) -> AnyResult<()> {
+        let now = Instant::now();
+        state.escalations.retain(|e| e.until > now);
+        let mut directives = state.current.filter_directives()?;
+        for escalation in &state.escalations {
+            directives.push(if escalation.target.is_empty() {
+                escalation.level.to_string()
+            } else {
+                format!("{}={}", escalation.target, escalation.level)
+            });
+        }
+        let filter = parse_filter(&directives)?;
+        self.inner
+            .reload
+            .reload(filter)
+            .map_err(|e| anyhow!("failed to reload log filter: {}", e))
+    }
+
+    // Change the current settings, rolling back if they are invalid
+    fn update(&self, change: impl FnOnce(&mut TracingConfig)) -> AnyResult<()> {
+        let mut state = self.state();
+        let previous = state.current.clone();
+        change(&mut state.current);
+        if let Err(e) = self.apply(&mut state) {
+            state.current = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Set the default level
+    pub fn set_level(&self, level: Level) -> AnyResult<()> {
+        self.update(|config| config.level = level.to_string().to_lowercase())
+    }
+
+    /// Replace the filter directives, in `RUST_LOG` syntax
+    ///
+    /// # Errors
+    /// * Returns an error if the directives cannot be parsed; the filter is unchanged
+    pub fn set_directives(&self, directives: &str) -> AnyResult<()> {
+        self.update(|config| config.directives = Some(directives.to_string()))
+    }
+
+    /// Set the level of one target and the modules below it
+    pub fn set_target(&self, target: &str, level: Level) -> AnyResult<()> {
+        self.update(|config| {
+            config.targets.insert(target.to_string(), level.to_string().to_lowercase());
+        })
+    }
+
+    /// Remove a per-target level set by `set_target` or the config
+    pub fn clear_target(&self, target: &str) -> AnyResult<()> {
+        self.update(|config| {
+            config.targets.remove(target);
+        })
+    }
+
+    /// Take the level, directives and targets of `config`
+    ///
+    /// Output settings such as the log file cannot change at runtime and are ignored.
+    pub fn apply_config(&self, config: &TracingConfig) -> AnyResult<()> {
+        self.update(|current| {
+            current.level = config.level.clone();
+            current.directives = config.directives.clone();
+            current.targets = config.targets.clone();
+        })
+    }
+
+    /// Restore the filter the subscriber was built with and end all escalations
+    pub fn reset(&self) -> AnyResult<()> {
+        let mut state = self.state();
+        state.current = state.initial.clone();
+        state.escalations.clear();
+        self.apply(&mut state)
+    }
+
+    /// Set `target` to `level` for `duration`, then revert automatically
+    ///
+    /// An empty target escalates the default level. Escalations override the
+    /// configured level of the same target and survive `set_*` calls until they expire.
+    /// A new escalation of the same target replaces the previous one, including its
+    /// expiry. Expired escalations are reverted within 200 milliseconds.
+    pub fn escalate(&self, target: &str, level: Level, duration: std::time::Duration) -> AnyResult<()> {
+        self.start_expiry()?;
+        let mut state = self.state();
+        state.escalations.retain(|e| e.target != target);
+        state.escalations.push(Escalation {
+            target: target.to_string(),
+            level: LevelFilter::from_level(level),
+            until: Instant::now() + duration,
+        });
+        self.apply(&mut state)
+    }
+
+    // Start the poller reverting expired escalations, once per filter
+    fn start_expiry(&self) -> AnyResult<()> {
+        if self.inner.expiry_started.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let started = self.spawn_poller("acovo-log-escalation", RELOAD_POLL_INTERVAL, |handle| {
+            let mut state = handle.state();
+            let now = Instant::now();
+            if state.escalations.iter().any(|e| e.until <= now) {
+                if let Err(e) = handle.apply(&mut state) {
+                    error!(target: "acovo::trace", "log escalation revert failed: {}", e);
+                }
+            }
+        });
+        if started.is_err() {
+            self.inner.expiry_started.store(false, Ordering::SeqCst);
+        }
+        started
+    }
+
+    /// The filter currently in effect, in `RUST_LOG` syntax
+    pub fn current(&self) -> String {
+        self.inner
+            .reload
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Whether an event at `level` from `target` currently passes the filter
+    ///
+    /// Only target and level directives are considered; span and field
+    /// directives depend on the span an event is recorded in.
+    pub fn would_enable(&self, target: &str, level: &Level) -> bool {
+        static_targets(&self.current()).would_enable(target, level)
+    }
+
+    // Reload the filter settings from a config file
+    fn reload_file(&self, path: &Path) {
+        match TracingConfig::from_file(path).and_then(|config| self.apply_config(&config)) {
+            Ok(()) => info!(target: "acovo::trace", path = %path.display(), filter = %self.current(), "log filter reloaded"),
+            Err(e) => error!(target: "acovo::trace", path = %path.display(), "log filter reload failed: {}", e),
+        }
+    }
+
+    // Run `check` every poll interval until all handles are dropped
+    fn spawn_poller<F>(&self, name: &str, interval: std::time::Duration, mut check: F) -> AnyResult<()>
+    where
+        F: FnMut(&FilterHandle) + Send + 'static,
+    {
+        let weak = Arc::downgrade(&self.inner);
+        thread::Builder::new().name(name.to_string()).spawn(move || loop {
+            thread::sleep(interval);
+            match weak.upgrade() {
+                Some(inner) => check(&FilterHandle { inner }),
+                None => break,
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Reload the filter settings from `path` whenever the file changes
+    ///
+    /// The file is checked every `interval`. Invalid files are reported as an
+    /// error event and leave the filter unchanged.
+    pub fn watch_file<P: Into<PathBuf>>(&self, path: P, interval: std::time::Duration) -> AnyResult<()> {
+        let path = path.into();
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last = modified(&path);
+        self.spawn_poller("acovo-log-watch", interval, move |handle| {
+            let now = modified(&path);
+            if now.is_some() && now != last {
+                last = now;
+                handle.reload_file(&path);
+            }
+        })
+    }
+
+    /// Reload the filter settings from `path` when the process receives SIGHUP
+    ///
+    /// Installs a process-wide SIGHUP handler, replacing the default action of
+    /// terminating the process.
+    #[cfg(unix)]
+    pub fn reload_on_sighup<P: Into<PathBuf>>(&self, path: P) -> AnyResult<()> {
+        install_sighup_handler()?;
+        let path = path.into();
+        let mut seen = SIGHUP_COUNT.load(Ordering::SeqCst);
+        self.spawn_poller("acovo-log-sighup", RELOAD_POLL_INTERVAL, move |handle| {
+            let count = SIGHUP_COUNT.load(Ordering::SeqCst);
+            if count != seen {
+                seen = count;
+                handle.reload_file(&path);
+            }
+        })
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Debug for FilterHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterHandle").field("current", &self.current()).finish()
+    }
+}
+
+// Number of SIGHUP signals received since the handler was installed
+#[cfg(all(feature = "trace", unix))]
+static SIGHUP_COUNT: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(all(feature = "trace", unix))]
+extern "C" fn on_sighup(_: libc::c_int) {
+    // Only async-signal-safe work here; the poller threads do the reload
+    SIGHUP_COUNT.fetch_add(1, Ordering::SeqCst);
+}
+
+// Install the SIGHUP handler once per process
+#[cfg(all(feature = "trace", unix))]
+fn install_sighup_handler() -> AnyResult<()> {
+    static INSTALLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    let installed = *INSTALLED.get_or_init(|| unsafe {
+        let handler = on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGHUP, handler) != libc::SIG_ERR
+    });
+    if installed {
+        Ok(())
+    } else {
+        Err(anyhow!("failed to install SIGHUP handler"))
+    }
+}
+
+// Collects event or span fields into a JSON object
+#[cfg(feature = "trace")]
+struct JsonVisitor<'a> {
+    fields: &'a mut serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "trace")]
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), serde_json::json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.fields.insert(field.name().to_string(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+/// Formats span fields as a JSON object, for use with `JsonFormat`
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFields;
+
+#[cfg(feature = "trace")]
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> std::fmt::Result {
+        let mut map = serde_json::Map::new();
+        fields.record(&mut JsonVisitor { fields: &mut map });
+        write!(writer, "{}", serde_json::Value::Object(map))
+    }
+
+    fn add_fields(&self, current: &'writer mut FormattedFields<Self>, fields: &span::Record<'_>) -> std::fmt::Result {
+        // Merge into the existing object rather than appending a second one
+        let mut map = match serde_json::from_str(&current.fields) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        fields.record(&mut JsonVisitor { fields: &mut map });
+        current.fields = serde_json::Value::Object(map).to_string();
+        Ok(())
+    }
+}
+
+// One entry of the span stack of a JSON record
+#[cfg(feature = "trace")]
+#[derive(Serialize)]
+struct JsonSpan<'a> {
+    name: &'a str,
+    fields: serde_json::Value,
+}
+
+// A JSON log record; field order is part of the schema
+#[cfg(feature = "trace")]
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<serde_json::Value>,
+    spans: Vec<JsonSpan<'a>>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+// Numeric id of the current thread, assigned in the order threads first log
+#[cfg(feature = "trace")]
+fn current_thread_number() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static NUMBER: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    // 0 once the thread's locals are destroyed, e.g. when logging from a TLS destructor
+    NUMBER.try_with(|number| *number).unwrap_or(0)
+}
+
+/// Formats each event as one JSON object per line
+///
+/// Schema version 1; keys appear in this order and are never renamed:
+///
+/// | key           | type   | notes                                                   |
+/// |---------------|--------|---------------------------------------------------------|
+/// | `timestamp`   | string | local time as written by `LocalTimeFormatter`           |
+/// | `level`       | string | `TRACE`, `DEBUG`, `INFO`, `WARN` or `ERROR`             |
+/// | `target`      | string | event target, usually the module path                   |
+/// | `file`        | string | source file; omitted when unknown or disabled           |
+/// | `line`        | number | source line; omitted when unknown or disabled           |
+/// | `thread_id`   | number | numeric id of the logging thread; omitted when disabled |
+/// | `thread_name` | string | omitted for unnamed threads                             |
+/// | `message`     | string | the event message; omitted for events without one       |
+/// | `spans`       | array  | `{ "name", "fields" }` objects, outermost span first    |
+/// | `fields`      | object | all other event fields, with JSON numbers and booleans  |
+///
+/// Use it with `JsonFields`, which stores span fields as JSON, e.g.
+/// `tracing_subscriber::fmt::layer().fmt_fields(JsonFields).event_format(JsonFormat::new())`.
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy)]
+pub struct JsonFormat {
+    line_numbers: bool,
+    thread_ids: bool,
+}
+
+#[cfg(feature = "trace")]
+impl JsonFormat {
+    /// A format writing every key of the schema
+    pub fn new() -> Self {
+        JsonFormat {
+            line_numbers: true,
+            thread_ids: true,
+        }
+    }
+
+    /// Whether records include `file` and `line`
+    pub fn with_line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Whether records include `thread_id`
+    pub fn with_thread_ids(mut self, thread_ids: bool) -> Self {
+        self.thread_ids = thread_ids;
+        self
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Default for JsonFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "trace")]
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let mut timestamp = String::new();
+        LocalTimeFormatter.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut fields = serde_json::Map::new();
+        event.record(&mut JsonVisitor { fields: &mut fields });
+        let message = fields.remove("message");
+
+        let span_fields: Vec<(&'static str, serde_json::Value)> = ctx
+            .event_scope()
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| {
+                        let fields = span
+                            .extensions()
+                            .get::<FormattedFields<JsonFields>>()
+                            .and_then(|f| serde_json::from_str(&f.fields).ok())
+                            .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
+                        (span.name(), fields)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let current = thread::current();
+        let metadata = event.metadata();
+        let record = JsonRecord {
+            timestamp,
+            level: metadata.level().as_str(),
+            target: metadata.target(),
+            file: metadata.file().filter(|_| self.line_numbers),
+            line: metadata.line().filter(|_| self.line_numbers),
+            thread_id: self.thread_ids.then(current_thread_number),
+            thread_name: current.name(),
+            message,
+            spans: span_fields
+                .into_iter()
+                .map(|(name, fields)| JsonSpan { name, fields })
+                .collect(),
+            fields,
+        };
+        let line = serde_json::to_string(&record).map_err(|_| std::fmt::Error)?;
+        writeln!(writer, "{}", line)
+    }
+}
+
+/// One event captured by `LogBuffer`
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    /// Local time the event was recorded
+    pub timestamp: DateTime<Local>,
+    /// Event level
+    pub level: Level,
+    /// Event target, usually the module path
+    pub target: String,
+    /// The event message, empty for events without one
+    pub message: String,
+    /// All other event fields
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:>5} {}: {}",
+            self.timestamp.format("%FT%T%.3f"),
+            self.level,
+            self.target,
+            self.message
+        )?;
+        for (name, value) in &self.fields {
+            match value {
+                serde_json::Value::String(text) => write!(f, " {}={}", name, text)?,
+                other => write!(f, " {}={}", name, other)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "trace")]
+impl LogRecord {
+    /// The record as a JSON object with `timestamp`, `level`, `target`, `message` and `fields`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "timestamp": self.timestamp.format("%FT%T%.3f%:z").to_string(),
+            "level": self.level.as_str(),
+            "target": self.target,
+            "message": self.message,
+            "fields": self.fields,
+        })
+    }
+}
+
+/// Selects records from a `LogBuffer`, a `LogCapture` or log files read by `LogReader`
+///
+/// All conditions must match; an empty query matches every record.
+///
+/// # Example
+/// ```
+/// use acovo::trace::LogQuery;
+/// use std::time::Duration;
+/// use tracing::Level;
+///
+/// let query = LogQuery::new()
+///     .level(Level::WARN)
+///     .target("my_app::db")
+///     .field_eq("user", "alice")
+///     .last(Duration::from_secs(600))
+///     .limit(50);
+/// ```
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    level: Option<Level>,
+    targets: Vec<String>,
+    fields: Vec<(String, Option<String>)>,
+    since: Option<DateTime<Local>>,
+    last: Option<chrono::Duration>,
+    until: Option<DateTime<Local>>,
+    text: Option<String>,
+    pattern: Option<regex::Regex>,
+    limit: Option<usize>,
+}
+
+#[cfg(feature = "trace")]
+impl LogQuery {
+    /// A query matching every record
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only records at `level` or more severe
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Only records from `target` or its submodules; repeat to allow several targets
+    pub fn target(mut self, target: &str) -> Self {
+        self.targets.push(target.to_string());
+        self
+    }
+
+    /// Only records having the field `name`
+    pub fn field(mut self, name: &str) -> Self {
+        self.fields.push((name.to_string(), None));
+        self
+    }
+
+    /// Only records whose field `name` equals `value`; non-string fields compare by their JSON text
+    pub fn field_eq(mut self, name: &str, value: &str) -> Self {
+        self.fields.push((name.to_string(), Some(value.to_string())));
+        self
+    }
+
+    /// Only records at or after `time`
+    pub fn since(mut self, time: DateTime<Local>) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Only records from the last `duration`, measured whenever the query is evaluated
+    pub fn last(mut self, duration: std::time::Duration) -> Self {
+        self.last = Some(chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX));
+        self
+    }
+
+    /// Only records before `time`
+    pub fn until(mut self, time: DateTime<Local>) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Only records whose message contains `text`
+    pub fn contains(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    /// Only records whose message matches `pattern`
+    pub fn matching(mut self, pattern: regex::Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Return at most the newest `count` matching records
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Whether `record` satisfies every condition except the limit
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if self.level.is_some_and(|level| record.level > level) {
+            return false;
+        }
+        if !self.targets.is_empty()
+            && !self.targets.iter().any(|target| {
+                record.target == *target
+                    || record
+                        .target
+                        .strip_prefix(target.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+        {
+            return false;
+        }
+        let fields_match = self.fields.iter().all(|(name, expected)| match (record.fields.get(name), expected) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(serde_json::Value::String(actual)), Some(expected)) => actual == expected,
+            (Some(actual), Some(expected)) => {
+                serde_json::from_str::<serde_json::Value>(expected).is_ok_and(|expected| expected == *actual)
+            }
+        });
+        if !fields_match {
+            return false;
+        }
+        if self.since.is_some_and(|since| record.timestamp < since) {
+            return false;
+        }
+        if let Some(last) = self.last {
+            if Local::now().checked_sub_signed(last).is_some_and(|cutoff| record.timestamp < cutoff) {
+                return false;
+            }
+        }
+        if self.until.is_some_and(|until| record.timestamp >= until) {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            if !record.message.contains(text.as_str()) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(&record.message) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Streams registered with `LogBuffer::subscribe` and the records they want
+#[cfg(feature = "trace")]
+type LogSubscribers = Vec<(LogQuery, mpsc::SyncSender<LogRecord>)>;
+
+// Number of records a subscriber may fall behind before records are dropped for it
+#[cfg(feature = "trace")]
+const LOG_SUBSCRIBER_QUEUE: usize = 1024;
+
+/// Default capacity of a `LogBuffer` limited only by age
+#[cfg(feature = "trace")]
+pub const DEFAULT_BUFFER_EVENTS: usize = 10_000;
+
+/// A tracing layer keeping the most recent events in memory
+///
+/// The buffer holds the last `capacity` events and, with `with_max_age`, only
+/// those from the last given duration. Records can be read with `snapshot` or
+/// `query`, and `subscribe` streams new records as they arrive.
+///
+/// Clones share the same buffer, so one clone can be added to the subscriber
+/// while another is handed to a crash handler or diagnostics endpoint. Only
+/// events that pass the subscriber's filter are recorded; `with_query` narrows
+/// that further. Configure a buffer before cloning it.
+#[cfg(feature = "trace")]
+#[derive(Clone)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    subscribers: Arc<Mutex<LogSubscribers>>,
+    capacity: usize,
+    max_age: Option<chrono::Duration>,
+    filter: Option<LogQuery>,
+}
+
+#[cfg(feature = "trace")]
+impl LogBuffer {
+    /// Create a buffer holding the last `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity.min(4096)))),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            capacity,
+            max_age: None,
+            filter: None,
+        }
+    }
+
+    /// Also drop events older than `age`
+    pub fn with_max_age(mut self, age: std::time::Duration) -> Self {
+        self.max_age = Some(chrono::Duration::from_std(age).unwrap_or(chrono::Duration::MAX));
+        self
+    }
+
+    /// Only record events matching `query`; its limit is ignored
+    pub fn with_query(mut self, query: LogQuery) -> Self {
+        self.filter = Some(query);
+        self
+    }
+
+    // Lock the records and drop expired ones, ignoring poisoning so a panic hook can still read them
+    fn records(&self) -> MutexGuard<'_, VecDeque<LogRecord>> {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(max_age) = self.max_age {
+            if let Some(cutoff) = Local::now().checked_sub_signed(max_age) {
+                while records.front().is_some_and(|record| record.timestamp < cutoff) {
+                    records.pop_front();
+                }
+            }
+        }
+        records
+    }
+
+    /// Maximum number of events kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of events currently held
+    pub fn len(&self) -> usize {
+        self.records().len()
+    }
+
+    /// Whether no events are held
+    pub fn is_empty(&self) -> bool {
+        self.records().is_empty()
+    }
+
+    /// Copy of the held events, oldest first
+    pub fn snapshot(&self) -> Vec<LogRecord> {
+        self.records().iter().cloned().collect()
+    }
+
+    /// Held events matching `query`, oldest first
+    pub fn query(&self, query: &LogQuery) -> Vec<LogRecord> {
+        let records = self.records();
+        let mut matched: Vec<LogRecord> = records.iter().filter(|r| query.matches(r)).cloned().collect();
+        if let Some(limit) = query.limit {
+            let excess = matched.len().saturating_sub(limit);
+            matched.drain(..excess);
+        }
+        matched
+    }
+
+    /// Stream new events matching `query`
+    ///
+    /// The receiver gets every matching event recorded after this call. A
+    /// receiver that falls more than 1024 events behind misses events until it
+    /// catches up. Dropping the receiver ends the subscription.
+    pub fn subscribe(&self, query: LogQuery) -> mpsc::Receiver<LogRecord> {
+        let (sender, receiver) = mpsc::sync_channel(LOG_SUBSCRIBER_QUEUE);
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((query, sender));
+        receiver
+    }
+
+    /// Drop all held events
+    pub fn clear(&self) {
+        self.records().clear();
+    }
+
+    // Append a record, evicting the oldest when full, and notify subscribers
+    fn push(&self, record: LogRecord) {
+        if self.capacity == 0 || self.filter.as_ref().is_some_and(|filter| !filter.matches(&record)) {
+            return;
+        }
+        {
+            let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+            subscribers.retain(|(query, sender)| {
+                if !query.matches(&record) {
+                    return true;
+                }
+                !matches!(sender.try_send(record.clone()), Err(mpsc::TrySendError::Disconnected(_)))
+            });
+        }
+        let mut records = self.records();
+        while records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Debug for LogBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogBuffer")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "trace")]
+impl<S: tracing::Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let mut fields = serde_json::Map::new();
+        event.record(&mut JsonVisitor { fields: &mut fields });
+        let message = match fields.remove("message") {
+            Some(serde_json::Value::String(text)) => text,
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        let metadata = event.metadata();
+        self.push(LogRecord {
+            timestamp: Local::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message,
+            fields,
+        });
+    }
+}
+
+// Time the crash handler was installed, used for the uptime in reports
+#[cfg(feature = "trace")]
+static CRASH_HANDLER_INSTALLED: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+
+/// A panic hook that writes a crash report file for each panic
+///
+/// Each report is a text file `<prefix>-<YYYYmmdd-HHMMSS-mmm>-<pid>.txt` in the
+/// report directory holding the panic message and location, thread, backtrace,
+/// build and process information, and the recent events of a `LogBuffer` if one
+/// is attached. The panic is also logged as an ERROR event and the global log
+/// files are flushed. The handler does not depend on the tracing subscriber,
+/// so it can be installed on its own.
+///
+/// # Example
+/// ```no_run
+/// use acovo::trace::{CrashHandler, TracingConfig};
+///
+/// let guard = TracingConfig::new().file_prefix("my_app").recent_events(500).init()?;
+/// let mut crash = CrashHandler::new(guard.log_path()?)
+///     .build_info("version", env!("CARGO_PKG_VERSION"))
+///     .chain_previous(true);
+/// if let Some(buffer) = guard.log_buffer() {
+///     crash = crash.recent_logs(buffer);
+/// }
+/// crash.install()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone)]
+pub struct CrashHandler {
+    dir: PathBuf,
+    prefix: String,
+    chain: bool,
+    recent: Option<LogBuffer>,
+    build_info: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "trace")]
+impl CrashHandler {
+    /// Create a handler writing reports into `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        CrashHandler {
+            dir: dir.into(),
+            prefix: "crash".to_string(),
+            chain: false,
+            recent: None,
+            build_info: BTreeMap::new(),
+        }
+    }
+
+    /// Set the report file name prefix, "crash" by default
+    pub fn file_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Also run the panic hook that was installed before this one
+    ///
+    /// With the default hook this keeps the usual panic message on stderr.
+    pub fn chain_previous(mut self, chain: bool) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Include the events held by `buffer` in each report
+    pub fn recent_logs(mut self, buffer: LogBuffer) -> Self {
+        self.recent = Some(buffer);
+        self
+    }
+
+    /// Add a line to the build section, e.g. the application version or git commit
+    pub fn build_info(mut self, key: &str, value: &str) -> Self {
+        self.build_info.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Install the handler as the process panic hook
+    ///
+    /// # Errors
+    /// * Returns an error if the report directory cannot be created
+    pub fn install(self) -> AnyResult<()> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| anyhow!("failed to create crash report directory {}: {}", self.dir.display(), e))?;
+        CRASH_HANDLER_INSTALLED.get_or_init(Instant::now);
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let message = match info.payload().downcast_ref::<&str>() {
+                Some(text) => text.to_string(),
+                None => match info.payload().downcast_ref::<String>() {
+                    Some(text) => text.clone(),
+                    None => "Box<dyn Any>".to_string(),
+                },
+            };
+            let location = info.location();
+            let report = self.write_report(&message, location);
+
+            match location {
+                Some(location) => error!(
+                    message = %message,
+                    panic.file = location.file(),
+                    panic.line = location.line(),
+                    panic.column = location.column(),
+                ),
+                None => error!(message = %message),
+            }
+            // Bounded: skips a guard that is already locked (e.g. a panic inside
+            // flush) and the log writer thread, and waits at most a few seconds
+            flush();
+
+            if self.chain {
+                previous(info);
+            }
+            match report {
+                Ok(path) => eprintln!("crash report written to {}", path.display()),
+                Err(e) => eprintln!("failed to write crash report: {}", e),
+            }
+        }));
+        Ok(())
+    }
+
+    /// Write a crash report for the current thread without panicking
+    ///
+    /// Useful for fatal errors that end the process some other way.
+    ///
+    /// # Returns
+    /// * The path of the report file
+    ///
+    /// # Errors
+    /// * Returns an error if the report file cannot be written
+    pub fn write_report(&self, message: &str, location: Option<&std::panic::Location<'_>>) -> AnyResult<PathBuf> {
+        let now = Local::now();
+        let path = self.dir.join(format!(
+            "{}-{}-{}.txt",
+            self.prefix,
+            now.format("%Y%m%d-%H%M%S-%3f"),
+            std::process::id()
+        ));
+        let report = self.render_report(&now, message, location);
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, report)?;
+        Ok(path)
+    }
+
+    // Text of a crash report
+    fn render_report(
+        &self,
+        now: &DateTime<Local>,
+        message: &str,
+        location: Option<&std::panic::Location<'_>>,
+    ) -> String {
+        use std::fmt::Write as _;
+
+        let mut report = String::new();
+        let current = thread::current();
+        let _ = writeln!(report, "=== Crash report ===");
+        let _ = writeln!(report, "time: {}", now.format("%FT%T%.3f%:z"));
+        let _ = writeln!(report, "message: {}", message);
+        match location {
+            Some(location) => {
+                let _ = writeln!(report, "location: {}:{}:{}", location.file(), location.line(), location.column());
+            }
+            None => {
+                let _ = writeln!(report, "location: unknown");
+            }
+        }
+        let _ = writeln!(
+            report,
+            "thread: {} ({:?})",
+            current.name().unwrap_or("<unnamed>"),
+            current.id()
+        );
+
+        let _ = writeln!(report, "\n--- Build ---");
+        for (key, value) in &self.build_info {
+            let _ = writeln!(report, "{}: {}", key, value);
+        }
+        let _ = writeln!(report, "acovo: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "target: {}-{}", std::env::consts::ARCH, std::env::consts::OS);
+        let _ = writeln!(
+            report,
+            "profile: {}",
+            if cfg!(debug_assertions) { "debug" } else { "release" }
+        );
+
+        let _ = writeln!(report, "\n--- Process ---");
+        let _ = writeln!(report, "pid: {}", std::process::id());
+        if let Ok(exe) = std::env::current_exe() {
+            let _ = writeln!(report, "exe: {}", exe.display());
+        }
+        let args: Vec<String> = std::env::args().collect();
+        let _ = writeln!(report, "args: {:?}", args);
+        if let Ok(dir) = std::env::current_dir() {
+            let _ = writeln!(report, "cwd: {}", dir.display());
+        }
+        if let Some(installed) = CRASH_HANDLER_INSTALLED.get() {
+            let _ = writeln!(report, "uptime: {}", format_duration_compact(installed.elapsed()));
+        }
+        // Memory and thread counts where the platform exposes them
+        if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
+            for line in status.lines() {
+                if line.starts_with("VmRSS:") || line.starts_with("VmPeak:") || line.starts_with("Threads:") {
+                    let _ = writeln!(report, "{}", line.split_whitespace().collect::<Vec<_>>().join(" "));
+                }
+            }
+        }
+
+        let _ = writeln!(report, "\n--- Backtrace ---");
+        let _ = writeln!(report, "{}", std::backtrace::Backtrace::force_capture());
+
+        if let Some(buffer) = &self.recent {
+            let records = buffer.snapshot();
+            let _ = writeln!(report, "\n--- Recent log ({} events) ---", records.len());
+            for record in records {
+                let _ = writeln!(report, "{}", record);
+            }
+        }
+        report
+    }
+}
+
+/// A log file writer with time and size based rotation
+///
+/// Files are named `<prefix>.<period>` (e.g. `app.2024-01-31` for daily rotation)
+/// or just `<prefix>` with `Rotation::Never`. When a size limit is set and the
+/// current file is full, the next file of the same period gets a counter suffix:
+/// `app.2024-01-31.1`, `app.2024-01-31.2` and so on. Restarting the process
+/// appends to the latest file of the current period.
+///
+/// Closed files are compressed and old files pruned on a background thread, so
+/// rotation does not stall logging. Retention only considers files created by
+/// this naming scheme.
+///
+/// # Example
+/// ```no_run
+/// use acovo::trace::{Compression, RollingFileWriter, Rotation};
+/// use std::io::Write;
+///
+/// let mut writer = RollingFileWriter::new("./logs", "my_app", Rotation::Daily)?
+///     .with_max_size(100 * 1024 * 1024)
+///     .with_compression(Compression::Gzip)?
+///     .with_max_files(30);
+/// writer.write_all(b"hello\n")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[cfg(feature = "trace")]
+pub struct RollingFileWriter {
+    dir: PathBuf,
+    prefix: String,
+    rotation: Rotation,
+    max_size: Option<u64>,
+    compression: Compression,
+    max_files: Option<usize>,
+    max_age: Option<std::time::Duration>,
+    symlink: bool,
+    clock: fn() -> DateTime<Local>,
+    file: Option<File>,
+    path: Option<PathBuf>,
+    period: Option<String>,
+    index: u32,
+    size: u64,
+    maintenance: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "trace")]
+impl RollingFileWriter {
+    /// Create a writer for `<dir>/<prefix>*` files; the directory is created if missing
+    ///
+    /// The first file is opened on the first write.
+    ///
+    /// # Errors
+    /// * Returns an error if the directory cannot be created
+    pub fn new<P: Into<PathBuf>>(dir: P, prefix: &str, rotation: Rotation) -> AnyResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("failed to create log directory {}: {}", dir.display(), e))?;
+        Ok(RollingFileWriter {
+            dir,
+            prefix: prefix.to_string(),
+            rotation,
+            max_size: None,
+            compression: Compression::None,
+            max_files: None,
+            max_age: None,
+            symlink: false,
+            clock: Local::now,
+            file: None,
+            path: None,
+            period: None,
+            index: 0,
+            size: 0,
+            maintenance: None,
+        })
+    }
+
+    /// Start a new file once the current one reaches `bytes`
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes.max(1));
+        self
+    }
+
+    /// Compress files once they are rotated
+    ///
+    /// # Errors
+    /// * Returns an error for `Compression::Zip` without the `compress` feature
+    pub fn with_compression(mut self, compression: Compression) -> AnyResult<Self> {
+        if compression == Compression::Zip && !cfg!(feature = "compress") {
+            return Err(anyhow!("zip log compression requires the compress feature"));
+        }
+        self.compression = compression;
+        Ok(self)
+    }
+
+    /// Keep at most `count` rotated files, deleting the oldest
+    pub fn with_max_files(mut self, count: usize) -> Self {
+        self.max_files = Some(count);
+        self
+    }
+
+    /// Delete rotated files last modified more than `age` ago
+    pub fn with_max_age(mut self, age: std::time::Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Maintain a `<prefix>.current` symlink to the file being written (Unix only)
+    pub fn with_symlink(mut self, symlink: bool) -> Self {
+        self.symlink = symlink;
+        self
+    }
+
+    /// Path of the file currently being written, once the first line is written
+    pub fn current_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    // File name of segment `index` of the period `period`
+    fn segment_name(&self, period: Option<&str>, index: u32) -> String {
+        let base = match period {
+            Some(period) => format!("{}.{}", self.prefix, period),
+            None => self.prefix.clone(),
+        };
+        if index == 0 {
+            base
+        } else {
+            format!("{}.{}", base, index)
+        }
+    }
+
+    // Whether the segment exists in plain or compressed form
+    fn segment_exists(&self, name: &str) -> bool {
+        self.dir.join(name).exists()
+            || ["gz", "zip"]
+                .iter()
+                .any(|ext| self.dir.join(format!("{}.{}", name, ext)).exists())
+    }
+
+    // Open the latest segment of the current period, or a new one if it is full
+    fn open(&mut self, now: &DateTime<Local>) -> std::io::Result<()> {
+        let period = self.rotation.period_key(now);
+        let mut index = 0;
+        while self.segment_exists(&self.segment_name(period.as_deref(), index + 1)) {
+            index += 1;
+        }
+        let name = self.segment_name(period.as_deref(), index);
+        let path = self.dir.join(&name);
+        let reusable = match std::fs::metadata(&path) {
+            Ok(meta) => self.max_size.is_none_or(|max| meta.len() < max),
+            Err(_) => !self.segment_exists(&name),
+        };
+        if !reusable {
+            index += 1;
+        }
+        self.open_segment(period, index)
+    }
+
+    // Open segment `index` of `period` for appending
+    fn open_segment(&mut self, period: Option<String>, index: u32) -> std::io::Result<()> {
+        let name = self.segment_name(period.as_deref(), index);
+        let path = self.dir.join(&name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        self.path = Some(path);
+        self.period = period;
+        self.index = index;
+        if self.symlink {
+            self.update_symlink(&name);
+        }
+        Ok(())
+    }
+
+    // Point `<prefix>.current` at `name`
+    fn update_symlink(&self, name: &str) {
+        #[cfg(unix)]
+        {
+            let link = self.dir.join(format!("{}.current", self.prefix));
+            let _ = std::fs::remove_file(&link);
+            let _ = std::os::unix::fs::symlink(name, &link);
+        }
+    }
+
+    // Close the current file and open the next one
+    fn rotate(&mut self, now: &DateTime<Local>) -> std::io::Result<()> {
+        let period = self.rotation.period_key(now);
+        let closed = self.path.take();
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        if period == self.period {
+            self.open_segment(period, self.index + 1)?;
+        } else {
+            self.open(now)?;
+        }
+        if closed.is_some() {
+            self.start_maintenance(closed);
+        }
+        Ok(())
+    }
+
+    // Compress `closed`, if any, and apply retention on a background thread
+    fn start_maintenance(&mut self, closed: Option<PathBuf>) {
+        let compress = closed.is_some() && self.compression != Compression::None;
+        if !compress && self.max_files.is_none() && self.max_age.is_none() {
+            return;
+        }
+        // One maintenance run at a time, so pruning never races a compression
+        if let Some(previous) = self.maintenance.take() {
+            let _ = previous.join();
+        }
+        let compression = self.compression;
+        let dir = self.dir.clone();
+        let prefix = self.prefix.clone();
+        let active = self.path.clone();
+        let max_files = self.max_files;
+        let max_age = self.max_age;
+        self.maintenance = thread::Builder::new()
+            .name("acovo-log-rotation".to_string())
+            .spawn(move || {
+                if let Some(closed) = closed {
+                    if let Err(e) = compress_log_file(&closed, compression) {
+                        eprintln!("failed to compress {}: {}", closed.display(), e);
+                    }
+                }
+                prune_log_files(&dir, &prefix, active.as_deref(), max_files, max_age);
+            })
+            .ok();
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Write for RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let now = (self.clock)();
+        if self.file.is_none() {
+            self.open(&now)?;
+            // Apply retention to the files left by earlier runs
+            self.start_maintenance(None);
+        } else {
+            let period_changed = self.rotation.period_key(&now) != self.period;
+            let full = self
+                .max_size
+                .is_some_and(|max| self.size > 0 && self.size + buf.len() as u64 > max);
+            if period_changed || full {
+                self.rotate(&now)?;
+            }
+        }
+        // Each buffer is one log line, so it is never split across files
+        let file = self.file.as_mut().expect("log file is open");
+        file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Drop for RollingFileWriter {
+    fn drop(&mut self) {
+        if let Some(maintenance) = self.maintenance.take() {
+            let _ = maintenance.join();
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Debug for RollingFileWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RollingFileWriter")
+            .field("dir", &self.dir)
+            .field("prefix", &self.prefix)
+            .field("rotation", &self.rotation)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+// Replace `path` by its compressed form, keeping the modification time
+#[cfg(feature = "trace")]
+fn compress_log_file(path: &Path, compression: Compression) -> AnyResult<()> {
+    let extension = match compression.extension() {
+        Some(extension) => extension,
+        None => return Ok(()),
+    };
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow!("invalid log file name {}", path.display()))?;
+    let target = path.with_file_name(format!("{}.{}", name, extension));
+    let modified = std::fs::metadata(path)?.modified()?;
+    let mut input = File::open(path)?;
+    let output = File::create(&target)?;
+
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.set_modified(modified)?;
+        }
+        #[cfg(feature = "compress")]
+        Compression::Zip => {
+            let mut archive = ::zip::ZipWriter::new(output);
+            let options = ::zip::write::SimpleFileOptions::default()
+                .compression_method(::zip::CompressionMethod::Deflated);
+            archive.start_file(name, options)?;
+            std::io::copy(&mut input, &mut archive)?;
+            archive.finish()?.set_modified(modified)?;
+        }
+        _ => return Err(anyhow!("unsupported log compression {:?}", compression)),
+    }
+    drop(input);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+// Whether `name` is a file created by RollingFileWriter for `prefix`
+#[cfg(feature = "trace")]
+fn is_rotated_log_name(name: &str, prefix: &str) -> bool {
+    let stem = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zip"))
+        .unwrap_or(name);
+    if stem == prefix {
+        return true;
+    }
+    match stem.strip_prefix(prefix).and_then(|r| r.strip_prefix('.')) {
+        Some(rest) => !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '.'),
+        None => false,
+    }
+}
+
+// Delete rotated files beyond `max_files` or older than `max_age`
+#[cfg(feature = "trace")]
+fn prune_log_files(
+    dir: &Path,
+    prefix: &str,
+    active: Option<&Path>,
+    max_files: Option<usize>,
+    max_age: Option<std::time::Duration>,
+) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| is_rotated_log_name(name, prefix))
+        })
+        .map(|entry| entry.path())
+        .filter(|path| Some(path.as_path()) != active)
+        .filter_map(|path| Some((std::fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    // Newest first
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let now = std::time::SystemTime::now();
+    for (i, (modified, path)) in files.iter().enumerate() {
+        let too_many = max_files.is_some_and(|max| i >= max);
+        let too_old = max_age.is_some_and(|age| {
+            now.duration_since(*modified).is_ok_and(|elapsed| elapsed > age)
+        });
+        if too_many || too_old {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
 
 /// Initialize tracing with a specific log file name
 /// 
-/// This macro sets up tracing with both stdout and file output, providing a 
-/// comprehensive logging solution for applications.
+/// This macro is a shorthand for `TracingConfig` with the settings below. Use the
+/// builder directly to change any of them.
 /// 
 /// # Arguments
 /// * `$e:expr` - The log file name (without extension)
 /// 
 /// # Features
-/// * Sets RUST_LOG environment variable to "poem=debug" if not already set
-/// * Creates daily rolling log files in the ./logs directory
+/// * Creates daily rolling log files in the `logs` directory next to the executable
 /// * Uses LocalTimeFormatter for timestamp formatting
 /// * Outputs to both stdout and file
 /// * Supports ANSI color codes in stdout
 /// * Configured for DEBUG level logging
+/// * Keeps the log writer alive until the end of the calling scope, then
+///   writes out all buffered lines
 /// 
 /// # Panics
-/// This function will panic if the executable directory cannot be determined
-/// or if the logs directory cannot be created.
+/// This macro will panic if the executable directory cannot be determined,
+/// the logs directory cannot be created, or a global subscriber is already set.
 /// 
 /// # Example
-/// ```rust
+/// ```no_run
+/// use acovo::init_tracing;
+///
 /// init_tracing!("my_app_log");
 /// ```
 #[cfg(feature = "trace")]
-#[macro_export]
-macro_rules! init_tracing {
-    ($e:expr) => {
-        // Set default log level if not already set
-        if std::env::var_os("RUST_LOG").is_none() {
-            std::env::set_var("RUST_LOG", "poem=debug");
-        }
-        
-        // Create log directory path
-        let log_path = format!("{}/logs", get_exe_dir().expect("Failed to determine executable directory"));
-        
-        // Create daily rolling file appender
-        let file_appender = tracing_appender::rolling::daily(&log_path, $e);
-        
-        // Configure formatting with local time
-        let format = tracing_subscriber::fmt::format()
-            .with_level(true)
-            .with_target(true)
-            .with_timer($crate::time::LocalTimeFormatter);
-            
-        // Create non-blocking writer for file output
-        let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-        
-        // Initialize tracing subscriber with both stdout and file writers
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .with_writer(std::io::stdout)
-            .with_writer(non_blocking)
-            .with_ansi(true)
-            .event_format(format)
-            .init();
+#[macro_export]
+macro_rules! init_tracing {
+    ($e:expr) => {
+        let _acovo_tracing_guard = $crate::trace::TracingConfig::new()
+            .level($crate::trace::__tracing::Level::DEBUG)
+            .file_prefix($e)
+            .init()
+            .expect("Failed to initialize tracing");
     };
 }
 
 /// Initialize global tracing with custom parameters
 /// 
-/// This macro sets up global tracing with more customization options, including
-/// background thread logging and panic hook integration.
+/// This macro sets up global tracing through `TracingConfig`, plus background
+/// thread logging and panic hook integration.
 /// 
 /// # Arguments
 /// * `$d:expr` - The directory for log files
 /// * `$f:expr` - The log file name prefix
-/// * `$w:expr` - Optional additional writer (currently commented out)
+/// * `$w:expr` - Optional additional writer (currently unused)
 /// 
 /// # Features
 /// * Creates a background thread for periodic logging
-/// * Sets up panic hook for capturing panic information
+/// * Installs a `CrashHandler` writing crash reports with the last 200 events into `$d`
 /// * Uses LocalTimeFormatter for timestamp formatting
 /// * Includes line numbers and thread IDs in log output
 /// * Supports TRACE level logging
 /// * Configured with ANSI color support
+/// * Flushes the log file after logging a panic and writes out the remaining
+///   lines at exit (Unix); elsewhere call `acovo::trace::shutdown()` before exiting
 /// 
 /// # Panics
-/// This function will panic if a global default subscriber has already been set.
+/// This macro will panic if a global default subscriber has already been set.
 /// 
 /// # Example
-/// ```rust
-/// init_global_tracing!("./logs", "my_app", None);
+/// ```no_run
+/// use acovo::init_global_tracing;
+///
+/// init_global_tracing!("./logs", "my_app", None::<std::io::Stdout>);
 /// ```
 #[cfg(feature = "trace")]
 #[macro_export]
 macro_rules! init_global_tracing {
     ($d:expr, $f:expr, $w:expr) => {
+        let log_dir: std::path::PathBuf = ($d).into();
+        $crate::trace::TracingConfig::new()
+            .level($crate::trace::__tracing::Level::TRACE)
+            .log_dir(log_dir.clone())
+            .file_prefix($f)
+            .line_numbers(true)
+            .thread_ids(true)
+            .recent_events(200)
+            .init_global()
+            .expect("Failed to set global default subscriber");
+
         // Spawn a background thread for periodic logging
-        thread::spawn(|| {
-            // Create daily rolling file appender with custom directory and file prefix
-            let log_appender = tracing_appender::rolling::daily($d, $f);
-            
-            // Configure formatting with additional metadata
-            let format = tracing_subscriber::fmt::format()
-                .with_level(true)
-                .with_target(true)
-                .with_line_number(true)
-                .with_thread_ids(true)
-                .with_timer($crate::time::LocalTimeFormatter);
-                
-            // Create non-blocking writer for file output
-            let (non_blocking, _guard) = tracing_appender::non_blocking(log_appender);
-            
-            // Build the logger with maximum verbosity
-            let logger_builder = tracing_subscriber::fmt()
-                .with_max_level(tracing::Level::TRACE)
-                .with_writer(std::io::stdout)
-                .with_writer(non_blocking)
-                .with_ansi(true)
-                .event_format(format)
-                .finish();
-            
-            // Set as global default subscriber
-            tracing::subscriber::set_global_default(logger_builder)
-                .expect("Failed to set global default subscriber");
-            
+        std::thread::spawn(|| {
             // Periodic logging loop (every 5 minutes)
             loop {
-                thread::sleep(time::Duration::from_secs(300));
-                info!("LogThread is running.");
+                std::thread::sleep(std::time::Duration::from_secs(300));
+                $crate::trace::__tracing::info!("LogThread is running.");
             }
         });
 
-        // Set up panic hook to capture panic information in logs
-        std::panic::set_hook(Box::new(|panic| {
-            if let Some(location) = panic.location() {
-                error!(
-                    message = %panic,
-                    panic.file = location.file(),
-                    panic.line = location.line(),
-                    panic.column = location.column(),
-                );
-            } else {
-                error!(message = %panic);
-            }
-        }));
+        // Write a crash report with the recent log for every panic
+        let mut crash_handler = $crate::trace::CrashHandler::new(log_dir);
+        if let Some(buffer) = $crate::trace::log_buffer() {
+            crash_handler = crash_handler.recent_logs(buffer);
+        }
+        crash_handler.install().expect("Failed to install crash handler");
     };
 }
 
@@ -153,6 +2747,7 @@ macro_rules! init_global_tracing {
 #[cfg(feature = "trace")]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::env;
 
     #[test]
@@ -232,4 +2827,1001 @@ mod tests {
         // Just test compilation, not execution
         // These tests ensure the macros accept the expected parameter types
     }
+
+    #[test]
+    fn test_tracing_config_defaults() {
+        let config = TracingConfig::new();
+        assert_eq!(config.level, "debug");
+        assert_eq!(config.rotation, Rotation::Daily);
+        assert!(config.stdout);
+        assert!(config.ansi);
+        assert!(config.file_prefix.is_none());
+
+        let filter = static_targets(&config.filter().unwrap().to_string());
+        assert!(filter.would_enable("anything", &Level::DEBUG));
+        assert!(!filter.would_enable("anything", &Level::TRACE));
+    }
+
+    #[test]
+    fn test_tracing_config_builder_filter() {
+        let config = TracingConfig::new()
+            .level(Level::INFO)
+            .directives("warn,my_app::db=trace")
+            .target("hyper", Level::ERROR);
+        let filter = static_targets(&config.filter().unwrap().to_string());
+
+        // A bare level in the directives replaces the default level
+        assert!(!filter.would_enable("my_app", &Level::INFO));
+        assert!(filter.would_enable("my_app", &Level::WARN));
+        assert!(filter.would_enable("my_app::db::pool", &Level::TRACE));
+        assert!(!filter.would_enable("hyper::client", &Level::WARN));
+        assert!(filter.would_enable("hyper::client", &Level::ERROR));
+    }
+
+    #[test]
+    fn test_tracing_config_invalid_values() {
+        let mut config = TracingConfig::new();
+        config.level = "loud".to_string();
+        assert!(config.filter().is_err());
+
+        let config = TracingConfig::new().directives("my_app=shouting");
+        assert!(config.filter().is_err());
+
+        let config = TracingConfig::new().directives("my_app[request{user=");
+        assert!(config.filter().is_err());
+
+        assert!("weekly".parse::<Rotation>().is_err());
+        assert_eq!("Hourly".parse::<Rotation>().unwrap(), Rotation::Hourly);
+    }
+
+    #[test]
+    fn test_tracing_config_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tracing.json");
+        std::fs::write(
+            &path,
+            r#"{ "level": "info", "targets": { "hyper": "warn" }, "file_prefix": "svc", "rotation": "hourly", "ansi": false }"#,
+        )
+        .unwrap();
+
+        let config = TracingConfig::from_file(&path).unwrap();
+        assert_eq!(config.level, "info");
+        assert_eq!(config.targets.get("hyper").map(String::as_str), Some("warn"));
+        assert_eq!(config.file_prefix.as_deref(), Some("svc"));
+        assert_eq!(config.rotation, Rotation::Hourly);
+        assert!(!config.ansi);
+        // Keys missing from the file keep their defaults
+        assert!(config.stdout);
+
+        std::fs::write(&path, r#"{ "level": "chatty" }"#).unwrap();
+        assert!(TracingConfig::from_file(&path).is_err());
+        assert!(TracingConfig::from_file(dir.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_tracing_config_with_env() {
+        let mut vars = HashMap::new();
+        vars.insert(ENV_LOG_LEVEL, "warn");
+        vars.insert(ENV_LOG_ROTATION, "never");
+        vars.insert(ENV_LOG_THREAD_IDS, "yes");
+        vars.insert(ENV_LOG_FILE, " ");
+        let lookup = |vars: &HashMap<&str, &str>, name: &str| vars.get(name).map(|v| v.to_string());
+        let config = TracingConfig::new().file_prefix("app").with_env_from(|name| lookup(&vars, name));
+        vars.insert(ENV_LOG_THREAD_IDS, "maybe");
+        let invalid = TracingConfig::new().with_env_from(|name| lookup(&vars, name));
+
+        let config = config.unwrap();
+        assert_eq!(config.level, "warn");
+        assert_eq!(config.rotation, Rotation::Never);
+        assert!(config.thread_ids);
+        assert_eq!(config.file_prefix.as_deref(), Some("app"));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_tracing_config_span_field_directives() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TracingConfig::new()
+            .level(Level::INFO)
+            .directives("acovo[request{user=alice}]=trace")
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("spans")
+            .rotation(Rotation::Never);
+        let (subscriber, guard) = config.build().unwrap();
+        let filter = guard.filter_handle();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", user = "alice").in_scope(|| tracing::trace!("traced for alice"));
+            tracing::info_span!("request", user = "bob").in_scope(|| tracing::trace!("traced for bob"));
+
+            // Outside the span only the default level applies
+            assert!(filter.would_enable("acovo", &Level::INFO));
+            assert!(!filter.would_enable("acovo", &Level::TRACE));
+        });
+        drop(guard);
+
+        let text = std::fs::read_to_string(dir.path().join("spans")).unwrap();
+        assert!(text.contains("traced for alice"));
+        assert!(!text.contains("traced for bob"));
+    }
+
+    #[test]
+    fn test_tracing_config_build_writes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("build")
+            .rotation(Rotation::Never);
+        let (subscriber, guard) = config.build().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            info!(answer = 42, "configured subscriber");
+            tracing::trace!("filtered out");
+        });
+        drop(guard);
+
+        let text = std::fs::read_to_string(dir.path().join("build")).unwrap();
+        assert!(text.contains("configured subscriber"));
+        assert!(text.contains("answer=42"));
+        assert!(!text.contains("filtered out"));
+    }
+
+    #[test]
+    fn test_tracing_guard_flush_reaches_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("flush");
+        let (subscriber, guard) = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("flush")
+            .rotation(Rotation::Never)
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..2000 {
+                info!(line = i, "queued");
+            }
+            info!("last line before flush");
+            guard.flush();
+            // flush returns only once the queue is written, so no polling is needed
+            let text = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(text.lines().count(), 2001);
+            assert!(text.lines().last().unwrap().contains("last line before flush"));
+
+            info!("last line before drop");
+        });
+        drop(guard);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.lines().last().unwrap().contains("last line before drop"));
+    }
+
+    #[test]
+    fn test_tracing_guard_shutdown_discards_later_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shutdown");
+        let (subscriber, guard) = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("shutdown")
+            .rotation(Rotation::Never)
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("before shutdown");
+            guard.shutdown();
+            // Logging after shutdown must neither block nor fail
+            info!("after shutdown");
+        });
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("before shutdown"));
+        assert!(!text.contains("after shutdown"));
+    }
+
+    #[test]
+    fn test_global_flush_without_guard_is_noop() {
+        // No test installs a global guard, so these must return immediately
+        assert!(!flush());
+        shutdown();
+    }
+
+    // Writer that blocks until released, standing in for a stalled disk
+    struct StalledWriter {
+        release: mpsc::Receiver<()>,
+        lines: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl Write for StalledWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.release.recv();
+            self.lines.lock().unwrap().push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stalled_writer_drops_lines_and_flush_times_out() {
+        let (release, stalled) = mpsc::channel();
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let (writer, worker) = spawn_writer_with_queue(
+            StalledWriter {
+                release: stalled,
+                lines: lines.clone(),
+            },
+            2,
+        )
+        .unwrap();
+        let guard = TracingGuard {
+            workers: vec![worker],
+            filter: TracingConfig::new().build().unwrap().1.filter_handle(),
+            buffer: None,
+            log_path: None,
+        };
+
+        // One line is held by the blocked writer and two are queued; the rest are dropped
+        let started = Instant::now();
+        for i in 0..10 {
+            writer.make_writer().write_all(format!("line {}\n", i).as_bytes()).unwrap();
+            if i == 0 {
+                thread::sleep(time::Duration::from_millis(50));
+            }
+        }
+        assert!(started.elapsed() < time::Duration::from_secs(1));
+        assert_eq!(guard.dropped_lines(), 7);
+        assert!(!guard.flush_timeout(time::Duration::from_millis(100)));
+
+        for _ in 0..3 {
+            release.send(()).unwrap();
+        }
+        assert!(guard.flush());
+        assert_eq!(lines.lock().unwrap().len(), 3);
+        drop(release);
+    }
+
+    // Writer that flushes a guard from inside the writer thread
+    struct FlushingWriter {
+        guard: Arc<TracingGuard>,
+        result: mpsc::Sender<bool>,
+    }
+
+    impl Write for FlushingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.result.send(self.guard.flush_timeout(time::Duration::from_secs(60)));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush_on_writer_thread_returns_immediately() {
+        let (_, guard) = TracingConfig::new().stdout(false).build().unwrap();
+        let (result, flushed) = mpsc::channel();
+        let (writer, _worker) = spawn_writer(FlushingWriter {
+            guard: Arc::new(guard),
+            result,
+        })
+        .unwrap();
+        writer.make_writer().write_all(b"line\n").unwrap();
+        assert_eq!(flushed.recv_timeout(time::Duration::from_secs(5)), Ok(false));
+    }
+
+    thread_local! {
+        static TEST_NOW: std::cell::Cell<(i32, u32, u32)> = const { std::cell::Cell::new((2024, 1, 1)) };
+    }
+
+    // Noon of the day set in TEST_NOW
+    fn test_clock() -> DateTime<Local> {
+        use chrono::TimeZone;
+        let (y, m, d) = TEST_NOW.with(|now| now.get());
+        Local.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap()
+    }
+
+    fn sorted_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("64KB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("20 MiB").unwrap(), 20 * 1024 * 1024);
+        assert_eq!(parse_size("1g").unwrap(), 1 << 30);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("10 bananas").is_err());
+        assert!(parse_size("99999999999999999999G").is_err());
+    }
+
+    #[test]
+    fn test_rolling_writer_size_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RollingFileWriter::new(dir.path(), "size", Rotation::Never)
+            .unwrap()
+            .with_max_size(100);
+        for i in 0..10 {
+            writer.write_all(format!("line {:02} padded to thirty bytes\n", i).as_bytes()).unwrap();
+        }
+        assert_eq!(writer.current_path(), Some(dir.path().join("size.3").as_path()));
+        drop(writer);
+
+        assert_eq!(sorted_names(dir.path()), vec!["size", "size.1", "size.2", "size.3"]);
+        let mut all = String::new();
+        for name in ["size", "size.1", "size.2", "size.3"] {
+            let text = std::fs::read_to_string(dir.path().join(name)).unwrap();
+            assert!(text.len() <= 100);
+            all.push_str(&text);
+        }
+        // Lines are never split and keep their order across files
+        let numbers: Vec<&str> = all.lines().map(|l| &l[5..7]).collect();
+        assert_eq!(numbers, vec!["00", "01", "02", "03", "04", "05", "06", "07", "08", "09"]);
+    }
+
+    #[test]
+    fn test_rolling_writer_hybrid_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        TEST_NOW.with(|now| now.set((2024, 1, 1)));
+        let mut writer = RollingFileWriter::new(dir.path(), "app", Rotation::Daily)
+            .unwrap()
+            .with_max_size(20);
+        writer.clock = test_clock;
+
+        writer.write_all(b"first day\n").unwrap();
+        TEST_NOW.with(|now| now.set((2024, 1, 2)));
+        writer.write_all(b"second day, line 1\n").unwrap();
+        writer.write_all(b"second day, line 2\n").unwrap();
+        drop(writer);
+
+        assert_eq!(sorted_names(dir.path()), vec!["app.2024-01-01", "app.2024-01-02", "app.2024-01-02.1"]);
+        assert_eq!(std::fs::read_to_string(dir.path().join("app.2024-01-01")).unwrap(), "first day\n");
+
+        // A restart appends to the latest segment of the day while it has room
+        let mut writer = RollingFileWriter::new(dir.path(), "app", Rotation::Daily).unwrap();
+        writer.clock = test_clock;
+        writer.write_all(b"restarted\n").unwrap();
+        assert_eq!(writer.current_path(), Some(dir.path().join("app.2024-01-02.1").as_path()));
+    }
+
+    #[test]
+    fn test_rolling_writer_gzip_and_max_files() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("gz.json"), "{}").unwrap();
+        let mut writer = RollingFileWriter::new(dir.path(), "gz", Rotation::Never)
+            .unwrap()
+            .with_max_size(10)
+            .with_compression(Compression::Gzip)
+            .unwrap()
+            .with_max_files(2);
+        for i in 0..5 {
+            writer.write_all(format!("segment {}\n", i).as_bytes()).unwrap();
+        }
+        // Dropping the writer waits for the background compression
+        drop(writer);
+
+        // Four rotated files were compressed, the two newest were kept
+        assert_eq!(sorted_names(dir.path()), vec!["gz.2.gz", "gz.3.gz", "gz.4", "gz.json"]);
+        assert!(is_rotated_log_name("gz.gz", "gz"));
+        assert!(!is_rotated_log_name("gz.json", "gz"));
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(File::open(dir.path().join("gz.3.gz")).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "segment 3\n");
+    }
+
+    #[test]
+    #[cfg(feature = "compress")]
+    fn test_rolling_writer_zip_compression() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RollingFileWriter::new(dir.path(), "zipped", Rotation::Never)
+            .unwrap()
+            .with_max_size(10)
+            .with_compression(Compression::Zip)
+            .unwrap();
+        writer.write_all(b"first file\n").unwrap();
+        writer.write_all(b"second file\n").unwrap();
+        drop(writer);
+
+        assert_eq!(sorted_names(dir.path()), vec!["zipped.1", "zipped.zip"]);
+        let mut archive = ::zip::ZipArchive::new(File::open(dir.path().join("zipped.zip")).unwrap()).unwrap();
+        let mut text = String::new();
+        archive.by_name("zipped").unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "first file\n");
+    }
+
+    #[test]
+    fn test_rolling_writer_max_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("aged.2020-01-01");
+        std::fs::write(&old, "old\n").unwrap();
+        let month_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(30 * 86400);
+        File::options().write(true).open(&old).unwrap().set_modified(month_ago).unwrap();
+
+        let mut writer = RollingFileWriter::new(dir.path(), "aged", Rotation::Never)
+            .unwrap()
+            .with_max_size(5)
+            .with_max_age(std::time::Duration::from_secs(7 * 86400));
+        writer.write_all(b"one\n").unwrap();
+        writer.write_all(b"two\n").unwrap();
+        drop(writer);
+
+        assert_eq!(sorted_names(dir.path()), vec!["aged", "aged.1"]);
+    }
+
+    #[test]
+    fn test_rolling_writer_prunes_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let month_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(30 * 86400);
+        for name in ["kept.1", "kept.2", "kept.3"] {
+            std::fs::write(dir.path().join(name), "old\n").unwrap();
+        }
+        File::options().write(true).open(dir.path().join("kept.1")).unwrap().set_modified(month_ago).unwrap();
+
+        // The first write opens `kept.3` and prunes without any rotation
+        let mut writer = RollingFileWriter::new(dir.path(), "kept", Rotation::Never)
+            .unwrap()
+            .with_max_files(1)
+            .with_max_age(std::time::Duration::from_secs(7 * 86400));
+        writer.write_all(b"new\n").unwrap();
+        drop(writer);
+
+        assert_eq!(sorted_names(dir.path()), vec!["kept.2", "kept.3"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_rolling_writer_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RollingFileWriter::new(dir.path(), "linked", Rotation::Never)
+            .unwrap()
+            .with_max_size(5)
+            .with_symlink(true);
+        writer.write_all(b"one\n").unwrap();
+        writer.write_all(b"two\n").unwrap();
+
+        let link = dir.path().join("linked.current");
+        assert_eq!(std::fs::read_link(&link).unwrap(), PathBuf::from("linked.1"));
+        assert_eq!(std::fs::read_to_string(&link).unwrap(), "two\n");
+    }
+
+    #[test]
+    fn test_tracing_config_rotation_settings() {
+        let config: TracingConfig = serde_json::from_str(
+            r#"{ "rotation": "daily", "max_file_size": 1048576, "compression": "gzip", "max_files": 7, "max_age": "14d" }"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_file_size, Some(1 << 20));
+        assert_eq!(config.compression, Compression::Gzip);
+        assert_eq!(config.max_files, Some(7));
+        assert_eq!(
+            config.max_age_duration().unwrap(),
+            Some(std::time::Duration::from_secs(14 * 86400))
+        );
+
+        // Sizes may also be given with a unit
+        let config: TracingConfig = serde_json::from_str(r#"{ "max_file_size": "100MB" }"#).unwrap();
+        assert_eq!(config.max_file_size, Some(100 << 20));
+        assert!(serde_json::from_str::<TracingConfig>(r#"{ "max_file_size": "100 parsecs" }"#).is_err());
+
+        let config = TracingConfig::new().max_age(std::time::Duration::from_secs(3 * 3600));
+        assert_eq!(config.max_age.as_deref(), Some("3h"));
+
+        let mut config = TracingConfig::new();
+        config.max_age = Some("forever".to_string());
+        assert!(config.check().is_err());
+    }
+
+    #[test]
+    fn test_json_file_output_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let (subscriber, guard) = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("json")
+            .rotation(Rotation::Never)
+            .file_format(LogFormat::Json)
+            .line_numbers(true)
+            .thread_ids(true)
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let outer = tracing::info_span!("request", id = 7, path = "/users");
+            let _outer = outer.enter();
+            let inner = tracing::debug_span!("db", table = tracing::field::Empty);
+            inner.record("table", "users");
+            let _inner = inner.enter();
+            tracing::warn!(rows = 3, cached = false, ratio = 0.5, "slow query");
+        });
+        guard.flush();
+
+        let text = std::fs::read_to_string(dir.path().join("json")).unwrap();
+        let line = text.lines().next().unwrap();
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        // Keys are written in schema order
+        let keys = ["timestamp", "level", "target", "file", "line", "thread_id", "thread_name", "message", "spans", "fields"];
+        let positions: Vec<usize> = keys.iter().map(|k| line.find(&format!("\"{}\":", k)).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(record.as_object().unwrap().len(), keys.len());
+        assert_eq!(record["level"], "WARN");
+        assert_eq!(record["target"], "acovo::trace::tests");
+        assert_eq!(record["message"], "slow query");
+        assert!(record["file"].as_str().unwrap().ends_with("trace.rs"));
+        assert!(record["line"].as_u64().unwrap() > 0);
+        assert_eq!(record["timestamp"].as_str().unwrap().len(), "2024-01-31T12:00:00.000".len());
+        assert_eq!(record["fields"], serde_json::json!({ "rows": 3, "cached": false, "ratio": 0.5 }));
+        assert_eq!(
+            record["spans"],
+            serde_json::json!([
+                { "name": "request", "fields": { "id": 7, "path": "/users" } },
+                { "name": "db", "fields": { "table": "users" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_json_output_honors_line_numbers_and_thread_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let (subscriber, guard) = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("plain")
+            .rotation(Rotation::Never)
+            .file_format(LogFormat::Json)
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || info!("no location"));
+        guard.flush();
+
+        let text = std::fs::read_to_string(dir.path().join("plain")).unwrap();
+        let record: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        for key in ["file", "line", "thread_id"] {
+            assert!(record.get(key).is_none(), "{} should be omitted", key);
+        }
+
+        // Thread numbers are stable per thread and distinct between threads
+        let here = current_thread_number();
+        assert_eq!(current_thread_number(), here);
+        let other = thread::spawn(current_thread_number).join().unwrap();
+        assert_ne!(other, here);
+        assert!(here > 0 && other > 0);
+    }
+
+    #[test]
+    fn test_log_format_selection() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+
+        let config: TracingConfig =
+            serde_json::from_str(r#"{ "stdout_format": "text", "file_format": "json" }"#).unwrap();
+        assert_eq!(config.stdout_format, LogFormat::Text);
+        assert_eq!(config.file_format, LogFormat::Json);
+
+        // Human text on one output and JSON on the other
+        let dir = tempfile::tempdir().unwrap();
+        let (subscriber, guard) = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("text")
+            .rotation(Rotation::Never)
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || info!(n = 1, "plain"));
+        drop(guard);
+        let text = std::fs::read_to_string(dir.path().join("text")).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(text.trim()).is_err());
+        assert!(text.contains("plain n=1"));
+    }
+
+    // Poll `condition` for up to five seconds
+    fn wait_until(condition: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + std::time::Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(std::time::Duration::from_millis(20));
+        }
+        condition()
+    }
+
+    #[test]
+    fn test_filter_handle_runtime_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (subscriber, guard) = TracingConfig::new()
+            .level(Level::INFO)
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("reload")
+            .rotation(Rotation::Never)
+            .build()
+            .unwrap();
+        let filter = guard.filter_handle();
+        let dispatch = tracing::Dispatch::new(subscriber);
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            tracing::debug!("hidden before reload");
+            filter.set_level(Level::DEBUG).unwrap();
+            tracing::debug!("shown after reload");
+            filter.set_target("acovo::trace", Level::WARN).unwrap();
+            info!("hidden by target");
+            filter.clear_target("acovo::trace").unwrap();
+            info!("shown after clear");
+        });
+        guard.flush();
+
+        let text = std::fs::read_to_string(dir.path().join("reload")).unwrap();
+        assert!(!text.contains("hidden"));
+        assert!(text.contains("shown after reload"));
+        assert!(text.contains("shown after clear"));
+
+        // Invalid directives leave the filter as it was
+        assert!(filter.set_directives("acovo=loud").is_err());
+        assert!(filter.would_enable("anything", &Level::DEBUG));
+
+        filter.set_directives("warn,my_app::db=trace").unwrap();
+        assert!(filter.would_enable("my_app::db", &Level::TRACE));
+        assert!(!filter.would_enable("my_app", &Level::INFO));
+
+        filter.reset().unwrap();
+        assert!(filter.would_enable("my_app", &Level::INFO));
+        assert!(!filter.would_enable("my_app::db", &Level::DEBUG));
+    }
+
+    #[test]
+    fn test_filter_handle_escalation_reverts() {
+        let (_subscriber, guard) = TracingConfig::new().level(Level::INFO).stdout(false).build().unwrap();
+        let filter = guard.filter_handle();
+
+        filter
+            .escalate("my_app::net", Level::TRACE, std::time::Duration::from_millis(300))
+            .unwrap();
+        assert!(filter.would_enable("my_app::net::ping", &Level::TRACE));
+        assert!(!filter.would_enable("my_app::db", &Level::DEBUG));
+
+        // Escalations outlive ordinary changes until they expire
+        filter.set_level(Level::WARN).unwrap();
+        assert!(filter.would_enable("my_app::net", &Level::TRACE));
+
+        assert!(wait_until(|| !filter.would_enable("my_app::net", &Level::TRACE)));
+        assert!(filter.would_enable("my_app::net", &Level::WARN));
+        assert!(!filter.would_enable("my_app::net", &Level::INFO));
+    }
+
+    #[test]
+    fn test_filter_handle_escalation_replaced() {
+        let (_subscriber, guard) = TracingConfig::new().level(Level::INFO).stdout(false).build().unwrap();
+        let filter = guard.filter_handle();
+
+        // The short escalation is replaced, so its expiry must not end the long one
+        let short = std::time::Duration::from_millis(100);
+        filter.escalate("my_app::net", Level::TRACE, short).unwrap();
+        filter
+            .escalate("my_app::net", Level::DEBUG, std::time::Duration::from_secs(60))
+            .unwrap();
+        filter.escalate("my_app::db", Level::TRACE, short).unwrap();
+
+        assert!(wait_until(|| !filter.would_enable("my_app::db", &Level::TRACE)));
+        assert!(filter.would_enable("my_app::net", &Level::DEBUG));
+        assert!(!filter.would_enable("my_app::net", &Level::TRACE));
+        assert!(filter.inner.expiry_started.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_filter_handle_watch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("filter.json");
+        std::fs::write(&path, r#"{ "level": "info" }"#).unwrap();
+        let (_subscriber, guard) = TracingConfig::from_file(&path).unwrap().stdout(false).build().unwrap();
+        let filter = guard.filter_handle();
+        filter.watch_file(&path, std::time::Duration::from_millis(20)).unwrap();
+
+        std::fs::write(&path, r#"{ "level": "info", "targets": { "my_app::db": "trace" } }"#).unwrap();
+        // Make the change visible even on filesystems with coarse timestamps
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+        assert!(wait_until(|| filter.would_enable("my_app::db", &Level::TRACE)));
+        assert!(!filter.would_enable("my_app", &Level::DEBUG));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_filter_handle_reload_on_sighup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sighup.json");
+        std::fs::write(&path, r#"{ "level": "error" }"#).unwrap();
+        let (_subscriber, guard) = TracingConfig::from_file(&path).unwrap().stdout(false).build().unwrap();
+        let filter = guard.filter_handle();
+        filter.reload_on_sighup(&path).unwrap();
+
+        std::fs::write(&path, r#"{ "level": "debug" }"#).unwrap();
+        assert!(!filter.would_enable("my_app", &Level::DEBUG));
+        unsafe {
+            libc::raise(libc::SIGHUP);
+        }
+        assert!(wait_until(|| filter.would_enable("my_app", &Level::DEBUG)));
+    }
+
+    #[test]
+    fn test_log_buffer_keeps_latest_events() {
+        let (subscriber, guard) = TracingConfig::new().stdout(false).recent_events(3).build().unwrap();
+        let buffer = guard.log_buffer().unwrap();
+        assert_eq!(buffer.capacity(), 3);
+        assert!(buffer.is_empty());
+
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..5 {
+                info!(n = i, user = "alice", "event");
+            }
+            tracing::trace!("filtered out");
+        });
+
+        let records = buffer.snapshot();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].fields["n"], 2);
+        assert_eq!(records[2].fields["n"], 4);
+        assert_eq!(records[2].level, Level::INFO);
+        assert_eq!(records[2].message, "event");
+        assert!(records[2].to_string().ends_with(" INFO acovo::trace::tests: event n=4 user=alice"));
+
+        buffer.clear();
+        assert_eq!(buffer.len(), 0);
+        assert!(TracingConfig::new().build().unwrap().1.log_buffer().is_none());
+    }
+
+    #[test]
+    fn test_crash_handler_write_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let buffer = LogBuffer::new(10);
+        let subscriber = tracing_subscriber::registry().with(buffer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(attempt = 3, "connection lost");
+        });
+
+        let handler = CrashHandler::new(dir.path())
+            .file_prefix("fatal")
+            .build_info("version", "9.9.9")
+            .recent_logs(buffer);
+        let path = handler
+            .write_report("database unreachable", Some(std::panic::Location::caller()))
+            .unwrap();
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("fatal-"));
+        assert!(name.ends_with(&format!("-{}.txt", std::process::id())));
+        let report = std::fs::read_to_string(&path).unwrap();
+        assert!(report.contains("message: database unreachable"));
+        assert!(report.lines().any(|line| line.starts_with("location: ") && line.contains("trace.rs:")));
+        assert!(report.contains("version: 9.9.9"));
+        assert!(report.contains(&format!("pid: {}", std::process::id())));
+        assert!(report.contains("--- Backtrace ---"));
+        assert!(report.contains("--- Recent log (1 events) ---"));
+        assert!(report.contains("connection lost attempt=3"));
+    }
+
+    // Report directory passed to the child process of test_crash_handler_panic_hook
+    const CRASH_TEST_DIR_ENV: &str = "ACOVO_CRASH_TEST_DIR";
+
+    // Runs only as the child process of test_crash_handler_panic_hook, since the panic
+    // hook is process-wide and would catch the panics of tests running in parallel
+    #[test]
+    fn test_crash_handler_panic_hook_child() {
+        let Some(dir) = std::env::var_os(CRASH_TEST_DIR_ENV) else { return };
+        CrashHandler::new(dir).chain_previous(true).install().unwrap();
+        let result = thread::Builder::new()
+            .name("crasher".to_string())
+            .spawn(|| panic!("crash handler test panic"))
+            .unwrap()
+            .join();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_crash_handler_panic_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "trace::tests::test_crash_handler_panic_hook_child", "--test-threads=1"])
+            .env(CRASH_TEST_DIR_ENV, dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+        let reports: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].contains("message: crash handler test panic"));
+        assert!(reports[0].contains("thread: crasher"));
+        assert!(reports[0].contains("uptime: "));
+    }
+
+    const EXIT_TEST_DIR_ENV: &str = "ACOVO_EXIT_TEST_DIR";
+
+    // Runs only as the child process of test_global_guard_flushed_at_exit, since it
+    // installs the global subscriber and relies on the process exiting afterwards
+    #[test]
+    fn test_global_guard_flushed_at_exit_child() {
+        let Some(dir) = std::env::var_os(EXIT_TEST_DIR_ENV) else { return };
+        TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir)
+            .file_prefix("exit")
+            .rotation(Rotation::Never)
+            .init_global()
+            .unwrap();
+        for i in 0..50_000 {
+            info!(n = i, "queued line");
+        }
+        // Exit while the writer thread is still behind
+        std::process::exit(0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_global_guard_flushed_at_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "trace::tests::test_global_guard_flushed_at_exit_child", "--test-threads=1"])
+            .env(EXIT_TEST_DIR_ENV, dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+        let text = std::fs::read_to_string(dir.path().join("exit")).unwrap();
+        assert_eq!(text.lines().filter(|line| line.contains("queued line")).count(), 50_000);
+    }
+
+    fn record_at(seconds_ago: i64, level: Level, target: &str, message: &str, user: &str) -> LogRecord {
+        let mut fields = serde_json::Map::new();
+        fields.insert("user".to_string(), user.into());
+        fields.insert("attempt".to_string(), 2.into());
+        LogRecord {
+            timestamp: Local::now() - chrono::Duration::seconds(seconds_ago),
+            level,
+            target: target.to_string(),
+            message: message.to_string(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_log_query_matching() {
+        let buffer = LogBuffer::new(100);
+        buffer.push(record_at(600, Level::ERROR, "my_app::db", "pool exhausted", "alice"));
+        buffer.push(record_at(30, Level::INFO, "my_app::db::pool", "connected", "bob"));
+        buffer.push(record_at(20, Level::WARN, "my_app::dbx", "slow", "alice"));
+        buffer.push(record_at(10, Level::DEBUG, "hyper", "request sent", "alice"));
+
+        let messages = |query: LogQuery| -> Vec<String> {
+            buffer.query(&query).into_iter().map(|r| r.message).collect()
+        };
+        assert_eq!(messages(LogQuery::new()).len(), 4);
+        assert_eq!(messages(LogQuery::new().level(Level::WARN)), vec!["pool exhausted", "slow"]);
+        // Targets match whole path segments
+        assert_eq!(messages(LogQuery::new().target("my_app::db")), vec!["pool exhausted", "connected"]);
+        assert_eq!(messages(LogQuery::new().target("hyper").target("my_app::dbx")), vec!["slow", "request sent"]);
+        assert_eq!(messages(LogQuery::new().field_eq("user", "bob")), vec!["connected"]);
+        assert_eq!(messages(LogQuery::new().field_eq("attempt", "2")).len(), 4);
+        assert!(messages(LogQuery::new().field("missing")).is_empty());
+        assert_eq!(messages(LogQuery::new().last(std::time::Duration::from_secs(60))).len(), 3);
+
+        // The window of `last` moves with the clock
+        let recent = LogQuery::new().last(std::time::Duration::from_millis(200));
+        let record = record_at(0, Level::INFO, "my_app", "fresh", "alice");
+        assert!(recent.matches(&record));
+        thread::sleep(std::time::Duration::from_millis(300));
+        assert!(!recent.matches(&record));
+        assert_eq!(messages(LogQuery::new().contains("pool")), vec!["pool exhausted"]);
+        assert_eq!(messages(LogQuery::new().field_eq("user", "alice").limit(2)), vec!["slow", "request sent"]);
+    }
+
+    #[test]
+    fn test_log_buffer_max_age_and_filter() {
+        let buffer = LogBuffer::new(100).with_max_age(std::time::Duration::from_secs(60));
+        buffer.push(record_at(120, Level::INFO, "my_app", "too old", "alice"));
+        buffer.push(record_at(5, Level::INFO, "my_app", "recent", "alice"));
+        let messages: Vec<String> = buffer.snapshot().into_iter().map(|r| r.message).collect();
+        assert_eq!(messages, vec!["recent"]);
+
+        let buffer = LogBuffer::new(100).with_query(LogQuery::new().level(Level::WARN));
+        buffer.push(record_at(0, Level::INFO, "my_app", "ignored", "alice"));
+        buffer.push(record_at(0, Level::ERROR, "my_app", "kept", "alice"));
+        assert_eq!(buffer.len(), 1);
+
+        let config = TracingConfig::new().stdout(false).recent_max_age(std::time::Duration::from_secs(900));
+        assert_eq!(config.recent_max_age.as_deref(), Some("15m"));
+        let buffer = config.build().unwrap().1.log_buffer().unwrap();
+        assert_eq!(buffer.capacity(), DEFAULT_BUFFER_EVENTS);
+    }
+
+    #[test]
+    fn test_log_buffer_subscribe() {
+        let buffer = LogBuffer::new(10);
+        let errors = buffer.subscribe(LogQuery::new().level(Level::ERROR));
+        let everything = buffer.subscribe(LogQuery::new());
+
+        let subscriber = tracing_subscriber::registry().with(buffer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            info!("starting");
+            error!(code = 7, "failed");
+        });
+
+        let received: Vec<String> = errors.try_iter().map(|r| r.message).collect();
+        assert_eq!(received, vec!["failed"]);
+        assert_eq!(everything.try_iter().count(), 2);
+
+        // Dropped receivers are removed on the next event
+        drop(errors);
+        drop(everything);
+        buffer.push(record_at(0, Level::ERROR, "my_app", "after drop", "alice"));
+        assert!(buffer.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_log_record_to_json() {
+        let record = record_at(0, Level::WARN, "my_app", "slow", "alice");
+        let json = record.to_json();
+        assert_eq!(json["level"], "WARN");
+        assert_eq!(json["target"], "my_app");
+        assert_eq!(json["message"], "slow");
+        assert_eq!(json["fields"]["user"], "alice");
+        assert!(DateTime::parse_from_str(json["timestamp"].as_str().unwrap(), "%FT%T%.3f%:z").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tracing_config_syslog_and_journald() {
+        let dir = tempfile::tempdir().unwrap();
+        let syslog_path = dir.path().join("log.sock");
+        let journal_path = dir.path().join("journal.sock");
+        let syslog_server = std::os::unix::net::UnixDatagram::bind(&syslog_path).unwrap();
+        let journal_server = std::os::unix::net::UnixDatagram::bind(&journal_path).unwrap();
+
+        let config_path = dir.path().join("tracing.json");
+        let config = serde_json::json!({
+            "level": "info",
+            "stdout": false,
+            "syslog": { "facility": "daemon", "format": "rfc5424", "socket": syslog_path },
+            "journald": { "identifier": "svc", "socket": journal_path, "severities": { "info": "notice" } },
+        });
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        let config = TracingConfig::from_file(&config_path).unwrap();
+        assert_eq!(config.syslog.as_ref().unwrap().facility, sink::Facility::Daemon);
+
+        let (subscriber, _guard) = config.build().unwrap();
+        tracing::subscriber::with_default(subscriber, || info!("to both sinks"));
+
+        let mut buf = [0u8; 4096];
+        let len = syslog_server.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(message.starts_with("<30>1 "), "{}", message);
+        assert!(message.ends_with(": to both sinks"));
+
+        let len = journal_server.recv(&mut buf).unwrap();
+        let entry = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(entry.starts_with("PRIORITY=5\n"));
+        assert!(entry.contains("SYSLOG_IDENTIFIER=svc\n"));
+
+        let missing = TracingConfig::new().stdout(false).syslog(sink::SyslogConfig::new().socket(dir.path().join("none")));
+        assert!(missing.build().is_err());
+    }
 }
@@ -0,0 +1,713 @@
+//! Reading log files written by `TracingConfig`
+//!
+//! `LogReader` walks the rotated files of one log prefix in time order,
+//! including gzip and zip compressed ones, and parses each line back into a
+//! `LogEntry`. Both the text format (`LocalTimeFormatter` timestamp, level,
+//! optional thread id, spans, target, optional file and line, message and
+//! fields) and the JSON format are understood. Entries are selected with the
+//! same `LogQuery` used for in-memory logs.
+//!
+//! ```no_run
+//! use acovo::trace::reader::LogReader;
+//! use acovo::trace::LogQuery;
+//! use std::time::Duration;
+//! use tracing::Level;
+//!
+//! let reader = LogReader::new("./logs", "my_app");
+//! let query = LogQuery::new()
+//!     .level(Level::WARN)
+//!     .target("my_app::db")
+//!     .matching(regex::Regex::new("timeout|refused")?);
+//! for entry in reader.entries(&query)? {
+//!     println!("{}", entry?);
+//! }
+//! for (start, count) in reader.error_counts(Duration::from_secs(3600))? {
+//!     println!("{} {}", start.format("%F %H:%M"), count);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result as AnyResult};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use tracing::Level;
+
+use super::{is_rotated_log_name, LogQuery, LogRecord, TracingConfig};
+
+/// One event read back from a log file
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Timestamp, level, target, message and fields
+    pub record: LogRecord,
+    /// Numeric id of the logging thread, when thread ids were enabled
+    pub thread_id: Option<u64>,
+    /// Enclosing spans, outermost first, e.g. `request{id=7}`
+    pub spans: Vec<String>,
+    /// Source file, when line numbers were enabled
+    pub file: Option<String>,
+    /// Source line, when line numbers were enabled
+    pub line: Option<u32>,
+    /// Log file the entry was read from
+    pub source: PathBuf,
+}
+
+#[cfg(feature = "trace")]
+impl LogEntry {
+    /// Parse one log line in the text or JSON format
+    ///
+    /// Trailing `name=value` words of a text line are taken as fields, so a
+    /// message that itself ends in such words is split the same way.
+    ///
+    /// # Returns
+    /// * `None` if the line does not start with a timestamp and level, e.g. the
+    ///   continuation of a multi-line message
+    pub fn parse(line: &str) -> Option<LogEntry> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.starts_with('{') {
+            return parse_json_line(line);
+        }
+        let (mut entry, tail) = parse_text_prefix(line)?;
+        set_message(&mut entry, &tail);
+        Some(entry)
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.record.fmt(f)
+    }
+}
+
+// Parse "2024-01-31T12:00:00.123", optionally with a UTC offset
+#[cfg(feature = "trace")]
+fn parse_timestamp(text: &str) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+// "src/net.rs:42" as written with line numbers enabled
+#[cfg(feature = "trace")]
+fn parse_file_line(segment: &str) -> Option<(String, u32)> {
+    if segment.contains(' ') {
+        return None;
+    }
+    let (file, line) = segment.rsplit_once(':')?;
+    if file.is_empty() {
+        return None;
+    }
+    Some((file.to_string(), line.parse().ok()?))
+}
+
+// Whether `segment` can be a tracing target such as "acovo::net"
+#[cfg(feature = "trace")]
+fn is_target(segment: &str) -> bool {
+    !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '-' || c == '.')
+}
+
+// "outer{a=1}:inner{b=2}" into its spans
+#[cfg(feature = "trace")]
+fn split_spans(segment: &str) -> Vec<String> {
+    let mut spans: Vec<String> = segment.split("}:").map(|span| format!("{}}}", span)).collect();
+    if let Some(last) = spans.last_mut() {
+        if segment.ends_with('}') {
+            last.pop();
+        }
+    }
+    spans
+}
+
+// Timestamp, level, thread, spans, target and file of a text line, and the rest
+#[cfg(feature = "trace")]
+fn parse_text_prefix(line: &str) -> Option<(LogEntry, String)> {
+    let (timestamp, rest) = line.split_once(' ')?;
+    let timestamp = parse_timestamp(timestamp)?;
+    let rest = rest.trim_start();
+    let (level, mut rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let level = Level::from_str(level).ok()?;
+
+    let mut thread_id = None;
+    if let Some(after) = rest.strip_prefix("ThreadId(") {
+        let (id, after) = after.split_once(')')?;
+        thread_id = id.parse().ok();
+        rest = after.trim_start();
+    }
+
+    // The prefix segments end in ": "; with line numbers the target is the
+    // segment right before "file:line", otherwise the first one after the spans
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0;
+    for (pos, _) in rest.match_indices(": ").take(8) {
+        segments.push((start, pos));
+        start = pos + 2;
+    }
+    let text = |(from, to): (usize, usize)| &rest[from..to];
+    let after = |(_, to): (usize, usize)| rest.get(to + 2..).unwrap_or("");
+
+    let mut spans = Vec::new();
+    let mut target = String::new();
+    let mut file = None;
+    let mut line_number = None;
+    let mut tail = rest;
+
+    let file_at = segments
+        .iter()
+        .position(|segment| parse_file_line(text(*segment)).is_some())
+        .filter(|index| *index > 0 && is_target(text(segments[index - 1])));
+    if let Some(index) = file_at {
+        (file, line_number) = parse_file_line(text(segments[index])).map(|(f, l)| (Some(f), Some(l)))?;
+        target = text(segments[index - 1]).to_string();
+        spans = segments[..index - 1].iter().flat_map(|s| split_spans(text(*s))).collect();
+        tail = after(segments[index]);
+    } else {
+        for segment in &segments {
+            let segment_text = text(*segment);
+            if segment_text.ends_with('}') && segment_text.contains('{') {
+                spans.extend(split_spans(segment_text));
+            } else {
+                if is_target(segment_text) {
+                    target = segment_text.to_string();
+                    tail = after(*segment);
+                }
+                break;
+            }
+        }
+    }
+
+    let entry = LogEntry {
+        record: LogRecord {
+            timestamp,
+            level,
+            target,
+            message: String::new(),
+            fields: serde_json::Map::new(),
+        },
+        thread_id,
+        spans,
+        file,
+        line: line_number,
+        source: PathBuf::new(),
+    };
+    Some((entry, tail.to_string()))
+}
+
+// Split "message key=1 name=\"x y\"" into the message and trailing fields
+#[cfg(feature = "trace")]
+fn set_message(entry: &mut LogEntry, text: &str) {
+    // Byte ranges of words, keeping quoted values with spaces together
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (pos, c) in text.char_indices() {
+        if start.is_none() {
+            if c == ' ' {
+                continue;
+            }
+            start = Some(pos);
+        }
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ' ' | '\n' if !quoted => {
+                words.push((start.take().unwrap_or(pos), pos));
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, text.len()));
+    }
+
+    let mut fields = Vec::new();
+    let mut message_end = text.len();
+    for (from, to) in words.into_iter().rev() {
+        let word = &text[from..to];
+        let Some((name, value)) = word.split_once('=') else { break };
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !valid_name {
+            break;
+        }
+        let value = serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        fields.push((name.to_string(), value));
+        message_end = from;
+    }
+
+    entry.record.message = text[..message_end].trim_end().to_string();
+    for (name, value) in fields.into_iter().rev() {
+        entry.record.fields.insert(name, value);
+    }
+}
+
+// A line of the JSON format described on `JsonFormat`
+#[cfg(feature = "trace")]
+fn parse_json_line(line: &str) -> Option<LogEntry> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let text = |key: &str| value.get(key).and_then(|v| v.as_str());
+    let spans = value
+        .get("spans")
+        .and_then(|spans| spans.as_array())
+        .map(|spans| {
+            spans
+                .iter()
+                .map(|span| {
+                    let name = span.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                    match span.get("fields").and_then(|f| f.as_object()).filter(|f| !f.is_empty()) {
+                        Some(fields) => {
+                            let fields: Vec<String> = fields.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                            format!("{}{{{}}}", name, fields.join(" "))
+                        }
+                        None => name.to_string(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(LogEntry {
+        record: LogRecord {
+            timestamp: parse_timestamp(text("timestamp")?)?,
+            level: Level::from_str(text("level")?).ok()?,
+            target: text("target").unwrap_or("").to_string(),
+            message: text("message").unwrap_or("").to_string(),
+            fields: value.get("fields").and_then(|f| f.as_object()).cloned().unwrap_or_default(),
+        },
+        thread_id: value.get("thread_id").and_then(|v| v.as_u64()),
+        spans,
+        file: text("file").map(|f| f.to_string()),
+        line: value.get("line").and_then(|v| v.as_u64()).map(|l| l as u32),
+        source: PathBuf::new(),
+    })
+}
+
+// Sort key of a log file name: the period and the size segment index
+#[cfg(feature = "trace")]
+fn file_order(name: &str, prefix: &str) -> (String, u32) {
+    let stem = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zip"))
+        .unwrap_or(name);
+    let rest = stem.strip_prefix(prefix).and_then(|r| r.strip_prefix('.')).unwrap_or("");
+    let (period, index) = match rest.rsplit_once('.') {
+        Some((period, index)) => (period, index),
+        None if !rest.contains('-') => ("", rest),
+        None => (rest, ""),
+    };
+    (period.to_string(), index.parse().unwrap_or(0))
+}
+
+// A buffered reader over a plain, gzip or zip log file
+#[cfg(feature = "trace")]
+fn open_log(path: &Path) -> AnyResult<Box<dyn BufRead + Send>> {
+    let file = File::open(path).map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if name.ends_with(".gz") {
+        return Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))));
+    }
+    if name.ends_with(".zip") {
+        #[cfg(feature = "compress")]
+        {
+            use std::io::Read;
+
+            let mut archive = ::zip::ZipArchive::new(file)?;
+            let mut data = Vec::new();
+            archive.by_index(0)?.read_to_end(&mut data)?;
+            return Ok(Box::new(std::io::Cursor::new(data)));
+        }
+        #[cfg(not(feature = "compress"))]
+        return Err(anyhow!("reading {} requires the compress feature", path.display()));
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+// Start of the interval containing `time`, aligned to local midnight when the
+// interval divides a day
+#[cfg(feature = "trace")]
+fn interval_start(time: &DateTime<Local>, interval: chrono::Duration) -> DateTime<Local> {
+    let step = interval.num_milliseconds().max(1);
+    const DAY_MS: i64 = 86_400_000;
+    if DAY_MS % step == 0 {
+        let local = time.naive_local();
+        let midnight = local.date().and_hms_opt(0, 0, 0).unwrap_or(local);
+        let offset = (local - midnight).num_milliseconds();
+        let start = midnight + chrono::Duration::milliseconds(offset - offset % step);
+        if let Some(start) = Local.from_local_datetime(&start).earliest() {
+            return start;
+        }
+    }
+    let millis = time.timestamp_millis();
+    Local
+        .timestamp_millis_opt(millis - millis.rem_euclid(step))
+        .single()
+        .unwrap_or(*time)
+}
+
+/// Reads the log files of one prefix, oldest first
+///
+/// Files follow the `RollingFileWriter` naming: `<prefix>`, `<prefix>.<period>`
+/// and `<prefix>.<period>.<N>`, each optionally ending in `.gz` or `.zip`
+/// (zip needs the `compress` feature).
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone)]
+pub struct LogReader {
+    dir: PathBuf,
+    prefix: String,
+}
+
+#[cfg(feature = "trace")]
+impl LogReader {
+    /// Create a reader for the files named `prefix` in `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P, prefix: &str) -> Self {
+        LogReader {
+            dir: dir.into(),
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Create a reader for the files written with `config`
+    ///
+    /// # Errors
+    /// * Returns an error if the config has no file prefix or log directory
+    pub fn from_config(config: &TracingConfig) -> AnyResult<Self> {
+        let prefix = config
+            .file_prefix
+            .as_deref()
+            .ok_or_else(|| anyhow!("the tracing config does not write a log file"))?;
+        Ok(LogReader::new(config.log_path()?, prefix))
+    }
+
+    /// Log files of the prefix in time order, oldest first
+    ///
+    /// # Errors
+    /// * Returns an error if the directory cannot be read
+    pub fn files(&self) -> AnyResult<Vec<PathBuf>> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| anyhow!("failed to read log directory {}: {}", self.dir.display(), e))?;
+        let mut files: Vec<((String, u32), PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.to_string();
+                is_rotated_log_name(&name, &self.prefix).then(|| (file_order(&name, &self.prefix), entry.path()))
+            })
+            .collect();
+        files.sort();
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Iterate over the entries matching `query`, oldest first
+    ///
+    /// Files are opened one at a time, so this works on logs larger than
+    /// memory. The query limit is ignored; use `search` for the newest N.
+    /// A file that cannot be read yields one error and iteration continues
+    /// with the next file.
+    ///
+    /// # Errors
+    /// * Returns an error if the directory cannot be read
+    pub fn entries(&self, query: &LogQuery) -> AnyResult<LogEntries> {
+        Ok(LogEntries {
+            files: self.files()?.into(),
+            current: None,
+            pending: None,
+            query: query.clone(),
+        })
+    }
+
+    /// Entries matching `query`, oldest first, keeping only the newest `limit` if set
+    ///
+    /// # Errors
+    /// * Returns an error if the directory or a log file cannot be read
+    pub fn search(&self, query: &LogQuery) -> AnyResult<Vec<LogEntry>> {
+        let mut found = VecDeque::new();
+        for entry in self.entries(query)? {
+            found.push_back(entry?);
+            if query.limit.is_some_and(|limit| found.len() > limit) {
+                found.pop_front();
+            }
+        }
+        Ok(found.into())
+    }
+
+    /// Number of entries matching `query` in each `interval`, keyed by interval start
+    ///
+    /// Intervals that divide a day start at local midnight (e.g. every 5 minutes
+    /// or every hour); longer ones are aligned to the Unix epoch. Intervals
+    /// without matching entries are omitted.
+    ///
+    /// # Errors
+    /// * Returns an error if the interval is zero or a log file cannot be read
+    pub fn count_by_interval(
+        &self,
+        query: &LogQuery,
+        interval: std::time::Duration,
+    ) -> AnyResult<BTreeMap<DateTime<Local>, usize>> {
+        let interval = chrono::Duration::from_std(interval).map_err(|e| anyhow!("invalid interval: {}", e))?;
+        if interval.num_milliseconds() <= 0 {
+            return Err(anyhow!("interval must be at least one millisecond"));
+        }
+        let mut counts = BTreeMap::new();
+        for entry in self.entries(query)? {
+            let entry = entry?;
+            *counts.entry(interval_start(&entry.record.timestamp, interval)).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Number of ERROR entries in each `interval`; see `count_by_interval`
+    ///
+    /// # Errors
+    /// * Returns an error if the interval is zero or a log file cannot be read
+    pub fn error_counts(&self, interval: std::time::Duration) -> AnyResult<BTreeMap<DateTime<Local>, usize>> {
+        self.count_by_interval(&LogQuery::new().level(Level::ERROR), interval)
+    }
+}
+
+/// Iterator over the matching entries of a `LogReader`, oldest first
+#[cfg(feature = "trace")]
+pub struct LogEntries {
+    files: VecDeque<PathBuf>,
+    current: Option<(PathBuf, Box<dyn BufRead + Send>)>,
+    // Entry whose message may continue on the following lines, and its unsplit text
+    pending: Option<(LogEntry, String)>,
+    query: LogQuery,
+}
+
+#[cfg(feature = "trace")]
+impl LogEntries {
+    // Finish the pending entry and return it if it matches the query
+    fn take_pending(&mut self) -> Option<LogEntry> {
+        let (mut entry, text) = self.pending.take()?;
+        if !text.is_empty() || entry.record.message.is_empty() {
+            set_message(&mut entry, &text);
+        }
+        self.query.matches(&entry.record).then_some(entry)
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Iterator for LogEntries {
+    type Item = AnyResult<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                let path = match self.files.pop_front() {
+                    Some(path) => path,
+                    None => return self.take_pending().map(Ok),
+                };
+                match open_log(&path) {
+                    Ok(reader) => self.current = Some((path, reader)),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            let (path, reader) = self.current.as_mut()?;
+            let mut buf = Vec::new();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => {
+                    // Entries never continue across files
+                    self.current = None;
+                    if let Some(entry) = self.take_pending() {
+                        return Some(Ok(entry));
+                    }
+                }
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf);
+                    let line = line.trim_end_matches(['\r', '\n']);
+                    if line.starts_with('{') {
+                        let parsed = parse_json_line(line).map(|mut entry| {
+                            entry.source = path.clone();
+                            entry
+                        });
+                        let previous = self.take_pending();
+                        if let Some(entry) = parsed.filter(|entry| self.query.matches(&entry.record)) {
+                            self.pending = Some((entry, String::new()));
+                        }
+                        if previous.is_some() {
+                            return previous.map(Ok);
+                        }
+                        continue;
+                    }
+                    match parse_text_prefix(line) {
+                        Some((mut entry, text)) => {
+                            entry.source = path.clone();
+                            let previous = self.take_pending();
+                            self.pending = Some((entry, text));
+                            if previous.is_some() {
+                                return previous.map(Ok);
+                            }
+                        }
+                        None => {
+                            if let Some((_, text)) = &mut self.pending {
+                                text.push('\n');
+                                text.push_str(line);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    let error = anyhow!("failed to read {}: {}", path.display(), e);
+                    self.current = None;
+                    self.pending = None;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "trace")]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_text_line_with_all_parts() {
+        let entry = LogEntry::parse(
+            "2024-05-01T10:20:30.123 ERROR ThreadId(07) req{id=7}:db{table=\"users\"}: acovo::net: src/net.rs:42: Net NoRoute host=\"10.0.0.1\" retries=3",
+        )
+        .unwrap();
+        assert_eq!(entry.record.timestamp.format("%FT%T%.3f").to_string(), "2024-05-01T10:20:30.123");
+        assert_eq!(entry.record.level, Level::ERROR);
+        assert_eq!(entry.thread_id, Some(7));
+        assert_eq!(entry.spans, vec!["req{id=7}", "db{table=\"users\"}"]);
+        assert_eq!(entry.record.target, "acovo::net");
+        assert_eq!(entry.file.as_deref(), Some("src/net.rs"));
+        assert_eq!(entry.line, Some(42));
+        assert_eq!(entry.record.message, "Net NoRoute");
+        assert_eq!(entry.record.fields["host"], "10.0.0.1");
+        assert_eq!(entry.record.fields["retries"], 3);
+    }
+
+    #[test]
+    fn test_parse_plain_and_json_lines() {
+        let entry = LogEntry::parse("2024-05-01T10:20:30.123  INFO my_app: started: version 2 ok=true").unwrap();
+        assert_eq!(entry.record.level, Level::INFO);
+        assert_eq!(entry.record.target, "my_app");
+        assert_eq!(entry.record.message, "started: version 2");
+        assert_eq!(entry.record.fields["ok"], true);
+        assert!(entry.thread_id.is_none() && entry.file.is_none() && entry.spans.is_empty());
+
+        let json = r#"{"timestamp":"2024-05-01T10:20:30.123","level":"WARN","target":"svc","file":"src/a.rs","line":9,"thread_id":3,"message":"slow","spans":[{"name":"req","fields":{"id":7}}],"fields":{"ms":900}}"#;
+        let entry = LogEntry::parse(json).unwrap();
+        assert_eq!(entry.record.level, Level::WARN);
+        assert_eq!(entry.record.fields["ms"], 900);
+        assert_eq!(entry.spans, vec!["req{id=7}"]);
+        assert_eq!((entry.thread_id, entry.line), (Some(3), Some(9)));
+
+        assert!(LogEntry::parse("continuation of a message").is_none());
+        assert!(LogEntry::parse("2024-05-01T10:20:30.123 LOUD x: y").is_none());
+    }
+
+    #[test]
+    fn test_file_order() {
+        let mut names = vec!["app.2024-01-02", "app.2024-01-01.1.gz", "app.2024-01-01.gz", "app.2024-01-01.10", "app.2024-01-01.2"];
+        names.sort_by_key(|name| file_order(name, "app"));
+        assert_eq!(names, vec!["app.2024-01-01.gz", "app.2024-01-01.1.gz", "app.2024-01-01.2", "app.2024-01-01.10", "app.2024-01-02"]);
+        assert_eq!(file_order("app", "app"), (String::new(), 0));
+        assert_eq!(file_order("app.3", "app"), (String::new(), 3));
+    }
+
+    fn write_gzip(path: &Path, text: &str) {
+        let mut encoder = flate2::write::GzEncoder::new(File::create(path).unwrap(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_reader_across_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write_gzip(
+            &dir.path().join("svc.2024-05-01.gz"),
+            "2024-05-01T23:10:00.000 ERROR svc::db: query failed table=users\n\
+             2024-05-01T23:40:00.000  WARN svc::db: slow query\n",
+        );
+        std::fs::write(
+            dir.path().join("svc.2024-05-02"),
+            "2024-05-02T00:05:00.000 ERROR svc::net: connection refused\n\
+             first detail line\n\
+             second detail line\n\
+             2024-05-02T00:20:00.000  INFO svc::net: reconnected\n\
+             2024-05-02T00:50:00.000 ERROR svc::db: timeout after 5s\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("other.2024-05-02"), "2024-05-02T00:00:00.000 ERROR x: ignored\n").unwrap();
+
+        let reader = LogReader::new(dir.path(), "svc");
+        assert_eq!(reader.files().unwrap().len(), 2);
+
+        let all = reader.search(&LogQuery::new()).unwrap();
+        let messages: Vec<&str> = all.iter().map(|e| e.record.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "query failed",
+                "slow query",
+                "connection refused\nfirst detail line\nsecond detail line",
+                "reconnected",
+                "timeout after 5s"
+            ]
+        );
+        assert!(all[0].source.ends_with("svc.2024-05-01.gz"));
+
+        let db_errors = reader
+            .search(&LogQuery::new().level(Level::ERROR).target("svc::db"))
+            .unwrap();
+        assert_eq!(db_errors.len(), 2);
+
+        let pattern = regex::Regex::new(r"refused|timeout").unwrap();
+        let matched = reader.search(&LogQuery::new().matching(pattern).limit(1)).unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].record.message, "timeout after 5s");
+
+        let since = parse_timestamp("2024-05-02T00:00:00.000").unwrap();
+        let until = parse_timestamp("2024-05-02T00:30:00.000").unwrap();
+        let window = reader.search(&LogQuery::new().since(since).until(until)).unwrap();
+        assert_eq!(window.len(), 2);
+
+        let counts = reader.error_counts(std::time::Duration::from_secs(3600)).unwrap();
+        let counts: Vec<(String, usize)> = counts.into_iter().map(|(t, n)| (t.format("%F %H:%M").to_string(), n)).collect();
+        assert_eq!(counts, vec![("2024-05-01 23:00".to_string(), 1), ("2024-05-02 00:00".to_string(), 2)]);
+        assert!(reader.error_counts(std::time::Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_reader_reads_files_written_by_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TracingConfig::new()
+            .stdout(false)
+            .log_dir(dir.path())
+            .file_prefix("written")
+            .line_numbers(true)
+            .thread_ids(true)
+            .rotation(crate::trace::Rotation::Never);
+        let (subscriber, guard) = config.build().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", id = 7);
+            let _enter = span.enter();
+            tracing::error!(host = "gw", "Net NoRoute");
+        });
+        drop(guard);
+
+        let entries = LogReader::from_config(&config).unwrap().search(&LogQuery::new()).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.record.message, "Net NoRoute");
+        assert_eq!(entry.record.fields["host"], "gw");
+        assert_eq!(entry.record.target, module_path!());
+        assert_eq!(entry.spans, vec!["request{id=7}"]);
+        assert!(entry.thread_id.is_some());
+        assert!(entry.file.as_deref().is_some_and(|f| f.ends_with("reader.rs")));
+    }
+}
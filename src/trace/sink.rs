@@ -0,0 +1,928 @@
+//! Syslog and systemd-journald outputs
+//!
+//! `SyslogLayer` sends each event as one datagram to the local syslog socket in
+//! RFC 3164 or RFC 5424 format. `JournaldLayer` uses the journald native
+//! protocol, so event fields arrive as structured journal fields. Both are
+//! normally enabled through `TracingConfig::syslog` and `TracingConfig::journald`.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result as AnyResult};
+use chrono::{DateTime, Local, SecondsFormat};
+use serde_derive::{Deserialize, Serialize};
+use tracing::Level;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(unix)]
+use std::sync::Mutex;
+#[cfg(unix)]
+use tracing_subscriber::Layer;
+
+#[cfg(unix)]
+use super::JsonVisitor;
+
+/// Default syslog socket on Linux
+#[cfg(all(feature = "trace", not(target_os = "macos")))]
+pub const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+/// Default syslog socket on macOS
+#[cfg(all(feature = "trace", target_os = "macos"))]
+pub const DEFAULT_SYSLOG_SOCKET: &str = "/var/run/syslog";
+/// Default journald native protocol socket
+#[cfg(feature = "trace")]
+pub const DEFAULT_JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Syslog severity, also used as the journald `PRIORITY`
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// System is unusable (0)
+    #[serde(alias = "emerg")]
+    Emergency,
+    /// Action must be taken immediately (1)
+    Alert,
+    /// Critical conditions (2)
+    #[serde(alias = "crit")]
+    Critical,
+    /// Error conditions (3)
+    #[serde(alias = "err")]
+    Error,
+    /// Warning conditions (4)
+    #[serde(alias = "warn")]
+    Warning,
+    /// Normal but significant conditions (5)
+    Notice,
+    /// Informational messages (6)
+    Info,
+    /// Debug-level messages (7)
+    Debug,
+}
+
+#[cfg(feature = "trace")]
+impl Severity {
+    /// Numeric severity, 0 (emergency) to 7 (debug)
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "emergency" | "emerg" | "0" => Ok(Severity::Emergency),
+            "alert" | "1" => Ok(Severity::Alert),
+            "critical" | "crit" | "2" => Ok(Severity::Critical),
+            "error" | "err" | "3" => Ok(Severity::Error),
+            "warning" | "warn" | "4" => Ok(Severity::Warning),
+            "notice" | "5" => Ok(Severity::Notice),
+            "info" | "6" => Ok(Severity::Info),
+            "debug" | "7" => Ok(Severity::Debug),
+            other => Err(anyhow!("unknown syslog severity '{}'", other)),
+        }
+    }
+}
+
+/// Severity used for each tracing level
+///
+/// The default maps ERROR to error, WARN to warning, INFO to info and both
+/// DEBUG and TRACE to debug.
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityMap {
+    pub error: Severity,
+    pub warn: Severity,
+    pub info: Severity,
+    pub debug: Severity,
+    pub trace: Severity,
+}
+
+#[cfg(feature = "trace")]
+impl Default for SeverityMap {
+    fn default() -> Self {
+        SeverityMap {
+            error: Severity::Error,
+            warn: Severity::Warning,
+            info: Severity::Info,
+            debug: Severity::Debug,
+            trace: Severity::Debug,
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl SeverityMap {
+    /// Severity for `level`
+    pub fn severity(&self, level: &Level) -> Severity {
+        match *level {
+            Level::ERROR => self.error,
+            Level::WARN => self.warn,
+            Level::INFO => self.info,
+            Level::DEBUG => self.debug,
+            _ => self.trace,
+        }
+    }
+
+    /// Use `severity` for `level`
+    pub fn with(mut self, level: Level, severity: Severity) -> Self {
+        match level {
+            Level::ERROR => self.error = severity,
+            Level::WARN => self.warn = severity,
+            Level::INFO => self.info = severity,
+            Level::DEBUG => self.debug = severity,
+            _ => self.trace = severity,
+        }
+        self
+    }
+}
+
+/// Syslog facility, the source category of a message
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Facility {
+    Kern,
+    #[default]
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    Authpriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+#[cfg(feature = "trace")]
+impl Facility {
+    /// Numeric facility as used in the PRI field
+    pub fn code(&self) -> u8 {
+        match self {
+            Facility::Kern => 0,
+            Facility::User => 1,
+            Facility::Mail => 2,
+            Facility::Daemon => 3,
+            Facility::Auth => 4,
+            Facility::Syslog => 5,
+            Facility::Lpr => 6,
+            Facility::News => 7,
+            Facility::Uucp => 8,
+            Facility::Cron => 9,
+            Facility::Authpriv => 10,
+            Facility::Ftp => 11,
+            Facility::Local0 => 16,
+            Facility::Local1 => 17,
+            Facility::Local2 => 18,
+            Facility::Local3 => 19,
+            Facility::Local4 => 20,
+            Facility::Local5 => 21,
+            Facility::Local6 => 22,
+            Facility::Local7 => 23,
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for Facility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        serde_json::from_value(serde_json::Value::String(s.trim().to_ascii_lowercase()))
+            .map_err(|_| anyhow!("unknown syslog facility '{}'", s))
+    }
+}
+
+/// Syslog message format
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogFormat {
+    /// BSD format, `<PRI>Mmm dd hh:mm:ss ident[pid]: message`, understood by every syslog daemon
+    #[default]
+    Rfc3164,
+    /// `<PRI>1 timestamp host ident pid - - message` with a full RFC 3339 timestamp
+    Rfc5424,
+}
+
+#[cfg(feature = "trace")]
+impl FromStr for SyslogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> AnyResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rfc3164" | "3164" | "bsd" => Ok(SyslogFormat::Rfc3164),
+            "rfc5424" | "5424" => Ok(SyslogFormat::Rfc5424),
+            other => Err(anyhow!("unknown syslog format '{}'", other)),
+        }
+    }
+}
+
+/// Settings of the syslog output
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyslogConfig {
+    /// Program name in each message; defaults to the executable name
+    pub identifier: Option<String>,
+    /// Facility of every message
+    pub facility: Facility,
+    /// Message format
+    pub format: SyslogFormat,
+    /// Datagram socket of the syslog daemon; defaults to `DEFAULT_SYSLOG_SOCKET`
+    pub socket: Option<PathBuf>,
+    /// Severity for each tracing level
+    pub severities: SeverityMap,
+}
+
+#[cfg(feature = "trace")]
+impl SyslogConfig {
+    /// Create a config with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the program name
+    pub fn identifier(mut self, identifier: &str) -> Self {
+        self.identifier = Some(identifier.to_string());
+        self
+    }
+
+    /// Set the facility
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Set the message format
+    pub fn format(mut self, format: SyslogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Send to the socket at `path` instead of the default
+    pub fn socket<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.socket = Some(path.into());
+        self
+    }
+
+    /// Set the severity for each level
+    pub fn severities(mut self, severities: SeverityMap) -> Self {
+        self.severities = severities;
+        self
+    }
+}
+
+/// Settings of the journald output
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournaldConfig {
+    /// `SYSLOG_IDENTIFIER` of each entry; defaults to the executable name
+    pub identifier: Option<String>,
+    /// Prefix of journal field names built from event fields, e.g. "F" gives `F_USER_ID`
+    pub field_prefix: Option<String>,
+    /// Native protocol socket; defaults to `DEFAULT_JOURNALD_SOCKET`
+    pub socket: Option<PathBuf>,
+    /// `PRIORITY` for each tracing level
+    pub severities: SeverityMap,
+}
+
+#[cfg(feature = "trace")]
+impl Default for JournaldConfig {
+    /// Executable name as identifier and event fields prefixed with "F"
+    fn default() -> Self {
+        JournaldConfig {
+            identifier: None,
+            field_prefix: Some("F".to_string()),
+            socket: None,
+            severities: SeverityMap::default(),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl JournaldConfig {
+    /// Create a config with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `SYSLOG_IDENTIFIER`
+    pub fn identifier(mut self, identifier: &str) -> Self {
+        self.identifier = Some(identifier.to_string());
+        self
+    }
+
+    /// Set the prefix of event field names, or `None` for no prefix
+    pub fn field_prefix(mut self, prefix: Option<&str>) -> Self {
+        self.field_prefix = prefix.map(|p| p.to_string());
+        self
+    }
+
+    /// Send to the socket at `path` instead of the default
+    pub fn socket<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.socket = Some(path.into());
+        self
+    }
+
+    /// Set the priority for each level
+    pub fn severities(mut self, severities: SeverityMap) -> Self {
+        self.severities = severities;
+        self
+    }
+}
+
+/// Name of the running executable, or "acovo" if unknown
+#[cfg(feature = "trace")]
+pub fn default_identifier() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "acovo".to_string())
+}
+
+// "target: message key=value ..." as sent to syslog
+#[cfg(feature = "trace")]
+fn event_text(target: &str, message: &str, fields: &serde_json::Map<String, serde_json::Value>) -> String {
+    let mut text = format!("{}: {}", target, message);
+    for (name, value) in fields {
+        match value {
+            serde_json::Value::String(value) => text.push_str(&format!(" {}={}", name, value)),
+            other => text.push_str(&format!(" {}={}", name, other)),
+        }
+    }
+    text
+}
+
+// RFC 5424 header fields are printable ASCII without spaces, "-" when empty
+#[cfg(feature = "trace")]
+fn header_token(value: &str, max_len: usize) -> String {
+    let token: String = value
+        .chars()
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .take(max_len)
+        .collect();
+    if token.is_empty() {
+        "-".to_string()
+    } else {
+        token
+    }
+}
+
+// RFC 3164 TAG: printable ASCII without the ":" and "[" that end it, at most 48 characters
+#[cfg(feature = "trace")]
+fn syslog_tag(identifier: &str) -> String {
+    let tag: String = identifier
+        .chars()
+        .filter(|c| c.is_ascii_graphic() && !matches!(c, ':' | '[' | ']'))
+        .take(48)
+        .collect();
+    if tag.is_empty() {
+        "-".to_string()
+    } else {
+        tag
+    }
+}
+
+/// Format one syslog message
+///
+/// # Arguments
+/// * `format` - RFC 3164 or RFC 5424
+/// * `priority` - Facility code * 8 + severity code
+/// * `timestamp` - Time of the event
+/// * `hostname` - Host name, used by RFC 5424 only
+/// * `identifier` - Program name; characters not allowed in the header are removed
+/// * `pid` - Process id
+/// * `text` - Message body
+#[cfg(feature = "trace")]
+pub fn format_syslog(
+    format: SyslogFormat,
+    priority: u8,
+    timestamp: &DateTime<Local>,
+    hostname: &str,
+    identifier: &str,
+    pid: u32,
+    text: &str,
+) -> String {
+    match format {
+        SyslogFormat::Rfc3164 => format!(
+            "<{}>{} {}[{}]: {}",
+            priority,
+            timestamp.format("%b %e %H:%M:%S"),
+            syslog_tag(identifier),
+            pid,
+            text
+        ),
+        SyslogFormat::Rfc5424 => format!(
+            "<{}>1 {} {} {} {} - - {}",
+            priority,
+            timestamp.to_rfc3339_opts(SecondsFormat::Micros, false),
+            header_token(hostname, 255),
+            header_token(identifier, 48),
+            pid,
+            text
+        ),
+    }
+}
+
+// Host name for RFC 5424 messages
+#[cfg(all(feature = "trace", unix))]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its length and gethostname NUL-terminates within it on success
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len() - 1) };
+    if ret != 0 {
+        return "-".to_string();
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+// Message and fields of an event
+#[cfg(all(feature = "trace", unix))]
+fn event_parts(event: &tracing::Event<'_>) -> (String, serde_json::Map<String, serde_json::Value>) {
+    let mut fields = serde_json::Map::new();
+    event.record(&mut JsonVisitor { fields: &mut fields });
+    let message = match fields.remove("message") {
+        Some(serde_json::Value::String(text)) => text,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    (message, fields)
+}
+
+/// A tracing layer sending events to the local syslog daemon
+///
+/// Messages are sent as datagrams without blocking the logging thread. When the
+/// daemon falls behind the event is dropped and counted (`dropped_events`); if
+/// the daemon restarted the socket is reconnected once.
+///
+/// # Example
+/// ```no_run
+/// use acovo::trace::sink::{Facility, SyslogConfig, SyslogFormat};
+/// use acovo::trace::TracingConfig;
+///
+/// let _guard = TracingConfig::new()
+///     .syslog(SyslogConfig::new().identifier("my_app").facility(Facility::Daemon).format(SyslogFormat::Rfc5424))
+///     .init()
+///     .expect("Failed to initialize tracing");
+/// ```
+#[cfg(all(feature = "trace", unix))]
+#[derive(Debug)]
+pub struct SyslogLayer {
+    socket: Mutex<UnixDatagram>,
+    path: PathBuf,
+    identifier: String,
+    hostname: String,
+    facility: Facility,
+    format: SyslogFormat,
+    severities: SeverityMap,
+    dropped: AtomicU64,
+}
+
+#[cfg(all(feature = "trace", unix))]
+impl SyslogLayer {
+    /// Connect to the syslog socket described by `config`
+    ///
+    /// # Errors
+    /// * Returns an error if the socket cannot be connected
+    pub fn new(config: &SyslogConfig) -> AnyResult<Self> {
+        let path = config.socket.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_SYSLOG_SOCKET));
+        let socket = Self::connect(&path)?;
+        Ok(SyslogLayer {
+            socket: Mutex::new(socket),
+            path,
+            identifier: config.identifier.clone().unwrap_or_else(default_identifier),
+            hostname: hostname(),
+            facility: config.facility,
+            format: config.format,
+            severities: config.severities,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Number of events dropped because the socket was full or unavailable
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn connect(path: &Path) -> AnyResult<UnixDatagram> {
+        let socket = UnixDatagram::unbound()?;
+        socket
+            .connect(path)
+            .map_err(|e| anyhow!("failed to connect to syslog socket {}: {}", path.display(), e))?;
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    }
+
+    fn send(&self, message: &str) {
+        let mut socket = self.socket.lock().unwrap_or_else(|e| e.into_inner());
+        let sent = match socket.send(message.as_bytes()) {
+            Ok(_) => true,
+            // The daemon is not keeping up; never stall the thread that logs
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+            Err(_) => match Self::connect(&self.path) {
+                Ok(reconnected) => {
+                    *socket = reconnected;
+                    socket.send(message.as_bytes()).is_ok()
+                }
+                Err(_) => false,
+            },
+        };
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(feature = "trace", unix))]
+impl<S: tracing::Subscriber> Layer<S> for SyslogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let metadata = event.metadata();
+        let (message, fields) = event_parts(event);
+        let priority = self.facility.code() * 8 + self.severities.severity(metadata.level()).code();
+        self.send(&format_syslog(
+            self.format,
+            priority,
+            &Local::now(),
+            &self.hostname,
+            &self.identifier,
+            std::process::id(),
+            &event_text(metadata.target(), &message, &fields),
+        ));
+    }
+}
+
+/// Journal field name for an event field: uppercase letters, digits and underscores
+///
+/// Other characters become underscores and the optional `prefix` is joined with
+/// an underscore. Names that would start with an underscore or a digit are
+/// prefixed with "F" since journald reserves or rejects them.
+#[cfg(feature = "trace")]
+pub fn journal_field_name(prefix: Option<&str>, name: &str) -> String {
+    let sanitize = |text: &str| -> String {
+        text.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect()
+    };
+    let mut field = match prefix {
+        Some(prefix) if !prefix.is_empty() => format!("{}_{}", sanitize(prefix), sanitize(name)),
+        _ => sanitize(name),
+    };
+    if field.is_empty() || field.starts_with('_') || field.starts_with(|c: char| c.is_ascii_digit()) {
+        field.insert(0, 'F');
+    }
+    field.truncate(64);
+    field
+}
+
+/// Append one field in the journald native protocol encoding
+///
+/// Values containing a newline use the length-prefixed binary form.
+#[cfg(feature = "trace")]
+pub fn encode_journal_field(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    buf.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
+
+/// A tracing layer writing events to systemd-journald
+///
+/// Each entry has `MESSAGE`, `PRIORITY`, `SYSLOG_IDENTIFIER`, `TARGET`,
+/// `CODE_FILE` and `CODE_LINE`, plus one field per event field named by
+/// `journal_field_name`. Entries too large for a datagram are passed to
+/// journald in a sealed memfd on Linux. Sending never blocks: when journald
+/// falls behind the entry is dropped and counted (`dropped_events`).
+#[cfg(all(feature = "trace", unix))]
+#[derive(Debug)]
+pub struct JournaldLayer {
+    socket: UnixDatagram,
+    path: PathBuf,
+    identifier: String,
+    field_prefix: Option<String>,
+    severities: SeverityMap,
+    dropped: AtomicU64,
+}
+
+#[cfg(all(feature = "trace", unix))]
+impl JournaldLayer {
+    /// Create a layer sending to the journald socket described by `config`
+    ///
+    /// # Errors
+    /// * Returns an error if the socket does not exist
+    pub fn new(config: &JournaldConfig) -> AnyResult<Self> {
+        let path = config.socket.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_JOURNALD_SOCKET));
+        std::fs::metadata(&path).map_err(|e| anyhow!("journald socket {} is not available: {}", path.display(), e))?;
+        let socket = UnixDatagram::unbound()?;
+        socket.set_nonblocking(true)?;
+        Ok(JournaldLayer {
+            socket,
+            path,
+            identifier: config.identifier.clone().unwrap_or_else(default_identifier),
+            field_prefix: config.field_prefix.clone(),
+            severities: config.severities,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Number of entries dropped because the socket was full or unavailable
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // Native protocol payload of one event
+    fn encode(&self, event: &tracing::Event<'_>) -> Vec<u8> {
+        let metadata = event.metadata();
+        let (message, fields) = event_parts(event);
+        let mut buf = Vec::with_capacity(256);
+        encode_journal_field(&mut buf, "PRIORITY", self.severities.severity(metadata.level()).code().to_string().as_bytes());
+        encode_journal_field(&mut buf, "MESSAGE", message.as_bytes());
+        encode_journal_field(&mut buf, "SYSLOG_IDENTIFIER", self.identifier.as_bytes());
+        encode_journal_field(&mut buf, "TARGET", metadata.target().as_bytes());
+        if let Some(file) = metadata.file() {
+            encode_journal_field(&mut buf, "CODE_FILE", file.as_bytes());
+        }
+        if let Some(line) = metadata.line() {
+            encode_journal_field(&mut buf, "CODE_LINE", line.to_string().as_bytes());
+        }
+        for (name, value) in &fields {
+            let name = journal_field_name(self.field_prefix.as_deref(), name);
+            match value {
+                serde_json::Value::String(text) => encode_journal_field(&mut buf, &name, text.as_bytes()),
+                other => encode_journal_field(&mut buf, &name, other.to_string().as_bytes()),
+            }
+        }
+        buf
+    }
+
+    fn send(&self, payload: &[u8]) {
+        let sent = match self.socket.send_to(payload, &self.path) {
+            Ok(_) => true,
+            #[cfg(target_os = "linux")]
+            Err(e) if e.raw_os_error() == Some(libc::EMSGSIZE) || e.raw_os_error() == Some(libc::ENOBUFS) => {
+                send_memfd(&self.socket, &self.path, payload).is_ok()
+            }
+            Err(_) => false,
+        };
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(feature = "trace", unix))]
+impl<S: tracing::Subscriber> Layer<S> for JournaldLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        self.send(&self.encode(event));
+    }
+}
+
+// Pass a large entry to journald as a sealed memfd, as sd_journal_send does
+#[cfg(all(feature = "trace", target_os = "linux"))]
+fn send_memfd(socket: &UnixDatagram, path: &Path, payload: &[u8]) -> std::io::Result<()> {
+    use std::fs::File;
+    use std::io::Write;
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::os::unix::ffi::OsStrExt;
+
+    // SAFETY: the name is a valid NUL-terminated string
+    let fd = unsafe { libc::memfd_create(c"acovo-journald".as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: fd was just created and is owned by nothing else
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(payload)?;
+    // SAFETY: fd is a valid memfd
+    let sealed = unsafe {
+        libc::fcntl(
+            fd,
+            libc::F_ADD_SEALS,
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL,
+        )
+    };
+    // journald rejects unsealed descriptors
+    if sealed < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: sockaddr_un is plain data, zero is a valid initial value
+    let mut addr: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    let path_bytes = path.as_os_str().as_bytes();
+    if path_bytes.len() >= addr.sun_path.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "journald socket path too long"));
+    }
+    for (dst, src) in addr.sun_path.iter_mut().zip(path_bytes) {
+        *dst = *src as libc::c_char;
+    }
+
+    let fd_size = std::mem::size_of::<libc::c_int>() as u32;
+    // SAFETY: CMSG_SPACE only computes a size
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fd_size) } as usize];
+    // SAFETY: msghdr is plain data, zero is a valid initial value
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_name = &mut addr as *mut libc::sockaddr_un as *mut libc::c_void;
+    msg.msg_namelen = std::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = control.len() as _;
+    // SAFETY: the control buffer has room for one header carrying one fd
+    let sent = unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(fd_size) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut libc::c_int, file.as_raw_fd());
+        libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL)
+    };
+    if sent < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "trace")]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_severity_map_and_parsing() {
+        let map = SeverityMap::default();
+        assert_eq!(map.severity(&Level::ERROR).code(), 3);
+        assert_eq!(map.severity(&Level::WARN).code(), 4);
+        assert_eq!(map.severity(&Level::TRACE), Severity::Debug);
+
+        let map = map.with(Level::INFO, Severity::Notice);
+        assert_eq!(map.severity(&Level::INFO), Severity::Notice);
+
+        assert_eq!("crit".parse::<Severity>().unwrap(), Severity::Critical);
+        assert_eq!("local3".parse::<Facility>().unwrap().code(), 19);
+        assert!("nope".parse::<Facility>().is_err());
+        assert_eq!("5424".parse::<SyslogFormat>().unwrap(), SyslogFormat::Rfc5424);
+
+        let parsed: SeverityMap = serde_json::from_str(r#"{"info":"notice","error":"crit"}"#).unwrap();
+        assert_eq!(parsed.info, Severity::Notice);
+        assert_eq!(parsed.error, Severity::Critical);
+        assert_eq!(parsed.warn, Severity::Warning);
+    }
+
+    #[test]
+    fn test_format_syslog() {
+        let ts = Local.with_ymd_and_hms(2024, 3, 5, 7, 8, 9).unwrap();
+        let priority = Facility::Daemon.code() * 8 + Severity::Error.code();
+
+        let bsd = format_syslog(SyslogFormat::Rfc3164, priority, &ts, "host", "app", 42, "net: down");
+        assert_eq!(bsd, "<27>Mar  5 07:08:09 app[42]: net: down");
+
+        // Characters that would end or corrupt the tag are removed
+        let bsd = format_syslog(SyslogFormat::Rfc3164, priority, &ts, "host", "my app:[1]\u{7}é", 42, "x");
+        assert_eq!(bsd, "<27>Mar  5 07:08:09 myapp1[42]: x");
+        assert_eq!(syslog_tag(": ["), "-");
+        assert_eq!(syslog_tag(&"a".repeat(60)).len(), 48);
+
+        let modern = format_syslog(SyslogFormat::Rfc5424, priority, &ts, "my host", "", 42, "net: down");
+        let expected = format!("<27>1 {} my_host - 42 - - net: down", ts.to_rfc3339_opts(SecondsFormat::Micros, false));
+        assert_eq!(modern, expected);
+    }
+
+    #[test]
+    fn test_journal_field_encoding() {
+        assert_eq!(journal_field_name(Some("F"), "user.id"), "F_USER_ID");
+        assert_eq!(journal_field_name(None, "_private"), "F_PRIVATE");
+        assert_eq!(journal_field_name(None, "2fa"), "F2FA");
+        assert_eq!(journal_field_name(None, &"x".repeat(80)).len(), 64);
+
+        let mut buf = Vec::new();
+        encode_journal_field(&mut buf, "MESSAGE", b"one line");
+        encode_journal_field(&mut buf, "TRACE", b"a\nb");
+        let mut expected = b"MESSAGE=one line\nTRACE\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert_eq!(buf, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_syslog_layer_sends_datagram() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let config = SyslogConfig::new().identifier("acovo-test").facility(Facility::Local0).socket(&path);
+        let subscriber = tracing_subscriber::registry().with(SyslogLayer::new(&config).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "acovo::net", host = "gw", "NoGateway");
+        });
+
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(message.starts_with("<132>"), "{}", message);
+        assert!(message.ends_with(&format!("acovo-test[{}]: acovo::net: NoGateway host=gw", std::process::id())));
+
+        assert!(SyslogLayer::new(&SyslogConfig::new().socket(dir.path().join("missing"))).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sinks_drop_events_when_daemon_stalls() {
+        let dir = tempfile::tempdir().unwrap();
+        let syslog_path = dir.path().join("log.sock");
+        let journal_path = dir.path().join("journal.sock");
+        // Bound but never read, so their queues fill up
+        let _syslog_server = UnixDatagram::bind(&syslog_path).unwrap();
+        let _journal_server = UnixDatagram::bind(&journal_path).unwrap();
+
+        let syslog = SyslogLayer::new(&SyslogConfig::new().socket(&syslog_path)).unwrap();
+        let journald = JournaldLayer::new(&JournaldConfig::new().socket(&journal_path)).unwrap();
+        let started = std::time::Instant::now();
+        for _ in 0..2_000 {
+            syslog.send("stalled");
+            journald.send(b"MESSAGE=stalled\n");
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(syslog.dropped_events() > 0);
+        assert!(journald.dropped_events() > 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_journald_layer_sends_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let config = JournaldConfig::new().identifier("acovo-test").socket(&path);
+        let subscriber = tracing_subscriber::registry().with(JournaldLayer::new(&config).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(target: "acovo::net", retries = 3, "Net NoRoute");
+        });
+
+        let mut buf = [0u8; 4096];
+        let len = server.recv(&mut buf).unwrap();
+        let payload = String::from_utf8_lossy(&buf[..len]).into_owned();
+        let lines: Vec<&str> = payload.lines().collect();
+        assert_eq!(lines[0], "PRIORITY=3");
+        assert!(lines.contains(&"MESSAGE=Net NoRoute"));
+        assert!(lines.contains(&"SYSLOG_IDENTIFIER=acovo-test"));
+        assert!(lines.contains(&"TARGET=acovo::net"));
+        assert!(lines.contains(&"F_RETRIES=3"));
+        assert!(lines.iter().any(|line| line.starts_with("CODE_LINE=")));
+
+        assert!(JournaldLayer::new(&JournaldConfig::new().socket(dir.path().join("missing"))).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_journald_large_entry_uses_memfd() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let config = JournaldConfig::new().socket(&path);
+        let subscriber = tracing_subscriber::registry().with(JournaldLayer::new(&config).unwrap());
+        let big = "x".repeat(4 * 1024 * 1024);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(payload = big.as_str(), "large entry");
+        });
+
+        // The entry travels as a file descriptor, leaving the datagram itself empty
+        let mut buf = [0u8; 16];
+        assert_eq!(server.recv(&mut buf).unwrap(), 0);
+    }
+}
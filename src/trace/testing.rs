@@ -0,0 +1,241 @@
+//! Capturing log events in tests
+//!
+//! `LogCapture` installs a subscriber for the current thread only, so tests
+//! can run in parallel and still see exactly the events they caused, whether
+//! or not a global subscriber is set. Records are the same `LogRecord`s a
+//! `LogBuffer` holds and are selected with `LogQuery`.
+//!
+//! ```
+//! use acovo::trace::testing::capture;
+//! use acovo::trace::LogQuery;
+//! use tracing::Level;
+//!
+//! let (_, logs) = capture(|| {
+//!     tracing::error!(host = "10.0.0.1", "Net NoRoute");
+//! });
+//! logs.assert_logged(&LogQuery::new().level(Level::ERROR).field("host"));
+//! logs.assert_error_logged("NoRoute");
+//! ```
+
+use tracing::subscriber::DefaultGuard;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+use super::{LogBuffer, LogQuery, LogRecord, DEFAULT_BUFFER_EVENTS};
+
+/// Records every event emitted on the current thread while it is active
+///
+/// Capturing starts with `start` and ends when the capture is dropped or
+/// `stop` is called; the records stay available after stopping. Events from
+/// other threads, including threads spawned by the code under test, are not
+/// captured. Captures can be nested: the innermost one receives the events.
+#[cfg(feature = "trace")]
+pub struct LogCapture {
+    buffer: LogBuffer,
+    guard: Option<DefaultGuard>,
+}
+
+#[cfg(feature = "trace")]
+impl LogCapture {
+    /// Start capturing events of every level on the current thread
+    pub fn start() -> Self {
+        let buffer = LogBuffer::new(DEFAULT_BUFFER_EVENTS);
+        let subscriber = Registry::default().with(buffer.clone());
+        LogCapture {
+            buffer,
+            guard: Some(tracing::subscriber::set_default(subscriber)),
+        }
+    }
+
+    /// Stop capturing and restore the previous subscriber for this thread
+    pub fn stop(&mut self) {
+        self.guard = None;
+    }
+
+    /// Whether events are still being captured
+    pub fn is_active(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    /// All captured records, oldest first
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.buffer.snapshot()
+    }
+
+    /// Captured records matching `query`, oldest first
+    pub fn query(&self, query: &LogQuery) -> Vec<LogRecord> {
+        self.buffer.query(query)
+    }
+
+    /// Number of captured records matching `query`
+    pub fn count(&self, query: &LogQuery) -> usize {
+        self.records().iter().filter(|record| query.matches(record)).count()
+    }
+
+    /// Whether any captured record matches `query`
+    pub fn logged(&self, query: &LogQuery) -> bool {
+        self.records().iter().any(|record| query.matches(record))
+    }
+
+    /// Messages of all captured records, oldest first
+    pub fn messages(&self) -> Vec<String> {
+        self.records().into_iter().map(|record| record.message).collect()
+    }
+
+    /// Forget the records captured so far
+    pub fn clear(&self) {
+        self.buffer.clear();
+    }
+
+    /// Panic unless a captured record matches `query`
+    ///
+    /// The panic message lists every captured record.
+    #[track_caller]
+    pub fn assert_logged(&self, query: &LogQuery) {
+        if !self.logged(query) {
+            panic!("no captured event matches {:?}\n{}", query, self.dump());
+        }
+    }
+
+    /// Panic if any captured record matches `query`
+    #[track_caller]
+    pub fn assert_not_logged(&self, query: &LogQuery) {
+        let found = self.query(query);
+        if !found.is_empty() {
+            panic!(
+                "{} captured event(s) match {:?}\n{}",
+                found.len(),
+                query,
+                self.dump()
+            );
+        }
+    }
+
+    /// Panic unless exactly `expected` captured records match `query`
+    #[track_caller]
+    pub fn assert_count(&self, query: &LogQuery, expected: usize) {
+        let count = self.count(query);
+        if count != expected {
+            panic!(
+                "expected {} captured event(s) matching {:?}, found {}\n{}",
+                expected,
+                query,
+                count,
+                self.dump()
+            );
+        }
+    }
+
+    /// Panic unless an ERROR event whose message contains `text` was captured
+    #[track_caller]
+    pub fn assert_error_logged(&self, text: &str) {
+        self.assert_logged(&LogQuery::new().level(Level::ERROR).contains(text));
+    }
+
+    /// Panic if any event at WARN or above was captured
+    #[track_caller]
+    pub fn assert_no_warnings(&self) {
+        self.assert_not_logged(&LogQuery::new().level(Level::WARN));
+    }
+
+    // Captured records, one per line, for failure messages
+    fn dump(&self) -> String {
+        let records = self.records();
+        if records.is_empty() {
+            return "captured events: none".to_string();
+        }
+        let mut text = format!("captured events ({}):", records.len());
+        for record in records {
+            text.push_str("\n  ");
+            text.push_str(&record.to_string());
+        }
+        text
+    }
+}
+
+#[cfg(feature = "trace")]
+impl std::fmt::Debug for LogCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogCapture")
+            .field("active", &self.is_active())
+            .field("records", &self.buffer.len())
+            .finish()
+    }
+}
+
+/// Run `f` while capturing the events it emits on the current thread
+///
+/// # Returns
+/// * The result of `f` and the stopped capture
+#[cfg(feature = "trace")]
+pub fn capture<R, F: FnOnce() -> R>(f: F) -> (R, LogCapture) {
+    let mut logs = LogCapture::start();
+    let result = f();
+    logs.stop();
+    (result, logs)
+}
+
+#[cfg(test)]
+#[cfg(feature = "trace")]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tracing::{debug, error, info, warn};
+
+    #[test]
+    fn test_capture_records_levels_and_fields() {
+        let (value, logs) = capture(|| {
+            debug!("starting");
+            info!(user = "alice", attempts = 3, "login ok");
+            error!(code = 503, "upstream failed");
+            7
+        });
+        assert_eq!(value, 7);
+        assert!(!logs.is_active());
+        assert_eq!(logs.messages(), vec!["starting", "login ok", "upstream failed"]);
+
+        logs.assert_logged(&LogQuery::new().level(Level::ERROR).field("code"));
+        logs.assert_logged(&LogQuery::new().field_eq("user", "alice").field_eq("attempts", "3"));
+        logs.assert_count(&LogQuery::new().level(Level::INFO), 2);
+        logs.assert_not_logged(&LogQuery::new().level(Level::WARN).contains("login"));
+        logs.assert_error_logged("upstream");
+    }
+
+    #[test]
+    fn test_capture_is_thread_local_and_scoped() {
+        let mut logs = LogCapture::start();
+        thread::spawn(|| error!("other thread")).join().unwrap();
+        warn!("this thread");
+        logs.stop();
+        warn!("after stop");
+
+        assert_eq!(logs.messages(), vec!["this thread"]);
+        logs.clear();
+        assert!(logs.records().is_empty());
+        logs.assert_no_warnings();
+    }
+
+    #[test]
+    fn test_nested_captures() {
+        let (_, outer) = capture(|| {
+            info!("outer");
+            let (_, inner) = capture(|| info!("inner"));
+            assert_eq!(inner.messages(), vec!["inner"]);
+            info!("outer again");
+        });
+        assert_eq!(outer.messages(), vec!["outer", "outer again"]);
+    }
+
+    #[test]
+    fn test_failed_assertion_lists_events() {
+        let (_, logs) = capture(|| info!(id = 1, "only event"));
+        let failure = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            logs.assert_error_logged("missing");
+        }))
+        .unwrap_err();
+        let message = failure.downcast_ref::<String>().unwrap();
+        assert!(message.contains("no captured event matches"));
+        assert!(message.contains("only event"));
+    }
+}
@@ -25,4 +25,16 @@ mod trace_tests {
         let _formatter = LocalTimeFormatter;
         let _dir = get_exe_dir();
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "trace")]
+    fn test_capture_acovo_events() {
+        use acovo::time::ScopedTimer;
+        use acovo::trace::testing::capture;
+        use acovo::trace::LogQuery;
+
+        let (_, logs) = capture(|| drop(ScopedTimer::new("load_batch")));
+        logs.assert_logged(&LogQuery::new().target("acovo::timing").field_eq("name", "load_batch"));
+        logs.assert_no_warnings();
+    }
+}